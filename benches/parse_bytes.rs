@@ -0,0 +1,56 @@
+//! Compares the throughput of `Parser::parse` and of the `Parser::parse_bytes` fast path over a
+//! batch of expressions. Run with `cargo bench --bench parse_bytes`.
+use arithmetic_parser::Parser;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// The number of expressions in the batch
+const BATCH: usize = 10_000;
+/// The number of times the batch is parsed, keeping the fastest run
+const RUNS: usize = 10;
+
+/// Times the fastest of several runs of a parse function over the whole batch
+fn measure(expressions: &[String], parse: impl Fn(&String) -> Option<usize>) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            for expression in expressions {
+                black_box(parse(black_box(expression)));
+            }
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn main() {
+    let expressions: Vec<String> = (0..BATCH)
+        .map(|i| format!("{}a{}ce{}a{}fd{}", i, i % 97, i % 13, i % 7, i % 5 + 1))
+        .collect();
+    for expression in &expressions {
+        let expected = Parser::new(expression.clone()).parse();
+        assert!(expected.is_ok(), "{}", expression);
+        assert_eq!(
+            expected,
+            Parser::parse_bytes(expression.as_bytes()),
+            "{}",
+            expression
+        );
+    }
+    let parse = measure(&expressions, |expression| {
+        Parser::new(expression.clone()).parse().ok()
+    });
+    let parse_bytes = measure(&expressions, |expression| {
+        Parser::parse_bytes(expression.as_bytes()).ok()
+    });
+    let per_expression = |duration: Duration| duration.as_nanos() / BATCH as u128;
+    println!("parse:       {:>8} ns/expression", per_expression(parse));
+    println!(
+        "parse_bytes: {:>8} ns/expression",
+        per_expression(parse_bytes)
+    );
+    println!(
+        "speedup:     {:>8.1}x",
+        parse.as_secs_f64() / parse_bytes.as_secs_f64()
+    );
+}
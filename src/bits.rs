@@ -0,0 +1,165 @@
+/// The widths, in bits, a bit pattern can be printed with
+pub const WIDTHS: [u32; 4] = [8, 16, 32, 64];
+
+/// How the bit pattern of a result is printed, for programmer calculator use
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitFormat {
+    /// Binary digits grouped in nibbles (e.g. `0b0001_0100`)
+    Binary,
+    /// Hexadecimal digits grouped in bytes (e.g. `0x00_14`)
+    Hex,
+}
+
+/// The bit of a value at a position, from 0 for the least significant
+/// # Arguments
+///  - value: The value having the bit
+///  - position: The position of the bit, beyond the width of `usize` for a 0 bit
+/// # Return
+/// The bit, 1 or 0
+pub fn bit(value: usize, position: usize) -> usize {
+    field(value, position, position).unwrap_or(0)
+}
+
+/// The bit field of a value between two positions, included, shifted down to the least
+/// significant bit (e.g. `0b1101` for the bits 7 to 4 of `0xD5`)
+/// # Arguments
+///  - value: The value having the field
+///  - high: The position of the most significant bit of the field
+///  - low: The position of the least significant bit of the field
+/// # Return
+/// The field, `None` if `high` is below `low`
+pub fn field(value: usize, high: usize, low: usize) -> Option<usize> {
+    let width = high.checked_sub(low)? + 1;
+    let shifted = u32::try_from(low)
+        .ok()
+        .and_then(|low| value.checked_shr(low))
+        .unwrap_or(0);
+    let mask = u32::try_from(width)
+        .ok()
+        .and_then(|width| 1usize.checked_shl(width))
+        .map_or(usize::MAX, |bit| bit - 1);
+    Some(shifted & mask)
+}
+
+/// The number of bits set in a value
+/// # Arguments
+///  - value: The value
+/// # Return
+/// The number of 1 bits
+pub fn popcount(value: usize) -> usize {
+    value.count_ones() as usize
+}
+
+/// The value of a bit pattern read as a signed two's-complement integer of a width, the bits
+/// above the width being ignored (e.g. `-2` for `0xFE` in 8 bits)
+/// # Arguments
+///  - pattern: The bit pattern
+///  - width: The number of bits of the integer, from 1 to 64
+/// # Return
+/// The signed value
+pub fn signed(pattern: usize, width: u32) -> i64 {
+    let shift = i64::BITS - width;
+    ((pattern as i64) << shift) >> shift
+}
+
+/// The bit pattern of a signed value as a two's-complement integer of a width, the bits above the
+/// width being dropped (e.g. `0xFE` for `-2` in 8 bits)
+/// # Arguments
+///  - value: The signed value
+///  - width: The number of bits of the integer, from 1 to 64
+/// # Return
+/// The bit pattern
+pub fn from_signed(value: i64, width: u32) -> usize {
+    field(value as usize, width as usize - 1, 0).unwrap_or(0)
+}
+
+/// Render the bit pattern of a value
+/// # Arguments
+///  - value: The value to render
+///  - format: The `BitFormat` of the pattern
+///  - width: The number of bits of the pattern, one of `WIDTHS`, or the narrowest of them holding
+///    the value if `None`
+/// # Return
+/// The bit pattern, `None` if the value does not fit the width
+pub fn pattern(value: usize, format: BitFormat, width: Option<u32>) -> Option<String> {
+    let needed = usize::BITS - value.leading_zeros();
+    let width = match width {
+        Some(width) if width >= needed => width,
+        Some(_) => return None,
+        None => WIDTHS.into_iter().find(|width| *width >= needed)?,
+    };
+    let (prefix, digits, group) = match format {
+        BitFormat::Binary => (
+            "0b",
+            format!("{:0width$b}", value, width = width as usize),
+            4,
+        ),
+        BitFormat::Hex => (
+            "0x",
+            format!("{:0width$X}", value, width = width as usize / 4),
+            2,
+        ),
+    };
+    let groups: Vec<&str> = digits
+        .as_bytes()
+        .chunks(group)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    Some(format!("{}{}", prefix, groups.join("_")))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bits::{bit, field, from_signed, pattern, popcount, signed, BitFormat};
+
+    #[test]
+    fn test_fields() {
+        assert_eq!(1, bit(0b100, 2));
+        assert_eq!(0, bit(0b100, 1));
+        assert_eq!(0, bit(usize::MAX, 64));
+        assert_eq!(Some(0xD), field(0xD5, 7, 4));
+        assert_eq!(Some(0x5), field(0xD5, 3, 0));
+        assert_eq!(Some(usize::MAX), field(usize::MAX, 63, 0));
+        assert_eq!(Some(1), field(usize::MAX, 70, 63));
+        assert_eq!(None, field(0xD5, 3, 4));
+        assert_eq!(5, popcount(0xD5));
+        assert_eq!(64, popcount(usize::MAX));
+    }
+
+    #[test]
+    fn test_signed() {
+        assert_eq!(-2, signed(0xFE, 8));
+        assert_eq!(127, signed(0x7F, 8));
+        assert_eq!(-128, signed(0x180, 8));
+        assert_eq!(-1, signed(usize::MAX, 64));
+        assert_eq!(0xFE, from_signed(-2, 8));
+        assert_eq!(0x8000, from_signed(i16::MIN as i64, 16));
+        assert_eq!(0x80, from_signed(128, 8));
+        for value in [i64::MIN, -1, 0, 1, i64::MAX] {
+            assert_eq!(value, signed(from_signed(value, 64), 64));
+        }
+    }
+
+    #[test]
+    fn test_pattern() {
+        assert_eq!(
+            Some("0b0001_0100".to_string()),
+            pattern(20, BitFormat::Binary, None)
+        );
+        assert_eq!(Some("0x14".to_string()), pattern(20, BitFormat::Hex, None));
+        assert_eq!(
+            Some("0x00_00_01_00".to_string()),
+            pattern(256, BitFormat::Hex, Some(32))
+        );
+        assert_eq!(
+            Some("0b0000_0001_0000_0000".to_string()),
+            pattern(256, BitFormat::Binary, None)
+        );
+        assert_eq!(
+            Some("0xFF_FF_FF_FF_FF_FF_FF_FF".to_string()),
+            pattern(usize::MAX, BitFormat::Hex, None)
+        );
+        assert_eq!(Some("0x00".to_string()), pattern(0, BitFormat::Hex, None));
+        assert_eq!(None, pattern(256, BitFormat::Binary, Some(8)));
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::expr::Expr;
+use crate::options::ParserOptions;
+use crate::parser::ParseError;
+
+/// How often a `ResultCache` spared a parse process
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    /// The number of results found in the cache
+    pub hits: usize,
+    /// The number of results computed, because they were not in the cache or could not be
+    pub misses: usize,
+    /// The number of results in the cache
+    pub entries: usize,
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cache: {} hits, {} misses, {} entries",
+            self.hits, self.misses, self.entries
+        )
+    }
+}
+
+/// A bounded cache of the results of expressions, for batch jobs evaluating many duplicate
+/// formulas. Expressions are keyed by their canonical form, so that `3a2` finds the result of
+/// `2a3`, and the least recently used result is evicted when the cache is full.
+///
+/// Only the expressions written with decimal operands, exactly as `Expr::to_code` renders them,
+/// are cached: the others, e.g. with cell references or leading zeros, may parse differently
+/// depending on the context, so they are always parsed. A cache serves a single set of options,
+/// and none at all if they set limits, which the canonical form could get around.
+#[derive(Debug)]
+pub struct ResultCache {
+    /// The largest number of results held
+    capacity: usize,
+    /// The results, with the time they were last used, by the canonical form of their expressions
+    entries: HashMap<Expr, (usize, u64)>,
+    /// The time, advanced on every lookup
+    clock: u64,
+    /// Whether the options allow caching
+    enabled: bool,
+    /// The hits and misses so far
+    stats: CacheStats,
+}
+
+/// Implementation of the result cache
+impl ResultCache {
+    /// Creates an empty cache
+    /// # Arguments
+    ///  - capacity: The largest number of results held
+    ///  - options: The options of the parse processes whose results are cached
+    pub fn new(capacity: usize, options: &ParserOptions) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+            enabled: options.max_digits.is_none()
+                && options.max_depth.is_none()
+                && options.max_length.is_none()
+                && options.max_operations.is_none(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Evaluate an expression, returning the cached result of an expression having the same
+    /// canonical form if any, parsing it otherwise
+    /// # Arguments
+    ///  - expression: The expression to evaluate
+    ///  - parse: Parses the expression with the options of the cache
+    /// # Return
+    /// The `Result` of the parse process, cached or not
+    pub fn evaluate<F>(&mut self, expression: &str, parse: F) -> Result<usize, ParseError>
+    where
+        F: FnOnce() -> Result<usize, ParseError>,
+    {
+        self.clock += 1;
+        let Some(key) = self.key(expression) else {
+            self.stats.misses += 1;
+            return parse();
+        };
+        if let Some((result, used)) = self.entries.get_mut(&key) {
+            *used = self.clock;
+            self.stats.hits += 1;
+            return Ok(*result);
+        }
+        self.stats.misses += 1;
+        // Errors report positions, which differ between expressions of the same canonical form
+        let result = parse()?;
+        if self.capacity > 0 {
+            if self.entries.len() >= self.capacity {
+                let oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, (_, used))| *used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+            self.entries.insert(key, (result, self.clock));
+        }
+        Ok(result)
+    }
+
+    /// The hits and misses of the cache so far
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            ..self.stats
+        }
+    }
+
+    /// The key of an expression, `None` if its result cannot be cached
+    fn key(&self, expression: &str) -> Option<Expr> {
+        if !self.enabled {
+            return None;
+        }
+        let expr = Expr::parse_tolerant(expression);
+        (expr.errors().is_empty() && expr.to_code() == expression).then(|| expr.canonical())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use crate::cache::{CacheStats, ResultCache};
+    use crate::options::{Limits, ParserOptions};
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_evaluate() {
+        let parses = Cell::new(0);
+        let mut cache = ResultCache::new(2, &ParserOptions::default());
+        let mut evaluate = |expression: &str| {
+            cache.evaluate(expression, || {
+                parses.set(parses.get() + 1);
+                Parser::new(expression.to_string()).parse()
+            })
+        };
+        assert_eq!(Ok(5), evaluate("3a2"));
+        assert_eq!(Ok(5), evaluate("2a3"));
+        assert_eq!(Ok(1), evaluate("3b2"));
+        assert_eq!(Ok(20), evaluate("4ce3a2f"));
+        // The least recently used `3a2` was evicted, while `3b2` was not
+        assert_eq!(Ok(1), evaluate("3b2"));
+        assert_eq!(Ok(5), evaluate("3a2"));
+        // Leading zeros and malformed expressions are never cached
+        assert_eq!(Ok(5), evaluate("03a2"));
+        assert!(evaluate("3aa2").is_err());
+        assert!(evaluate("3aa2").is_err());
+        assert_eq!(7, parses.get());
+        assert_eq!(
+            CacheStats {
+                hits: 2,
+                misses: 7,
+                entries: 2,
+            },
+            cache.stats()
+        );
+        assert_eq!(
+            "cache: 2 hits, 7 misses, 2 entries",
+            cache.stats().to_string()
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let options = ParserOptions::default().with_limits(Limits::untrusted());
+        let mut cache = ResultCache::new(2, &options);
+        assert_eq!(Ok(5), cache.evaluate("3a2", || Ok(5)));
+        assert_eq!(Ok(5), cache.evaluate("3a2", || Ok(5)));
+        assert_eq!(0, cache.stats().hits);
+        assert_eq!(0, cache.stats().entries);
+    }
+}
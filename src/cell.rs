@@ -0,0 +1,39 @@
+use std::collections::HashMap;
+
+/// Resolves spreadsheet-style cell references (e.g. `A1`, `AB12`) to their values
+pub trait CellResolver {
+    /// Resolve the value of a cell
+    /// # Arguments
+    ///  - column: The uppercase column letters (e.g. `A`, `AB`)
+    ///  - row: The row number
+    /// # Return
+    /// An `Option` having the value of the cell if it is defined, `None` otherwise
+    fn resolve(&self, column: &str, row: usize) -> Option<usize>;
+
+    /// Resolve the value of a cell referenced by its column only (e.g. `A`), relative to the
+    /// row the resolver stands for, if it does. None do by default.
+    /// # Arguments
+    ///  - column: The uppercase column letters (e.g. `A`, `AB`)
+    /// # Return
+    /// An `Option` having the value of the cell if it is defined, `None` otherwise
+    fn resolve_relative(&self, _column: &str) -> Option<usize> {
+        None
+    }
+}
+
+/// Cells defined in a map, keyed by their reference (e.g. `A1`)
+impl CellResolver for HashMap<String, usize> {
+    fn resolve(&self, column: &str, row: usize) -> Option<usize> {
+        self.get(&format!("{}{}", column, row)).copied()
+    }
+}
+
+/// Cells computed by a closure receiving column and row
+impl<F> CellResolver for F
+where
+    F: Fn(&str, usize) -> Option<usize>,
+{
+    fn resolve(&self, column: &str, row: usize) -> Option<usize> {
+        self(column, row)
+    }
+}
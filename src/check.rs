@@ -0,0 +1,485 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::diagnostics::{DiagnosticsSink, Level, Notice};
+use crate::history::{History, HistoryError};
+use crate::json;
+
+/// The extension of the formula files found in the directories checked
+const EXTENSION: &str = "expr";
+
+/// How serious a problem of an expression is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    /// The expression fails to evaluate
+    Error,
+    /// The expression evaluates, but is suspicious, e.g. having operands with leading zeros
+    Warning,
+}
+
+/// Implementation of the severities
+impl Severity {
+    /// The name of the severity in the reports
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        }
+    }
+}
+
+/// A problem of an expression, as found when evaluating it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// The position in the expression where the problem was found, from 0, if known
+    pub column: Option<usize>,
+    /// The stable code of the problem (e.g. `E003`), if it has one
+    pub code: Option<&'static str>,
+    /// The description of the problem
+    pub message: String,
+    /// How serious the problem is
+    pub severity: Severity,
+}
+
+/// A problem of an expression of a formula file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// The path of the file
+    pub file: String,
+    /// The number of the line having the expression, from 1
+    pub line: usize,
+    /// The problem, whose column is in the line, from 1
+    pub problem: Problem,
+}
+
+/// Implementation of the findings
+impl Finding {
+    /// Serialize the finding as a JSON object
+    /// # Return
+    /// The JSON text of the finding
+    pub fn to_json(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "null".to_string());
+        format!(
+            "{{\"file\":{},\"line\":{},\"column\":{},\"code\":{},\"message\":{},\"severity\":\"{}\"}}",
+            json::string(&self.file),
+            self.line,
+            optional(self.problem.column.map(|column| column.to_string())),
+            optional(self.problem.code.map(json::string)),
+            json::string(&self.problem.message),
+            self.problem.severity.name()
+        )
+    }
+}
+
+/// The outcome of checking a formula file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileReport {
+    /// The path of the file
+    pub file: String,
+    /// The number of expressions checked, skipping the blank lines and comments
+    pub expressions: usize,
+    /// The problems found, in the order of the lines
+    pub findings: Vec<Finding>,
+}
+
+/// Implementation of the file reports
+impl FileReport {
+    /// The number of problems of a severity
+    /// # Arguments
+    ///  - severity: The `Severity` of the problems counted
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.problem.severity == severity)
+            .count()
+    }
+}
+
+/// Summarizes the report in a line (e.g. `budget.expr: 12 expressions, 1 errors, 0 warnings`)
+impl fmt::Display for FileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} expressions, {} errors, {} warnings",
+            self.file,
+            self.expressions,
+            self.count(Severity::Error),
+            self.count(Severity::Warning)
+        )
+    }
+}
+
+/// The warnings issued by the parsers it is passed to, as diagnostics and notices
+#[derive(Debug, Default)]
+pub struct Warnings(RefCell<Vec<Problem>>);
+
+/// Implementation of the warning collector
+impl Warnings {
+    /// Take the warnings issued so far, those from notices having the column of their span
+    pub fn take(&self) -> Vec<Problem> {
+        self.0.take()
+    }
+
+    /// Collect a warning
+    fn push(&self, column: Option<usize>, message: String) {
+        self.0.borrow_mut().push(Problem {
+            column,
+            code: None,
+            message,
+            severity: Severity::Warning,
+        });
+    }
+}
+
+/// Shared warnings, collecting the warnings of every parser they are passed to
+impl DiagnosticsSink for Rc<Warnings> {
+    fn enabled(&self, level: Level) -> bool {
+        level == Level::Warn
+    }
+
+    fn diagnostic(&self, _level: Level, message: fmt::Arguments) {
+        self.push(None, message.to_string());
+    }
+
+    fn notice(&self, notice: &Notice) {
+        self.push(Some(notice.span().start), notice.to_string());
+    }
+}
+
+/// Check every expression of a formula file, one per line, not stopping at the first problem.
+/// Blank lines and lines starting with `#` are skipped, and an expression can reference the result
+/// of an earlier line by its number (e.g. `$2`), as with `--watch`.
+/// # Arguments
+///  - file: The path of the file, for the findings
+///  - text: The formula file
+///  - evaluate: Evaluates an expression into the result and its warnings, or the error
+/// # Return
+/// The `FileReport` of the file
+pub fn check<F>(file: &str, text: &str, evaluate: F) -> FileReport
+where
+    F: Fn(&str) -> Result<(usize, Vec<Problem>), Problem>,
+{
+    let mut history = History::default();
+    let mut report = FileReport {
+        file: file.to_string(),
+        expressions: 0,
+        findings: Vec::new(),
+    };
+    for (index, line) in text.lines().enumerate() {
+        let expression = line.trim();
+        if expression.is_empty() || expression.starts_with('#') {
+            history.push(None);
+            continue;
+        }
+        report.expressions += 1;
+        let indent = line.chars().take_while(|char| char.is_whitespace()).count();
+        // The columns are in the line as written, so those in the resolved expression are moved
+        // to the references their values come from
+        let resolved = history.resolve_sources(expression).map_err(|err| Problem {
+            column: match err {
+                HistoryError::Malformed(position) => Some(expression[..position].chars().count()),
+                _ => None,
+            },
+            code: None,
+            message: err.to_string(),
+            severity: Severity::Error,
+        });
+        let (evaluated, sources) = match resolved {
+            Ok((resolved, sources)) => (evaluate(&resolved), sources),
+            Err(error) => (Err(error), (0..expression.chars().count()).collect()),
+        };
+        history.push(evaluated.as_ref().ok().map(|(result, _)| *result));
+        let problems = match evaluated {
+            Ok((_, warnings)) => warnings,
+            Err(error) => vec![error],
+        };
+        let source = |column: usize| {
+            sources
+                .get(column)
+                .copied()
+                .unwrap_or(expression.chars().count())
+        };
+        report
+            .findings
+            .extend(problems.into_iter().map(|problem| Finding {
+                file: file.to_string(),
+                line: index + 1,
+                problem: Problem {
+                    column: problem.column.map(|column| indent + source(column) + 1),
+                    ..problem
+                },
+            }));
+    }
+    report
+}
+
+/// The files to check for paths, each either a file, a directory, scanned recursively for the
+/// `.expr` files, or a glob, where `*` and `?` match within a name and `**` any number of
+/// directories (e.g. `formulas/**/*.expr`)
+/// # Arguments
+///  - paths: The paths
+/// # Return
+/// A `Result` having the files, those found in directories and by globs in the order of their
+/// paths and each only once, `io::Error` if a directory cannot be read
+pub fn files(paths: &[String]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        let components: Vec<&str> = path.split('/').collect();
+        let wildcard = components
+            .iter()
+            .position(|component| component.contains(['*', '?']));
+        let Some(wildcard) = wildcard else {
+            match Path::new(path).is_dir() {
+                true => files.extend(
+                    walk(Path::new(path))?
+                        .into_iter()
+                        .filter(|file| file.extension().is_some_and(|e| e == EXTENSION)),
+                ),
+                false => files.push(PathBuf::from(path)),
+            }
+            continue;
+        };
+        let base = match components[..wildcard].join("/") {
+            base if base.is_empty() && path.starts_with('/') => "/".to_string(),
+            base if base.is_empty() => ".".to_string(),
+            base => base,
+        };
+        let pattern = &components[wildcard..];
+        for file in walk(Path::new(&base))? {
+            let Ok(relative) = file.strip_prefix(&base) else {
+                continue;
+            };
+            let relative: Vec<String> = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            let relative: Vec<&str> = relative.iter().map(String::as_str).collect();
+            if matches(pattern, &relative) {
+                files.push(file);
+            }
+        }
+    }
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file.clone()));
+    Ok(files)
+}
+
+/// The files of a directory and of its subdirectories, sorted by path
+fn walk(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut entries: Vec<PathBuf> = fs::read_dir(directory)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+    for entry in entries {
+        match entry.is_dir() {
+            true => files.extend(walk(&entry)?),
+            false => files.push(entry),
+        }
+    }
+    Ok(files)
+}
+
+/// Whether the components of a path match the components of a glob
+fn matches(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (Some(&"**"), _) => {
+            matches(&pattern[1..], path) || (!path.is_empty() && matches(pattern, &path[1..]))
+        }
+        (Some(component), Some(name)) => {
+            let component: Vec<char> = component.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            matches_name(&component, &name) && matches(&pattern[1..], &path[1..])
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Whether a name matches a component of a glob, where `*` matches any characters and `?` one
+fn matches_name(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (Some('*'), _) => {
+            matches_name(&pattern[1..], name)
+                || (!name.is_empty() && matches_name(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => matches_name(&pattern[1..], &name[1..]),
+        (Some(expected), Some(char)) if expected == char => matches_name(&pattern[1..], &name[1..]),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Serialize findings as a JSON array, one finding per line
+/// # Arguments
+///  - findings: The findings
+/// # Return
+/// The JSON text of the findings
+pub fn to_json(findings: &[Finding]) -> String {
+    let findings: Vec<String> = findings
+        .iter()
+        .map(|finding| format!("  {}", finding.to_json()))
+        .collect();
+    format!("[\n{}\n]", findings.join(",\n"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::check::{check, files, matches, to_json, Finding, Problem, Severity, Warnings};
+    use crate::options::{LeadingZeroPolicy, ParserOptions};
+    use crate::parser::Parser;
+
+    fn evaluate(expression: &str) -> Result<(usize, Vec<Problem>), Problem> {
+        let warnings = Rc::new(Warnings::default());
+        let options = ParserOptions {
+            leading_zeros: LeadingZeroPolicy::Warn,
+            max_digits: Some(3),
+            redundant_parentheses: true,
+            ..ParserOptions::default()
+        };
+        let result = Parser::new(expression.to_string())
+            .with_options(options)
+            .with_diagnostics(Rc::clone(&warnings))
+            .parse()
+            .map_err(|err| Problem {
+                column: err.position(),
+                code: Some(err.error_code()),
+                message: format!("{:?}", err),
+                severity: Severity::Error,
+            })?;
+        Ok((result, warnings.take()))
+    }
+
+    #[test]
+    fn test_check() {
+        let text = "# Budget\n3a2\n  3aa2\n$2a07\n\n$3\n  3a1000\n 3ae2f\n$2a5aa1\n3a$";
+        let report = check("budget.expr", text, evaluate);
+        assert_eq!(8, report.expressions);
+        assert_eq!(
+            "budget.expr: 8 expressions, 5 errors, 2 warnings",
+            report.to_string()
+        );
+        let findings = report.findings;
+        let lines: Vec<(usize, Option<usize>, Option<&str>, Severity)> = findings
+            .iter()
+            .map(|finding| {
+                let problem = &finding.problem;
+                (finding.line, problem.column, problem.code, problem.severity)
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (3, Some(5), Some("E003"), Severity::Error),
+                (4, None, None, Severity::Warning),
+                (6, None, None, Severity::Error),
+                (7, Some(5), Some("E012"), Severity::Error),
+                (8, Some(4), None, Severity::Warning),
+                // Columns are in the line as written, not once `$2` is resolved
+                (9, Some(6), Some("E003"), Severity::Error),
+                (10, Some(3), None, Severity::Error),
+            ],
+            lines
+        );
+        assert_eq!("operand 07 has leading zeros", findings[1].problem.message);
+        assert_eq!("Failed(3)", findings[2].problem.message);
+        assert_eq!(
+            "redundant parentheses at 2..5 change nothing",
+            findings[4].problem.message
+        );
+        assert!(check("budget.expr", "3a2\n$1c2", evaluate)
+            .findings
+            .is_empty());
+    }
+
+    #[test]
+    fn test_files() {
+        let root = std::env::temp_dir().join(format!("check-{}", std::process::id()));
+        fs::create_dir_all(root.join("taxes/2024")).unwrap();
+        for file in [
+            "budget.expr",
+            "notes.txt",
+            "taxes/vat.expr",
+            "taxes/2024/q1.expr",
+        ] {
+            fs::write(root.join(file), "3a2").unwrap();
+        }
+        let path = |file: &str| root.join(file);
+        let root_text = root.to_string_lossy().to_string();
+        assert_eq!(
+            vec![
+                path("budget.expr"),
+                path("taxes/2024/q1.expr"),
+                path("taxes/vat.expr"),
+                path("notes.txt"),
+            ],
+            files(&[root_text.clone(), format!("{}/notes.txt", root_text)]).unwrap()
+        );
+        assert_eq!(
+            vec![path("taxes/2024/q1.expr")],
+            files(&[format!("{}/taxes/*/q?.expr", root_text)]).unwrap()
+        );
+        assert_eq!(
+            vec![path("taxes/2024/q1.expr"), path("taxes/vat.expr")],
+            files(&[
+                format!("{}/taxes/**/*.expr", root_text),
+                format!("{}/taxes", root_text)
+            ])
+            .unwrap()
+        );
+        assert_eq!(
+            Vec::<PathBuf>::new(),
+            files(&[format!("{}/**/*.csv", root_text)]).unwrap()
+        );
+        fs::remove_dir_all(&root).unwrap();
+        assert!(files(&[format!("{}/*.expr", root_text)]).is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches(&["**", "*.expr"], &["a", "b", "c.expr"]));
+        assert!(matches(&["**", "*.expr"], &["c.expr"]));
+        assert!(matches(&["a", "**"], &["a"]));
+        assert!(matches(&["q?.expr"], &["q1.expr"]));
+        assert!(!matches(&["q?.expr"], &["q10.expr"]));
+        assert!(!matches(&["*.expr"], &["a", "c.expr"]));
+        assert!(matches(&["*"], &["è"]));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let finding = Finding {
+            file: "a \"b\".expr".to_string(),
+            line: 3,
+            problem: Problem {
+                column: Some(4),
+                code: Some("E003"),
+                message: "malformed".to_string(),
+                severity: Severity::Error,
+            },
+        };
+        let warning = Finding {
+            problem: Problem {
+                column: None,
+                code: None,
+                severity: Severity::Warning,
+                ..finding.problem.clone()
+            },
+            ..finding.clone()
+        };
+        assert_eq!(
+            "[\n  {\"file\":\"a \\\"b\\\".expr\",\"line\":3,\"column\":4,\"code\":\"E003\",\"message\":\"malformed\",\"severity\":\"error\"},\n  {\"file\":\"a \\\"b\\\".expr\",\"line\":3,\"column\":null,\"code\":null,\"message\":\"malformed\",\"severity\":\"warning\"}\n]",
+            to_json(&[finding, warning])
+        );
+        assert_eq!("[\n\n]", to_json(&[]));
+    }
+}
@@ -0,0 +1,419 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::ApplicationError;
+use arithmetic_parser::bits::{BitFormat, WIDTHS};
+use arithmetic_parser::division::DivisionFormat;
+use arithmetic_parser::notation::Notation;
+use arithmetic_parser::options::{
+    LeadingZeroPolicy, Limits, ParserOptions, RoundingMode, TrailingInputPolicy,
+};
+
+/// Description of the options accepted on the command line
+pub const OPTIONS_HELP: &str = "Options:
+  --join                              Join all the arguments into a single expression
+  --chain                             Start every expression from the result of the previous one
+  --defs <file>                       Load named formulas, callable in expressions as @name[arguments]
+  --seed <number>                     Draw the same random numbers with rand(low, high) on every run
+  --watch <file>                      Evaluate the expressions of a file, one per line, whenever it changes
+  --fix                               Repair whitespace, repeated operations and unclosed parentheses
+  --quiet                             Do not print the result, only report success in the exit status
+  --exit-result                       Do not print the result, exit with it as status (0 to 254)
+  -v, -vv                             Print debug, or trace, messages of the parse process
+  --leading-zeros accept|warn|reject  How operands with leading zeros are treated
+  --warn-parentheses                  Warn about the parentheses which change nothing, e.g. 3ae2f
+  --case-insensitive                  Accept uppercase operation codes, e.g. 3A2
+  --roman-input                       Accept uppercase Roman numerals as operands
+  --roman-output                      Print the result as a Roman numeral
+  --division remainder|fraction       Print what the last division leaves, e.g. 3 r 1 or 3 1/2
+  --notation grouped|sci|eng          Print the result as 1,234,567, 1.234567e6 or 12.345e3
+  --round <places>                    Print the exact result with decimal places, e.g. 3.50 for 7d2
+  --bits bin|hex                      Also print the bit pattern of the result, e.g. 0b0001_0100
+  --width 8|16|32|64                  The bits of the pattern, the fewest holding the result by default
+  --rounding <mode>                   Round quotients: trunc (default), floor, ceil, round or euclid
+  --radix <base>                      Read the operands in the given base, from 2 to 36
+  --trailing strict|lenient           Reject, or ignore, the input following a complete expression
+  --wrapping                          On overflow, also print the result wrapping around, for teaching
+  --signed 8|16|32|64                 Compute with signed integers of the given bits, e.g. -2 for 3b5
+  --untrusted                         Limit digits, depth, length and operations for untrusted input, the ones below override
+  --max-digits <count>                Reject operands having more digits than the given count
+  --max-depth <count>                 Reject expressions nesting parentheses deeper than the given count
+  --max-length <count>                Reject expressions having more characters than the given count
+  --max-operations <count>            Reject expressions applying more operations than the given count
+  --cell <reference>=<value>          Define a cell usable as operand (e.g. --cell A1=3)
+  --events <file>                     Write the events of the parse process to a file, as JSON
+  --record <file>                     Record the parse process to a file, for `replay`
+  --stats                             Print statistics about the parse process on the standard error
+  --teach                             Narrate every step of the parse process in plain language
+  --time                              Print the time spent in each phase of the parse process
+  --column <name>                     (csv) The column holding the expressions
+  --result-column <name>              (csv) The name of the appended column, `result` by default
+  --cache <capacity>                  (csv) Reuse the results of up to <capacity> duplicate expressions
+  --from <pattern>                    (rewrite) The subtrees to replace, with _ matching any, e.g. \"_ c 1\"
+  --to <template>                     (rewrite) What replaces them, its _ taking the matched subtrees in order";
+
+/// The subcommands of the application
+#[derive(Debug, Default, PartialEq)]
+pub enum Command {
+    /// Evaluate the expression passed as argument
+    #[default]
+    Evaluate,
+    /// Evaluate a column of the CSV read from the standard input
+    Csv,
+    /// Expand the `{{ expression }}` placeholders of the text read from the standard input
+    Render,
+    /// Parse again the recording passed as argument, checking that it reproduces
+    Replay,
+    /// Print the grammar of the expressions accepted with the options passed
+    Grammar,
+    /// Check the expressions of the files, directories or globs passed as arguments, reporting
+    /// every problem as JSON
+    Check,
+    /// Rewrite the expressions of the files, directories or globs passed as arguments, or of the
+    /// standard input, replacing the subtrees matching a pattern
+    Rewrite,
+    /// Print the tokens which may follow the partial expression passed as argument, or read from
+    /// the standard input, one per line, for shell completion scripts. Hidden from the usage.
+    Complete,
+}
+
+/// The arguments accepted on the command line
+#[derive(Debug)]
+pub struct Arguments {
+    /// The subcommand to execute
+    pub command: Command,
+    /// The positional arguments, holding the expression to evaluate
+    pub expressions: Vec<String>,
+    /// Whether the positional arguments are joined into a single expression
+    pub join: bool,
+    /// Whether every expression starts from the result of the previous one
+    pub chain: bool,
+    /// The file defining the named formulas
+    pub defs: Option<PathBuf>,
+    /// The seed of the random numbers, different on every run if `None`
+    pub seed: Option<u64>,
+    /// The formula file evaluated whenever it changes
+    pub watch: Option<PathBuf>,
+    /// Whether the safe repairs are applied to the expression before evaluating it
+    pub fix: bool,
+    /// Whether printing the result is suppressed
+    pub quiet: bool,
+    /// Whether the result is the exit status
+    pub exit_result: bool,
+    /// How detailed the messages of the parse process are, from 0 (errors only) to 2 (trace)
+    pub verbosity: u8,
+    /// The options tuning the parser
+    pub options: ParserOptions,
+    /// Whether the result is printed as a Roman numeral
+    pub roman_output: bool,
+    /// How the remainder of the last division is printed
+    pub division: DivisionFormat,
+    /// How the result is written, as plain digits if `None`
+    pub notation: Option<Notation>,
+    /// The number of decimal places the exact result is printed with, if it is
+    pub round: Option<usize>,
+    /// How the bit pattern of the result is printed, if it is
+    pub bits: Option<BitFormat>,
+    /// The number of bits of the pattern, the fewest holding the result if `None`
+    pub width: Option<u32>,
+    /// The cells defined by reference
+    pub cells: HashMap<String, usize>,
+    /// Whether overflows are explained with the result wrapping around
+    pub wrapping: bool,
+    /// The file where the parse events are written as JSON
+    pub events: Option<PathBuf>,
+    /// The file where the parse process is recorded
+    pub record: Option<PathBuf>,
+    /// Whether the statistics of the parse process are printed
+    pub stats: bool,
+    /// Whether the parse process is narrated step by step
+    pub teach: bool,
+    /// Whether the durations of the parse phases are printed
+    pub time: bool,
+    /// The CSV column holding the expressions
+    pub column: Option<String>,
+    /// The name of the CSV column holding the results
+    pub result_column: String,
+    /// The number of CSV results reused for duplicate expressions, if they are
+    pub cache: Option<usize>,
+    /// The pattern of the subtrees to rewrite
+    pub from: Option<String>,
+    /// The template replacing the rewritten subtrees
+    pub to: Option<String>,
+}
+
+impl Default for Arguments {
+    fn default() -> Self {
+        Self {
+            command: Command::default(),
+            expressions: Vec::new(),
+            join: false,
+            chain: false,
+            defs: None,
+            seed: None,
+            watch: None,
+            fix: false,
+            quiet: false,
+            exit_result: false,
+            verbosity: 0,
+            options: ParserOptions::default(),
+            roman_output: false,
+            bits: None,
+            width: None,
+            division: DivisionFormat::default(),
+            notation: None,
+            round: None,
+            cells: HashMap::new(),
+            wrapping: false,
+            events: None,
+            record: None,
+            stats: false,
+            teach: false,
+            time: false,
+            column: None,
+            result_column: "result".to_string(),
+            cache: None,
+            from: None,
+            to: None,
+        }
+    }
+}
+
+/// The command line parsing implementation
+impl Arguments {
+    /// Parse the command line arguments, excluding the binary path
+    /// # Arguments
+    ///  - args: The arguments passed to the program
+    /// # Return
+    /// A `Result` having the `Arguments` if valid, `ApplicationError` otherwise
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, ApplicationError> {
+        let mut arguments = Self::default();
+        let mut args = args.peekable();
+        let (mut untrusted, mut limits) = (false, Limits::unlimited());
+        if let Some(command) = args.next_if(|arg| {
+            matches!(
+                arg.as_str(),
+                "csv" | "render" | "replay" | "grammar" | "check" | "rewrite" | "__complete"
+            )
+        }) {
+            arguments.command = match command.as_str() {
+                "csv" => Command::Csv,
+                "render" => Command::Render,
+                "replay" => Command::Replay,
+                "grammar" => Command::Grammar,
+                "check" => Command::Check,
+                "rewrite" => Command::Rewrite,
+                _ => Command::Complete,
+            };
+        }
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--join" => arguments.join = true,
+                "--chain" => arguments.chain = true,
+                "--defs" => arguments.defs = Some(Self::value(&arg, args.next())?.into()),
+                "--seed" => {
+                    let value = Self::value(&arg, args.next())?;
+                    let seed = value
+                        .parse::<u64>()
+                        .map_err(|_| ApplicationError::InvalidArgument(arg, value))?;
+                    arguments.seed = Some(seed);
+                }
+                "--watch" => arguments.watch = Some(Self::value(&arg, args.next())?.into()),
+                "--fix" => arguments.fix = true,
+                "--quiet" => arguments.quiet = true,
+                "--exit-result" => arguments.exit_result = true,
+                "-v" | "--verbose" => arguments.verbosity += 1,
+                "-vv" => arguments.verbosity += 2,
+                "--leading-zeros" => {
+                    arguments.options.leading_zeros = match Self::value(&arg, args.next())?.as_str()
+                    {
+                        "accept" => LeadingZeroPolicy::Accept,
+                        "warn" => LeadingZeroPolicy::Warn,
+                        "reject" => LeadingZeroPolicy::Reject,
+                        value => {
+                            return Err(ApplicationError::InvalidArgument(arg, value.to_string()))
+                        }
+                    }
+                }
+                "--warn-parentheses" => arguments.options.redundant_parentheses = true,
+                "--case-insensitive" => arguments.options.case_insensitive_opcodes = true,
+                "--roman-input" => arguments.options.roman_numerals = true,
+                "--roman-output" => arguments.roman_output = true,
+                "--division" => {
+                    arguments.division = match Self::value(&arg, args.next())?.as_str() {
+                        "truncate" => DivisionFormat::Truncate,
+                        "remainder" => DivisionFormat::Remainder,
+                        "fraction" => DivisionFormat::Fraction,
+                        value => {
+                            return Err(ApplicationError::InvalidArgument(arg, value.to_string()))
+                        }
+                    }
+                }
+                "--notation" => {
+                    arguments.notation = match Self::value(&arg, args.next())?.as_str() {
+                        "grouped" => Some(Notation::Grouped),
+                        "sci" => Some(Notation::Scientific),
+                        "eng" => Some(Notation::Engineering),
+                        value => {
+                            return Err(ApplicationError::InvalidArgument(arg, value.to_string()))
+                        }
+                    }
+                }
+                "--round" => arguments.round = Some(Self::count(arg, args.next())?),
+                "--bits" => {
+                    arguments.bits = match Self::value(&arg, args.next())?.as_str() {
+                        "bin" => Some(BitFormat::Binary),
+                        "hex" => Some(BitFormat::Hex),
+                        value => {
+                            return Err(ApplicationError::InvalidArgument(arg, value.to_string()))
+                        }
+                    }
+                }
+                "--width" => {
+                    let value = Self::value(&arg, args.next())?;
+                    match value.parse::<u32>() {
+                        Ok(width) if WIDTHS.contains(&width) => arguments.width = Some(width),
+                        _ => return Err(ApplicationError::InvalidArgument(arg, value)),
+                    }
+                }
+                "--radix" => {
+                    let value = Self::value(&arg, args.next())?;
+                    arguments.options.radix = value
+                        .parse::<u32>()
+                        .map_err(|_| ApplicationError::InvalidArgument(arg, value))?;
+                }
+                "--rounding" => {
+                    arguments.options.rounding = match Self::value(&arg, args.next())?.as_str() {
+                        "trunc" => RoundingMode::Trunc,
+                        "floor" => RoundingMode::Floor,
+                        "ceil" => RoundingMode::Ceil,
+                        "round" => RoundingMode::Round,
+                        "euclid" => RoundingMode::Euclid,
+                        value => {
+                            return Err(ApplicationError::InvalidArgument(arg, value.to_string()))
+                        }
+                    }
+                }
+                "--trailing" => {
+                    arguments.options.trailing_input = match Self::value(&arg, args.next())?
+                        .as_str()
+                    {
+                        "strict" => TrailingInputPolicy::Strict,
+                        "lenient" => TrailingInputPolicy::Lenient,
+                        value => {
+                            return Err(ApplicationError::InvalidArgument(arg, value.to_string()))
+                        }
+                    }
+                }
+                "--wrapping" => arguments.wrapping = true,
+                "--signed" => {
+                    let value = Self::value(&arg, args.next())?;
+                    match value.parse::<u32>() {
+                        Ok(width) if WIDTHS.contains(&width) => {
+                            arguments.options.signed_width = Some(width)
+                        }
+                        _ => return Err(ApplicationError::InvalidArgument(arg, value)),
+                    }
+                }
+                "--untrusted" => untrusted = true,
+                "--max-digits" => limits.max_digits = Some(Self::count(arg, args.next())?),
+                "--max-depth" => limits.max_depth = Some(Self::count(arg, args.next())?),
+                "--max-length" => limits.max_length = Some(Self::count(arg, args.next())?),
+                "--max-operations" => limits.max_operations = Some(Self::count(arg, args.next())?),
+                "--cell" => {
+                    let definition = Self::value(&arg, args.next())?;
+                    let cell = definition
+                        .split_once('=')
+                        .and_then(|(reference, value)| {
+                            Some((reference.to_string(), value.parse::<usize>().ok()?))
+                        })
+                        .ok_or(ApplicationError::InvalidArgument(arg, definition.clone()))?;
+                    arguments.cells.insert(cell.0, cell.1);
+                }
+                "--events" => arguments.events = Some(Self::value(&arg, args.next())?.into()),
+                "--record" => arguments.record = Some(Self::value(&arg, args.next())?.into()),
+                "--stats" => arguments.stats = true,
+                "--teach" => arguments.teach = true,
+                "--time" => arguments.time = true,
+                "--column" => arguments.column = Some(Self::value(&arg, args.next())?),
+                "--result-column" => arguments.result_column = Self::value(&arg, args.next())?,
+                "--cache" => arguments.cache = Some(Self::count(arg, args.next())?),
+                "--from" => arguments.from = Some(Self::value(&arg, args.next())?),
+                "--to" => arguments.to = Some(Self::value(&arg, args.next())?),
+                _ => arguments.expressions.push(arg),
+            }
+        }
+        // The preset applies first, whatever its position, so that explicit limits override it
+        let preset = if untrusted {
+            Limits::untrusted()
+        } else {
+            Limits::unlimited()
+        };
+        arguments.options = arguments.options.with_limits(Limits {
+            max_digits: limits.max_digits.or(preset.max_digits),
+            max_depth: limits.max_depth.or(preset.max_depth),
+            max_length: limits.max_length.or(preset.max_length),
+            max_operations: limits.max_operations.or(preset.max_operations),
+        });
+        Ok(arguments)
+    }
+
+    /// Extract the value following an option, failing if it is missing
+    fn value(option: &str, value: Option<String>) -> Result<String, ApplicationError> {
+        value.ok_or_else(|| ApplicationError::InvalidArgument(option.to_string(), String::new()))
+    }
+
+    /// Extract the count following an option, failing if it is missing or not a number
+    fn count(option: String, value: Option<String>) -> Result<usize, ApplicationError> {
+        let value = Self::value(&option, value)?;
+        value
+            .parse::<usize>()
+            .map_err(|_| ApplicationError::InvalidArgument(option, value))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Arguments {
+        Arguments::parse(args.iter().map(|arg| arg.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_limits() {
+        let untrusted = Limits::untrusted();
+        for args in [
+            ["--max-depth", "5", "--untrusted"],
+            ["--untrusted", "--max-depth", "5"],
+        ] {
+            let options = parse(&args).options;
+            assert_eq!(Some(5), options.max_depth);
+            assert_eq!(untrusted.max_digits, options.max_digits);
+            assert_eq!(untrusted.max_length, options.max_length);
+            assert_eq!(untrusted.max_operations, options.max_operations);
+        }
+        let options = parse(&["--max-length", "10"]).options;
+        assert_eq!(
+            (None, None, Some(10)),
+            (options.max_digits, options.max_depth, options.max_length)
+        );
+        let options = parse(&["--untrusted", "--max-operations", "3"]).options;
+        assert_eq!(untrusted.max_depth, options.max_depth);
+        assert_eq!(Some(3), options.max_operations);
+    }
+
+    #[test]
+    fn test_signed() {
+        assert_eq!(
+            Some(16),
+            parse(&["--signed", "16", "3b5"]).options.signed_width
+        );
+        assert_eq!(None, parse(&["3b5"]).options.signed_width);
+        let error = Arguments::parse(["--signed", "12"].iter().map(|arg| arg.to_string())).err();
+        let Some(ApplicationError::InvalidArgument(option, value)) = error else {
+            panic!("the width 12 is accepted");
+        };
+        assert_eq!(("--signed", "12"), (option.as_str(), value.as_str()));
+    }
+}
@@ -0,0 +1,184 @@
+use crate::operation::{codes::*, Operation};
+use crate::parser::MAX_RECURSION;
+
+/// Errors that the compile-time evaluation can cause
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstEvalError {
+    /// The expression is empty
+    Empty,
+    /// A byte which is not valid at its position was found (position)
+    UnexpectedByte(usize),
+    /// The expression ended while an operand or a closing parenthesis was expected
+    UnexpectedEnd,
+    /// An operand does not fit in an unsigned integer (position of its first digit)
+    OperandTooLarge(usize),
+    /// The application of an operation overflows (position of the operation code)
+    Overflow(usize),
+    /// The parenthesis nesting is deeper than `MAX_RECURSION` (position of the parenthesis)
+    TooDeep(usize),
+}
+
+/// Evaluates a literal expression at compile time, yielding a `usize` constant.
+/// The compilation fails if the expression is not valid.
+/// ```
+/// # use arithmetic_parser::arith;
+/// const AREA: usize = arith!("3a2c4");
+/// assert_eq!(20, AREA);
+/// ```
+#[macro_export]
+macro_rules! arith {
+    ($expression:literal) => {{
+        const RESULT: usize = match $crate::constant::eval_const($expression) {
+            Ok(result) => result,
+            Err(_) => panic!(concat!("invalid arithmetic expression: ", $expression)),
+        };
+        RESULT
+    }};
+}
+
+/// Evaluates a literal expression in a `const` context, so that it can size arrays or
+/// initialize statics. Only decimal operands, operation codes and parentheses are accepted,
+/// and operations are applied from left to right, as the `Parser` does.
+/// ```
+/// # use arithmetic_parser::constant::eval_const;
+/// const SIZE: usize = match eval_const("2ce3a5f") {
+///     Ok(size) => size,
+///     Err(_) => panic!("invalid buffer size"),
+/// };
+/// static BUFFER: [u8; SIZE] = [0; 16];
+/// ```
+/// # Arguments
+///  - expression: The expression to evaluate
+/// # Return
+/// A `Result` having the expression result if valid, `ConstEvalError` otherwise
+pub const fn eval_const(expression: &str) -> Result<usize, ConstEvalError> {
+    eval_bytes(expression.as_bytes())
+}
+
+/// Evaluates an expression given as ASCII bytes, in a `const` context
+/// # Arguments
+///  - bytes: The expression to evaluate
+/// # Return
+/// A `Result` having the expression result if valid, `ConstEvalError` otherwise
+pub const fn eval_bytes(bytes: &[u8]) -> Result<usize, ConstEvalError> {
+    if bytes.is_empty() {
+        return Err(ConstEvalError::Empty);
+    }
+    match expression(bytes, 0, 0) {
+        Ok((result, end)) if end == bytes.len() => Ok(result),
+        Ok((_, end)) => Err(ConstEvalError::UnexpectedByte(end)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Evaluates a sequence of operations starting at a position, up to the end of the bytes or
+/// a closing parenthesis, at a parenthesis nesting depth
+/// # Return
+/// A `Result` having the value and the position where the evaluation stopped, `ConstEvalError` otherwise
+const fn expression(
+    bytes: &[u8],
+    index: usize,
+    depth: usize,
+) -> Result<(usize, usize), ConstEvalError> {
+    let (mut result, mut index) = match operand(bytes, index, depth) {
+        Ok(operand) => operand,
+        Err(err) => return Err(err),
+    };
+    while index < bytes.len() && bytes[index] != OPCODE_CLOSE as u8 {
+        let operation = match Operation::from_code(bytes[index] as char, result) {
+            Some(operation) => operation,
+            None => return Err(ConstEvalError::UnexpectedByte(index)),
+        };
+        let (second_operand, next) = match operand(bytes, index + 1, depth) {
+            Ok(operand) => operand,
+            Err(err) => return Err(err),
+        };
+        result = match operation.checked_apply(second_operand) {
+            Some(result) => result,
+            None => return Err(ConstEvalError::Overflow(index)),
+        };
+        index = next;
+    }
+    Ok((result, index))
+}
+
+/// Evaluates a decimal operand or a parenthesized expression starting at a position, at a
+/// parenthesis nesting depth
+/// # Return
+/// A `Result` having the value and the position following the operand, `ConstEvalError` otherwise
+const fn operand(
+    bytes: &[u8],
+    index: usize,
+    depth: usize,
+) -> Result<(usize, usize), ConstEvalError> {
+    if index >= bytes.len() {
+        return Err(ConstEvalError::UnexpectedEnd);
+    }
+    if bytes[index] == OPCODE_OPEN as u8 {
+        if depth >= MAX_RECURSION {
+            return Err(ConstEvalError::TooDeep(index));
+        }
+        return match expression(bytes, index + 1, depth + 1) {
+            Ok((_, end)) if end >= bytes.len() => Err(ConstEvalError::UnexpectedEnd),
+            Ok((result, end)) => Ok((result, end + 1)),
+            Err(err) => Err(err),
+        };
+    }
+    let start = index;
+    let mut index = index;
+    let mut value: usize = 0;
+    while index < bytes.len() && bytes[index].is_ascii_digit() {
+        let digit = (bytes[index] - b'0') as usize;
+        value = match value.checked_mul(10) {
+            Some(value) => match value.checked_add(digit) {
+                Some(value) => value,
+                None => return Err(ConstEvalError::OperandTooLarge(start)),
+            },
+            None => return Err(ConstEvalError::OperandTooLarge(start)),
+        };
+        index += 1;
+    }
+    if index == start {
+        return Err(ConstEvalError::UnexpectedByte(index));
+    }
+    Ok((value, index))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::constant::{eval_bytes, eval_const, ConstEvalError};
+
+    #[test]
+    fn test_arith() {
+        const RESULT: usize = arith!("3c4d2aee2a4c41fc4f");
+        assert_eq!(990, RESULT);
+        assert_eq!(235, arith!("3ae4c66fb32"));
+    }
+
+    #[test]
+    fn test_eval_const() {
+        const SIZE: usize = match eval_const("2ce3a5f") {
+            Ok(size) => size,
+            Err(_) => 0,
+        };
+        let buffer = [0u8; SIZE];
+        assert_eq!(16, buffer.len());
+        assert_eq!(Ok(17), eval_const("32a2d2"));
+    }
+
+    #[test]
+    fn test_eval_bytes() {
+        assert_eq!(Ok(20), eval_bytes(b"3a2c4"));
+        assert_eq!(Ok(659), eval_bytes(b"233b3ae4c66fb99ae33ce3a5ff"));
+        assert_eq!(Err(ConstEvalError::Empty), eval_bytes(b""));
+        assert_eq!(Err(ConstEvalError::UnexpectedByte(2)), eval_bytes(b"3aa2"));
+        assert_eq!(Err(ConstEvalError::UnexpectedEnd), eval_bytes(b"3ae2"));
+        assert_eq!(Err(ConstEvalError::UnexpectedByte(3)), eval_bytes(b"3a2f"));
+        assert_eq!(Err(ConstEvalError::UnexpectedByte(1)), eval_bytes(b"3x"));
+        assert_eq!(Err(ConstEvalError::Overflow(1)), eval_bytes(b"3d0"));
+        assert_eq!(
+            Err(ConstEvalError::OperandTooLarge(0)),
+            eval_bytes(b"99999999999999999999999")
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+
+use crate::cache::ResultCache;
+use crate::cell::CellResolver;
+use crate::options::ParserOptions;
+use crate::parser::{ParseError, Parser};
+use crate::telemetry::Timings;
+
+/// The separator between fields of a record
+const SEPARATOR: char = ',';
+/// The character enclosing fields that contain separators, quotes or line breaks
+const QUOTE: char = '"';
+
+/// Errors that the CSV processing can cause
+#[derive(Debug)]
+pub enum CsvError {
+    /// Reading the input or writing the output failed
+    Io(io::Error),
+    /// The input has no header record
+    MissingHeader,
+    /// The header has no column with the given name (column name)
+    MissingColumn(String),
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Io({})", err),
+            Self::MissingHeader => write!(f, "MissingHeader"),
+            Self::MissingColumn(column) => write!(f, "MissingColumn({:?})", column),
+        }
+    }
+}
+
+/// The cells of a CSV table, addressed spreadsheet-style: columns are lettered from `A`
+/// and rows are numbered from 1, the header being the first row. A column without row
+/// refers to the row being evaluated.
+struct Table {
+    /// The records of the table, header included
+    records: Rc<Vec<Vec<String>>>,
+    /// The number of the row being evaluated
+    row: usize,
+}
+
+impl CellResolver for Table {
+    fn resolve_relative(&self, column: &str) -> Option<usize> {
+        self.resolve(column, self.row)
+    }
+
+    fn resolve(&self, column: &str, row: usize) -> Option<usize> {
+        let column = column
+            .bytes()
+            .try_fold(0usize, |index, letter| {
+                index
+                    .checked_mul(26)?
+                    .checked_add((letter - b'A') as usize + 1)
+            })?
+            .checked_sub(1)?;
+        self.records
+            .get(row.checked_sub(1)?)?
+            .get(column)?
+            .trim()
+            .parse::<usize>()
+            .ok()
+    }
+}
+
+/// Evaluate a column of expressions for every record of a CSV and append the results as a new column.
+/// Expressions can reference the other fields as cells (e.g. `A2` is the first field of the first record
+/// after the header), or by column only for the field of their own record (e.g. `AcB`). Records whose expression fails to evaluate get an empty result, and the error
+/// is returned along with the number of their row.
+/// # Arguments
+///  - input: The CSV to read, starting with a header record
+///  - output: Where to write the CSV having the additional result column
+///  - columns: The names of the column holding the expressions and of the column holding the
+///    results
+///  - options: The options tuning the parser
+///  - render: Renders a result as text
+///  - timings: Where to accumulate the durations of the parse processes, if profiled
+///  - cache: Where to look up the results of duplicate expressions, if cached
+/// # Return
+/// A `Result` having the row numbers and errors of the records that failed to evaluate if the CSV
+/// was processed, `CsvError` otherwise
+pub fn process(
+    mut input: impl Read,
+    mut output: impl Write,
+    (column, result_column): (&str, &str),
+    options: &ParserOptions,
+    render: impl Fn(usize) -> Option<String>,
+    timings: Option<&Rc<Timings>>,
+    mut cache: Option<&mut ResultCache>,
+) -> Result<Vec<(usize, ParseError)>, CsvError> {
+    let mut text = String::new();
+    input.read_to_string(&mut text).map_err(CsvError::Io)?;
+    let records = Rc::new(read_records(&text));
+    let header = records.first().ok_or(CsvError::MissingHeader)?;
+    let index = header
+        .iter()
+        .position(|name| name == column)
+        .ok_or(CsvError::MissingColumn(column.to_string()))?;
+
+    let mut header = header.clone();
+    header.push(result_column.to_string());
+    write_record(&mut output, &header).map_err(CsvError::Io)?;
+    let mut errors = Vec::new();
+    for (row, record) in records.iter().enumerate().skip(1) {
+        let expression = record.get(index).cloned().unwrap_or_default();
+        let mut parser = Parser::new(expression.clone())
+            .with_options(options.clone())
+            .with_cell_resolver(Table {
+                records: Rc::clone(&records),
+                row: row + 1,
+            });
+        if let Some(timings) = timings {
+            parser = parser.with_telemetry(Rc::clone(timings));
+        }
+        let result = match &mut cache {
+            Some(cache) => cache.evaluate(&expression, || parser.parse()),
+            None => parser.parse(),
+        };
+        let result = match result {
+            Ok(result) => render(result).unwrap_or_default(),
+            Err(err) => {
+                errors.push((row + 1, err));
+                String::new()
+            }
+        };
+        let mut record = record.clone();
+        record.push(result);
+        write_record(&mut output, &record).map_err(CsvError::Io)?;
+    }
+    Ok(errors)
+}
+
+/// Split a CSV text into records of fields, honouring quoted fields
+fn read_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut data = text.chars().peekable();
+    while let Some(char) = data.next() {
+        match char {
+            QUOTE if quoted && data.peek() == Some(&QUOTE) => {
+                data.next();
+                field.push(QUOTE);
+            }
+            QUOTE => quoted = !quoted,
+            SEPARATOR if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted && data.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            char => field.push(char),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+/// Write a record, quoting the fields where needed
+fn write_record(output: &mut impl Write, record: &[String]) -> io::Result<()> {
+    let fields: Vec<String> = record
+        .iter()
+        .map(|field| {
+            if field.contains([SEPARATOR, QUOTE, '\n', '\r']) {
+                format!("{}{}{}", QUOTE, field.replace(QUOTE, "\"\""), QUOTE)
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+    writeln!(output, "{}", fields.join(&SEPARATOR.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cache::ResultCache;
+    use crate::csv::{process, read_records, CsvError};
+    use crate::options::ParserOptions;
+    use crate::parser::ParseError;
+
+    #[test]
+    fn test_read_records() {
+        let records = read_records("a,\"b,\"\"c\"\"\"\r\n1,2\n");
+        assert_eq!(
+            vec![
+                vec!["a".to_string(), "b,\"c\"".to_string()],
+                vec!["1".to_string(), "2".to_string()]
+            ],
+            records
+        );
+    }
+
+    #[test]
+    fn test_process() {
+        let input = "price,quantity,total\n3,4,A2cB2\n5,2,A3cB3a1\n1,1,aa\n";
+        let mut output = Vec::new();
+        let errors = process(
+            input.as_bytes(),
+            &mut output,
+            ("total", "result"),
+            &ParserOptions::default(),
+            |result| Some(result.to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(matches!(
+            errors.as_slice(),
+            [(4, ParseError::IllegalState { .. })]
+        ));
+        assert_eq!(
+            "price,quantity,total,result\n3,4,A2cB2,12\n5,2,A3cB3a1,11\n1,1,aa,\n",
+            String::from_utf8(output).unwrap()
+        );
+
+        let result = process(
+            input.as_bytes(),
+            Vec::new(),
+            ("formula", "result"),
+            &ParserOptions::default(),
+            |result| Some(result.to_string()),
+            None,
+            None,
+        );
+        assert!(matches!(result, Err(CsvError::MissingColumn(column)) if column == "formula"));
+
+        let input = "price,quantity,total\n3,4,AcB\n5,2,AcBa1\n7,0,AdB\n2,9,AcB3\n";
+        let mut output = Vec::new();
+        process(
+            input.as_bytes(),
+            &mut output,
+            ("total", "result"),
+            &ParserOptions::default(),
+            |result| Some(result.to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            "price,quantity,total,result\n3,4,AcB,12\n5,2,AcBa1,11\n7,0,AdB,\n2,9,AcB3,4\n",
+            String::from_utf8(output).unwrap()
+        );
+
+        let input = "total,n\n3a2,4\n2a3,4\nB2a1,4\n3a2,4\n";
+        let mut output = Vec::new();
+        let mut cache = ResultCache::new(10, &ParserOptions::default());
+        process(
+            input.as_bytes(),
+            &mut output,
+            ("total", "result"),
+            &ParserOptions::default(),
+            |result| Some(result.to_string()),
+            None,
+            Some(&mut cache),
+        )
+        .unwrap();
+        assert_eq!(
+            "total,n,result\n3a2,4,5\n2a3,4,5\nB2a1,4,5\n3a2,4,5\n",
+            String::from_utf8(output).unwrap()
+        );
+        assert_eq!((2, 2), (cache.stats().hits, cache.stats().misses));
+    }
+}
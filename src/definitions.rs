@@ -0,0 +1,1285 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::bits;
+use crate::operation::codes::{
+    OPCODE_ADD, OPCODE_CLOSE, OPCODE_DIV, OPCODE_MUL, OPCODE_OPEN, OPCODE_SUB,
+};
+use crate::operation::Operation;
+use crate::parser::{ParseError, Parser};
+use crate::theory;
+
+/// The character introducing a call to a named formula (e.g. `@tax[100]`)
+const CALL: char = '@';
+/// The deepest nesting of calls, bounding the expansion of recursive formulas
+const MAX_DEPTH: usize = 32;
+/// The keyword introducing a local binding (e.g. `let t = 3a2 in t c t`)
+const LET: &str = "let";
+/// The keyword separating the value of a local binding from the expression using it
+const IN: &str = "in";
+/// The keyword introducing a function (e.g. `fn(x) -> x c x`)
+const FN: &str = "fn";
+/// The separator of the parameters of a function from its body
+const ARROW: &str = "->";
+/// The builtin adding the terms of a range (e.g. `sum(1..100)`)
+const SUM: &str = "sum";
+/// The builtin multiplying the terms of a range (e.g. `prod(1..5)`)
+const PROD: &str = "prod";
+/// The builtin averaging a list (e.g. `mean([1, 2, 3])`)
+const MEAN: &str = "mean";
+/// The builtin taking the middle value of a list (e.g. `median([3, 1, 2])`)
+const MEDIAN: &str = "median";
+/// The builtin taking the least value of a list (e.g. `min([3, 1, 2])`)
+const MIN: &str = "min";
+/// The builtin taking the greatest value of a list (e.g. `max([3, 1, 2])`)
+const MAX: &str = "max";
+/// The builtins reducing a list or a range
+const BUILTINS: [&str; 6] = [SUM, PROD, MEAN, MEDIAN, MIN, MAX];
+/// The builtin checking whether a number is prime, yielding 1 or 0 (e.g. `is_prime(7)`)
+const IS_PRIME: &str = "is_prime";
+/// The builtin counting the numbers coprime with a number (e.g. `totient(84)`)
+const TOTIENT: &str = "totient";
+/// The builtin listing the prime factors of a number, for the builtins reducing a list
+/// (e.g. `max(factor(84))`)
+const FACTOR: &str = "factor";
+/// The builtin counting the combinations of `k` items out of `n` (e.g. `choose(5, 2)`)
+const CHOOSE: &str = "choose";
+/// The builtin counting the permutations of `k` items out of `n` (e.g. `perm(5, 2)`)
+const PERM: &str = "perm";
+/// The builtin computing a factorial (e.g. `fact(5)`)
+const FACT: &str = "fact";
+/// The builtins of number theory and combinatorics
+const THEORY: [&str; 6] = [IS_PRIME, TOTIENT, FACTOR, CHOOSE, PERM, FACT];
+/// The builtin extracting the bit of a number at a position, from 0 (e.g. `bit(5, 2)`)
+const BIT: &str = "bit";
+/// The builtin extracting the bits of a number between two positions, included, shifted down
+/// (e.g. `bits(213, 7, 4)`)
+const BITS: &str = "bits";
+/// The builtin counting the bits set in a number (e.g. `popcount(213)`)
+const POPCOUNT: &str = "popcount";
+/// The builtins extracting bit fields, for register math
+const BIT_FIELDS: [&str; 3] = [BIT, BITS, POPCOUNT];
+/// The operations which can overflow, by the name of their builtins choosing the overflow
+/// semantics (e.g. `add_sat(3, 2)`)
+const OVERFLOWING: [(&str, char); 3] = [
+    ("add", OPCODE_ADD),
+    ("sub", OPCODE_SUB),
+    ("mul", OPCODE_MUL),
+];
+/// The suffix of the builtins clamping the result of an operation at the bounds of the numbers
+const SATURATING: &str = "_sat";
+/// The suffix of the builtins wrapping the result of an operation around the bounds of the numbers
+const WRAPPING: &str = "_wrap";
+/// The builtin drawing a random number between two bounds, included (e.g. `rand(1, 6)`)
+const RAND: &str = "rand";
+/// The separator of the bounds of a range
+const RANGE: &str = "..";
+/// The most terms a range can have, bounding the length of the expression it expands to
+const MAX_TERMS: usize = 10_000;
+/// The most characters an expression can desugar into, since a value bound to a name is copied
+/// wherever the name is used, and values can use the names bound before them
+const MAX_EXPANSION: usize = 1 << 20;
+
+/// Errors that loading or expanding named formulas can cause
+#[derive(Debug, PartialEq)]
+pub enum DefinitionError {
+    /// A line of the definitions is not a `name = body` definition (line number, line)
+    Malformed(usize, String),
+    /// A formula is called but not defined (name)
+    Undefined(String),
+    /// A formula or a builtin is called with a wrong number of arguments (name, parameters,
+    /// arguments)
+    Arity(String, usize, usize),
+    /// A call is not written as `@name[arguments]` (position)
+    MalformedCall(usize),
+    /// Calls nest deeper than `MAX_DEPTH`, e.g. because a formula calls itself (name)
+    TooDeep(String),
+    /// A local binding is not written as `let name = value in expression` (binding)
+    MalformedBinding(String),
+    /// A range is not written as `first..last` or `variable, first, last, term`, a list as
+    /// `[number, ...]`, or the bounds of a range are reversed (builtin)
+    MalformedRange(String),
+    /// A range has more than `MAX_TERMS` terms (builtin)
+    TooManyTerms(String),
+    /// A statistic is computed over an empty list or range (builtin)
+    Empty(String),
+    /// A number is too large for a builtin to compute in reasonable time, or the result of the
+    /// builtin overflows (builtin, number)
+    TooLarge(String, usize),
+    /// A list is used where a number is expected (builtin)
+    NotANumber(String),
+    /// An argument of a builtin is not an expression the `Parser` evaluates (builtin, error)
+    InvalidArgument(String, ParseError),
+    /// An expression desugars into more than `MAX_EXPANSION` characters (limit)
+    TooLong(usize),
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(number, line) => write!(f, "Malformed({}, {:?})", number, line),
+            Self::Undefined(name) => write!(f, "Undefined({})", name),
+            Self::Arity(name, parameters, arguments) => {
+                write!(f, "Arity({}, {}, {})", name, parameters, arguments)
+            }
+            Self::MalformedCall(position) => write!(f, "MalformedCall({})", position),
+            Self::TooDeep(name) => write!(f, "TooDeep({})", name),
+            Self::MalformedBinding(binding) => write!(f, "MalformedBinding({:?})", binding),
+            Self::MalformedRange(builtin) => write!(f, "MalformedRange({})", builtin),
+            Self::TooManyTerms(builtin) => write!(f, "TooManyTerms({})", builtin),
+            Self::Empty(builtin) => write!(f, "Empty({})", builtin),
+            Self::TooLarge(builtin, number) => write!(f, "TooLarge({}, {})", builtin, number),
+            Self::NotANumber(builtin) => write!(f, "NotANumber({})", builtin),
+            Self::InvalidArgument(builtin, err) => {
+                write!(f, "InvalidArgument({}, {:?})", builtin, err)
+            }
+            Self::TooLong(limit) => write!(f, "TooLong({})", limit),
+        }
+    }
+}
+
+/// A named formula, whose body refers to its parameters as `{parameter}`
+#[derive(Debug, Clone, PartialEq)]
+struct Formula {
+    /// The parameters, in order of first appearance in the body
+    parameters: Vec<String>,
+    /// The body, without whitespace
+    body: String,
+}
+
+/// A registry of named formulas, called in expressions as `@name[argument,...]`. Every call
+/// expands, before parsing, into the body of the formula enclosed in parentheses, where every
+/// parameter is replaced by its argument, itself enclosed in parentheses.
+#[derive(Debug, Default)]
+pub struct Definitions {
+    /// The formulas by name
+    formulas: HashMap<String, Formula>,
+    /// The seed of the random numbers drawn by `rand`, different on every expansion if `None`
+    seed: Option<u64>,
+}
+
+/// Implementation of the formula registry
+impl Definitions {
+    /// Load the definitions written one per line as `name = body`. Blank lines and lines
+    /// starting with `#` are ignored.
+    /// # Arguments
+    ///  - text: The definitions
+    /// # Return
+    /// A `Result` having the `Definitions` if valid, `DefinitionError` otherwise
+    pub fn parse(text: &str) -> Result<Self, DefinitionError> {
+        let mut definitions = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let malformed = || DefinitionError::Malformed(index + 1, line.to_string());
+            let (name, body) = trimmed.split_once('=').ok_or_else(malformed)?;
+            if !definitions.define(name.trim(), body) {
+                return Err(malformed());
+            }
+        }
+        Ok(definitions)
+    }
+
+    /// Define a formula, replacing any formula having the same name
+    /// # Arguments
+    ///  - name: The name, made of lowercase letters, digits and underscores
+    ///  - body: The expression computed by the formula, referring to its parameters as
+    ///    `{parameter}`, or a function written `fn(parameter, ...) -> expression`
+    /// # Return
+    /// Whether the name is valid and the formula was defined
+    pub fn define(&mut self, name: &str, body: &str) -> bool {
+        if name.is_empty() || !name.chars().all(Self::is_name_char) {
+            return false;
+        }
+        let words = words(body);
+        let formula = match function(&words) {
+            // The parameters of a function become the parameters of the formula, in order
+            Some((parameters, body)) => {
+                let scope = parameters
+                    .iter()
+                    .map(|parameter| (*parameter, Binding::Value(format!("{{{}}}", parameter))))
+                    .collect();
+                let Ok(body) = bind_words(body, &scope) else {
+                    return false;
+                };
+                let parameters = parameters.iter().map(|parameter| parameter.to_string());
+                Formula {
+                    parameters: parameters.collect(),
+                    body,
+                }
+            }
+            None if words.first() == Some(&FN) => return false,
+            None => {
+                let body: String = body.chars().filter(|char| !char.is_whitespace()).collect();
+                let mut parameters: Vec<String> = Vec::new();
+                let mut rest = body.as_str();
+                while let Some((_, after)) = rest.split_once('{') {
+                    let Some((parameter, after)) = after.split_once('}') else {
+                        return false;
+                    };
+                    if !parameters.iter().any(|known| known == parameter) {
+                        parameters.push(parameter.to_string());
+                    }
+                    rest = after;
+                }
+                Formula { parameters, body }
+            }
+        };
+        self.formulas.insert(name.to_string(), formula);
+        true
+    }
+
+    /// Draw the same random numbers on every expansion, for reproducible runs
+    /// # Arguments
+    ///  - seed: The seed of the random numbers drawn by `rand`
+    /// # Return
+    /// The `Definitions` drawing from the seed
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Expand every local binding and every call to a named formula in an expression
+    /// # Arguments
+    ///  - expression: The expression calling the formulas
+    /// # Return
+    /// A `Result` having the expression without calls if valid, `DefinitionError` otherwise
+    pub fn expand(&self, expression: &str) -> Result<String, DefinitionError> {
+        let expression = capture(&bind_random(expression, &Rc::new(Random::new(self.seed)))?);
+        self.expand_nested(&expression, 0)
+    }
+
+    /// Expand the calls of an expression, nested in calls up to the given depth
+    fn expand_nested(&self, expression: &str, depth: usize) -> Result<String, DefinitionError> {
+        if !expression.contains(CALL) {
+            return Ok(expression.to_string());
+        }
+        let chars: Vec<char> = expression.chars().collect();
+        let mut expanded = String::with_capacity(expression.len());
+        let mut position = 0;
+        while position < chars.len() {
+            if chars[position] != CALL {
+                expanded.push(chars[position]);
+                position += 1;
+                continue;
+            }
+            let start = position;
+            position += 1;
+            let name_start = position;
+            while chars
+                .get(position)
+                .is_some_and(|char| Self::is_name_char(*char))
+            {
+                position += 1;
+            }
+            let name: String = chars[name_start..position].iter().collect();
+            if name.is_empty() || chars.get(position) != Some(&'[') {
+                return Err(DefinitionError::MalformedCall(start));
+            }
+            // Arguments are split at the commas outside of nested calls
+            let mut arguments = vec![String::new()];
+            let mut nesting = 0;
+            loop {
+                position += 1;
+                match chars.get(position) {
+                    None => return Err(DefinitionError::MalformedCall(start)),
+                    Some(']') if nesting == 0 => break,
+                    Some(',') if nesting == 0 => arguments.push(String::new()),
+                    Some(&char) => {
+                        match char {
+                            '[' => nesting += 1,
+                            ']' => nesting -= 1,
+                            _ => {}
+                        }
+                        arguments.last_mut().unwrap().push(char);
+                    }
+                }
+            }
+            position += 1;
+            expanded.push_str(&self.call(&name, &arguments, depth)?);
+        }
+        Ok(expanded)
+    }
+
+    /// Expand a call to a formula into its body, enclosed in parentheses
+    fn call(
+        &self,
+        name: &str,
+        arguments: &[String],
+        depth: usize,
+    ) -> Result<String, DefinitionError> {
+        if depth >= MAX_DEPTH {
+            return Err(DefinitionError::TooDeep(name.to_string()));
+        }
+        let formula = self
+            .formulas
+            .get(name)
+            .ok_or_else(|| DefinitionError::Undefined(name.to_string()))?;
+        // A formula without parameters is called with empty brackets
+        let arguments = match arguments {
+            [argument] if argument.trim().is_empty() => &[],
+            arguments => arguments,
+        };
+        if arguments.len() != formula.parameters.len() {
+            return Err(DefinitionError::Arity(
+                name.to_string(),
+                formula.parameters.len(),
+                arguments.len(),
+            ));
+        }
+        let mut body = formula.body.clone();
+        for (parameter, argument) in formula.parameters.iter().zip(arguments) {
+            let argument = self.expand_nested(argument.trim(), depth + 1)?;
+            body = body.replace(&format!("{{{}}}", parameter), &Self::group(argument));
+        }
+        let body = self.expand_nested(&body, depth + 1)?;
+        Ok(Self::group(body))
+    }
+
+    /// Enclose an expression in parentheses, unless it is a number or already enclosed
+    fn group(expression: String) -> String {
+        let mut depth = 0;
+        let enclosed = expression.starts_with(OPCODE_OPEN)
+            && expression.chars().enumerate().all(|(position, char)| {
+                match char {
+                    OPCODE_OPEN => depth += 1,
+                    OPCODE_CLOSE => depth -= 1,
+                    _ => {}
+                }
+                depth > 0 || position == expression.chars().count() - 1
+            });
+        match enclosed || expression.chars().all(|char| char.is_ascii_digit()) {
+            true if !expression.is_empty() => expression,
+            _ => format!("{}{}{}", OPCODE_OPEN, expression, OPCODE_CLOSE),
+        }
+    }
+
+    /// Whether a character can be part of a formula name
+    fn is_name_char(char: char) -> bool {
+        char.is_ascii_lowercase() || char.is_ascii_digit() || char == '_'
+    }
+}
+
+/// Desugar the local bindings of an expression, written `let name = value in expression`, by
+/// replacing every word of the expression equal to the name with the value in parentheses.
+/// Names are words, so they are separated from operations by whitespace (e.g. `t c t`); a binding
+/// shadows the bindings of the same name enclosing it. A value written `fn(x, y) -> expression`
+/// is a function, applied as `name[argument, ...]`. The builtins `sum` and `prod` reduce a range,
+/// written `sum(1..100)` or `sum(i, 1, 100, i c i)`, or a list, written `sum([1, 2, 3])`, which
+/// `mean`, `median`, `min` and `max` reduce as well. The builtins `is_prime` and `totient` compute
+/// over a number, and `factor` lists its prime factors for the builtins reducing a list. The
+/// builtins `choose`, `perm` and `fact` count combinations, permutations and arrangements. The
+/// builtins `bit`, `bits` and `popcount` extract bit fields. The builtins `add_sat`, `sub_sat` and
+/// `mul_sat` clamp the result of an operation at the bounds, while `add_wrap`, `sub_wrap` and
+/// `mul_wrap` wrap it around. The builtin `rand` draws a random number, different on every call.
+/// # Arguments
+///  - expression: The expression having the bindings
+/// # Return
+/// A `Result` having the expression without bindings if valid, `DefinitionError` otherwise
+pub fn bind(expression: &str) -> Result<String, DefinitionError> {
+    bind_random(expression, &Rc::new(Random::new(None)))
+}
+
+/// Desugar the local bindings of an expression, drawing the random numbers from a generator
+fn bind_random(expression: &str, random: &Rc<Random>) -> Result<String, DefinitionError> {
+    let words = words(expression);
+    let keyword = |word: &&str| {
+        *word == LET
+            || *word == RAND
+            || BUILTINS.contains(word)
+            || THEORY.contains(word)
+            || BIT_FIELDS.contains(word)
+            || overflow_builtin(word).is_some()
+    };
+    if !words.iter().any(keyword) {
+        return Ok(expression.to_string());
+    }
+    let scope = Scope::from([(RAND, Binding::Random(Rc::clone(random)))]);
+    bind_words(&words, &scope)
+}
+
+/// The generator of the random numbers drawn by `rand`, a SplitMix64 sequence
+#[derive(Debug)]
+struct Random {
+    /// The state, advanced on every draw
+    state: Cell<u64>,
+}
+
+/// Implementation of the random number generator
+impl Random {
+    /// Creates the generator from a seed, or from the current time if `None`
+    fn new(seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+        });
+        Self {
+            state: Cell::new(seed),
+        }
+    }
+
+    /// Draw a number between two bounds, included
+    fn draw(&self, low: usize, high: usize) -> usize {
+        let state = self.state.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+        self.state.set(state);
+        let mut mixed = (state ^ (state >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        mixed ^= mixed >> 31;
+        match (high - low).checked_add(1) {
+            Some(count) => low + (mixed % count as u64) as usize,
+            None => mixed as usize,
+        }
+    }
+}
+
+/// Desugar the captures of an expression, where a group or a number followed by `@name` is
+/// labelled (e.g. `e3a2f@t c @t`) and every later `@name` is replaced with it, to be computed
+/// again. The whitespace ending the names is removed, while the calls to named formulas, followed
+/// by `[`, and the names not labelled yet are left to expand.
+/// # Arguments
+///  - expression: The expression having the captures
+/// # Return
+/// The expression without captures
+pub fn capture(expression: &str) -> String {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut captures: HashMap<String, String> = HashMap::new();
+    let mut captured = String::with_capacity(expression.len());
+    let mut position = 0;
+    while position < chars.len() {
+        let char = chars[position];
+        position += 1;
+        if char != CALL {
+            if !char.is_whitespace() {
+                captured.push(char);
+            }
+            continue;
+        }
+        let start = position;
+        while chars
+            .get(position)
+            .is_some_and(|char| Definitions::is_name_char(*char))
+        {
+            position += 1;
+        }
+        let name: String = chars[start..position].iter().collect();
+        let call = name.is_empty() || chars.get(position) == Some(&'[');
+        if let (false, Some(operand)) = (call, labelled(&captured)) {
+            captures.insert(name, operand.to_string());
+        } else if let (false, Some(operand)) = (call, captures.get(&name)) {
+            captured.push_str(operand);
+        } else {
+            captured.push(CALL);
+            captured.push_str(&name);
+        }
+    }
+    match captures.is_empty() {
+        true => expression.to_string(),
+        false => captured,
+    }
+}
+
+/// The operand ending an expression, either a group or a number
+fn labelled(expression: &str) -> Option<&str> {
+    let start = match expression.chars().last()? {
+        OPCODE_CLOSE => {
+            let mut depth = 0;
+            expression
+                .char_indices()
+                .rev()
+                .find(|(_, char)| {
+                    match *char {
+                        OPCODE_CLOSE => depth += 1,
+                        OPCODE_OPEN => depth -= 1,
+                        _ => {}
+                    }
+                    depth == 0
+                })?
+                .0
+        }
+        char if char.is_ascii_digit() => expression
+            .rfind(|char: char| !char.is_ascii_digit())
+            .map_or(0, |position| position + 1),
+        _ => return None,
+    };
+    Some(&expression[start..])
+}
+
+/// The bindings visible at some point of an expression, by name
+type Scope<'a> = HashMap<&'a str, Binding<'a>>;
+
+/// The value bound to a name
+#[derive(Debug, Clone)]
+enum Binding<'a> {
+    /// An expression, already desugared and in parentheses
+    Value(String),
+    /// A function (parameters, body, bindings visible where the function is written)
+    Function(Vec<&'a str>, &'a [&'a str], Rc<Scope<'a>>),
+    /// The generator drawing the random numbers, shared by all the scopes
+    Random(Rc<Random>),
+}
+
+/// Desugar the bindings of an expression split into words, given the bindings in scope
+fn bind_words<'a>(words: &'a [&'a str], scope: &Scope<'a>) -> Result<String, DefinitionError> {
+    let malformed = || DefinitionError::MalformedBinding(words.concat());
+    let [LET, name, "=", rest @ ..] = words else {
+        if words.first() == Some(&LET) {
+            return Err(malformed());
+        }
+        let mut desugared = String::new();
+        let mut position = 0;
+        while let Some(word) = words.get(position) {
+            if desugared.len() > MAX_EXPANSION {
+                return Err(DefinitionError::TooLong(MAX_EXPANSION));
+            }
+            position += 1;
+            match scope.get(word) {
+                Some(Binding::Value(value)) => desugared.push_str(value),
+                Some(Binding::Function(parameters, body, closure)) => {
+                    let (arguments, next) =
+                        arguments(words, position, ("[", "]")).ok_or_else(malformed)?;
+                    if arguments.len() != parameters.len() {
+                        return Err(DefinitionError::Arity(
+                            word.to_string(),
+                            parameters.len(),
+                            arguments.len(),
+                        ));
+                    }
+                    let mut inner = Scope::clone(closure);
+                    for (parameter, argument) in parameters.iter().zip(arguments) {
+                        let argument = Definitions::group(bind_words(argument, scope)?);
+                        inner.insert(parameter, Binding::Value(argument));
+                    }
+                    desugared.push_str(&Definitions::group(bind_words(body, &inner)?));
+                    position = next;
+                }
+                Some(Binding::Random(random)) => {
+                    let malformed = || DefinitionError::MalformedRange(word.to_string());
+                    let (arguments, next) =
+                        arguments(words, position, ("(", ")")).ok_or_else(malformed)?;
+                    let [low, high] = arguments[..] else {
+                        return Err(DefinitionError::Arity(word.to_string(), 2, arguments.len()));
+                    };
+                    let (low, high) = (number(word, low, scope)?, number(word, high, scope)?);
+                    if low > high {
+                        return Err(malformed());
+                    }
+                    desugared.push_str(&random.draw(low, high).to_string());
+                    position = next;
+                }
+                None if BUILTINS.contains(word)
+                    || THEORY.contains(word)
+                    || BIT_FIELDS.contains(word) =>
+                {
+                    let (arguments, next) = arguments(words, position, ("(", ")"))
+                        .ok_or_else(|| DefinitionError::MalformedRange(word.to_string()))?;
+                    let value = match *word {
+                        IS_PRIME => {
+                            (theory::is_prime(unary(word, &arguments, scope)?) as usize).to_string()
+                        }
+                        TOTIENT => {
+                            let number = unary(word, &arguments, scope)?;
+                            theory::totient(number)
+                                .ok_or_else(|| DefinitionError::TooLarge(word.to_string(), number))?
+                                .to_string()
+                        }
+                        FACTOR => return Err(DefinitionError::NotANumber(word.to_string())),
+                        CHOOSE | PERM => {
+                            let [n, k] = arguments[..] else {
+                                return Err(arity(word, 2, &arguments));
+                            };
+                            let n = number(word, n, scope)?;
+                            let count = match *word {
+                                CHOOSE => theory::choose(n, number(word, k, scope)?),
+                                _ => theory::perm(n, number(word, k, scope)?),
+                            };
+                            count
+                                .ok_or_else(|| DefinitionError::TooLarge(word.to_string(), n))?
+                                .to_string()
+                        }
+                        FACT => {
+                            let number = unary(word, &arguments, scope)?;
+                            theory::perm(number, number)
+                                .ok_or_else(|| DefinitionError::TooLarge(word.to_string(), number))?
+                                .to_string()
+                        }
+                        BIT => {
+                            let [value, position] = arguments[..] else {
+                                return Err(arity(word, 2, &arguments));
+                            };
+                            let value = number(word, value, scope)?;
+                            bits::bit(value, number(word, position, scope)?).to_string()
+                        }
+                        BITS => {
+                            let [value, high, low] = arguments[..] else {
+                                return Err(arity(word, 3, &arguments));
+                            };
+                            let value = number(word, value, scope)?;
+                            let (high, low) =
+                                (number(word, high, scope)?, number(word, low, scope)?);
+                            bits::field(value, high, low)
+                                .ok_or_else(|| DefinitionError::MalformedRange(word.to_string()))?
+                                .to_string()
+                        }
+                        POPCOUNT => bits::popcount(unary(word, &arguments, scope)?).to_string(),
+                        _ => reduce(word, &arguments, scope)?,
+                    };
+                    desugared.push_str(&value);
+                    position = next;
+                }
+                None => match overflow_builtin(word) {
+                    Some((code, saturating)) => {
+                        let malformed = || DefinitionError::MalformedRange(word.to_string());
+                        let (arguments, next) =
+                            arguments(words, position, ("(", ")")).ok_or_else(malformed)?;
+                        let [first, second] = arguments[..] else {
+                            return Err(arity(word, 2, &arguments));
+                        };
+                        let first = number(word, first, scope)?;
+                        let second = number(word, second, scope)?;
+                        let operation = Operation::from_code(code, first).ok_or_else(malformed)?;
+                        let result = match saturating {
+                            true => operation.saturating_apply(second),
+                            false => operation.wrapping_apply(second),
+                        };
+                        desugared.push_str(&result.ok_or_else(malformed)?.to_string());
+                        position = next;
+                    }
+                    None => desugared.push_str(word),
+                },
+            }
+        }
+        return Ok(desugared);
+    };
+    // The value ends at the `in` matching the `let`, since values may have bindings too
+    let mut nesting = 0;
+    let end = rest.iter().position(|word| {
+        match *word {
+            LET => nesting += 1,
+            IN if nesting == 0 => return true,
+            IN => nesting -= 1,
+            _ => {}
+        }
+        false
+    });
+    let Some(end) = end.filter(|_| is_binding_name(name)) else {
+        return Err(malformed());
+    };
+    let binding = match &rest[..end] {
+        [FN, ..] => {
+            let (parameters, body) = function(&rest[..end]).ok_or_else(malformed)?;
+            Binding::Function(parameters, body, Rc::new(scope.clone()))
+        }
+        value => Binding::Value(Definitions::group(bind_words(value, scope)?)),
+    };
+    let mut scope = scope.clone();
+    scope.insert(name, binding);
+    bind_words(&rest[end + 1..], &scope)
+}
+
+/// Split a function, written `fn(x, y) -> expression`, into its parameters and body
+fn function<'a>(words: &'a [&'a str]) -> Option<(Vec<&'a str>, &'a [&'a str])> {
+    let [FN, "(", rest @ ..] = words else {
+        return None;
+    };
+    let close = rest.iter().position(|word| *word == ")")?;
+    let [")", ARROW, body @ ..] = &rest[close..] else {
+        return None;
+    };
+    let parameters: Vec<&str> = rest[..close].iter().step_by(2).copied().collect();
+    let separated = rest[..close]
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .all(|word| *word == ",");
+    let valid = parameters
+        .iter()
+        .all(|parameter| is_binding_name(parameter));
+    (separated && valid && !body.is_empty()).then_some((parameters, body))
+}
+
+/// Split the arguments of an application, written `[argument, ...]` from the given position, or
+/// `(argument, ...)` for the builtins
+/// # Return
+/// The arguments and the position following the application, `None` if malformed
+fn arguments<'a>(
+    words: &'a [&'a str],
+    position: usize,
+    (open, close): (&str, &str),
+) -> Option<(Vec<&'a [&'a str]>, usize)> {
+    if words.get(position) != Some(&open) {
+        return None;
+    }
+    let mut arguments = Vec::new();
+    let mut start = position + 1;
+    let mut nesting = 0;
+    for (index, word) in words.iter().enumerate().skip(start) {
+        match *word {
+            "[" | "(" => nesting += 1,
+            word if nesting == 0 && word == close => {
+                if index > start || !arguments.is_empty() {
+                    arguments.push(&words[start..index]);
+                }
+                return Some((arguments, index + 1));
+            }
+            "]" | ")" if nesting == 0 => return None,
+            "]" | ")" => nesting -= 1,
+            "," if nesting == 0 => {
+                arguments.push(&words[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Desugar a builtin reducing a list, written `sum([1, 2, 3])`, or a range, written
+/// `sum(first..last)` or, for `sum` and `prod`, `sum(i, first, last, term)`
+/// # Arguments
+///  - builtin: One of `BUILTINS`
+///  - arguments: The arguments of the builtin
+///  - scope: The bindings visible where the builtin is written
+/// # Return
+/// A `Result` having the reduction if valid, `DefinitionError` otherwise
+fn reduce<'a>(
+    builtin: &str,
+    arguments: &[&'a [&'a str]],
+    scope: &Scope<'a>,
+) -> Result<String, DefinitionError> {
+    let malformed = || DefinitionError::MalformedRange(builtin.to_string());
+    let number = |words| number(builtin, words, scope);
+    let range = |first: usize, last: usize| match last.saturating_sub(first) < MAX_TERMS {
+        true => Ok(first..=last),
+        false => Err(DefinitionError::TooManyTerms(builtin.to_string())),
+    };
+    let terms: Vec<String> = match arguments {
+        [["[", "]"]] => Vec::new(),
+        [[FACTOR, "(", factor @ .., ")"]] => {
+            let number = number(factor)?;
+            theory::factors(number)
+                .ok_or_else(|| DefinitionError::TooLarge(FACTOR.to_string(), number))?
+                .iter()
+                .map(usize::to_string)
+                .collect()
+        }
+        [["[", elements @ .., "]"]] => elements
+            .split(|word| *word == ",")
+            .map(|element| number(element).map(|value| value.to_string()))
+            .collect::<Result<_, _>>()?,
+        [words] => {
+            let bounds = bind_words(words, scope)?;
+            let (first, last) = bounds.split_once(RANGE).ok_or_else(malformed)?;
+            let (first, last) = (evaluate(builtin, first)?, evaluate(builtin, last)?);
+            range(first, last)?.map(|value| value.to_string()).collect()
+        }
+        [[variable], first, last, term] if [SUM, PROD].contains(&builtin) => {
+            if !is_binding_name(variable) {
+                return Err(malformed());
+            }
+            range(number(first)?, number(last)?)?
+                .map(|value| {
+                    let mut scope = scope.clone();
+                    scope.insert(variable, Binding::Value(value.to_string()));
+                    bind_words(term, &scope).map(Definitions::group)
+                })
+                .collect::<Result<_, _>>()?
+        }
+        _ => return Err(malformed()),
+    };
+    let join = |operation: char| Definitions::group(terms.join(&operation.to_string()));
+    match builtin {
+        SUM if terms.is_empty() => Ok("0".to_string()),
+        PROD if terms.is_empty() => Ok("1".to_string()),
+        SUM => Ok(join(OPCODE_ADD)),
+        PROD => Ok(join(OPCODE_MUL)),
+        _ if terms.is_empty() => Err(DefinitionError::Empty(builtin.to_string())),
+        // The statistics truncate, like divisions do
+        MEAN => {
+            let mean = format!("{}{}{}", join(OPCODE_ADD), OPCODE_DIV, terms.len());
+            Ok(Definitions::group(mean))
+        }
+        _ => {
+            let mut values: Vec<usize> = terms.iter().map(|term| term.parse().unwrap()).collect();
+            values.sort_unstable();
+            let middle = values.len() / 2;
+            Ok(match builtin {
+                MIN => values[0].to_string(),
+                MAX => values[values.len() - 1].to_string(),
+                _ if values.len() % 2 == 1 => values[middle].to_string(),
+                _ => {
+                    let sum = format!("{}{}{}", values[middle - 1], OPCODE_ADD, values[middle]);
+                    Definitions::group(format!("{}{}2", Definitions::group(sum), OPCODE_DIV))
+                }
+            })
+        }
+    }
+}
+
+/// The operation code of a builtin choosing the overflow semantics of an operation, and whether
+/// it saturates rather than wraps around (e.g. `('c', false)` for `mul_wrap`)
+fn overflow_builtin(word: &str) -> Option<(char, bool)> {
+    let (name, saturating) = match word.strip_suffix(SATURATING) {
+        Some(name) => (name, true),
+        None => (word.strip_suffix(WRAPPING)?, false),
+    };
+    OVERFLOWING
+        .iter()
+        .find(|(operation, _)| *operation == name)
+        .map(|(_, code)| (*code, saturating))
+}
+
+/// The number which is the only argument of a builtin
+fn unary<'a>(
+    builtin: &str,
+    arguments: &[&'a [&'a str]],
+    scope: &Scope<'a>,
+) -> Result<usize, DefinitionError> {
+    match arguments {
+        [argument] => number(builtin, argument, scope),
+        _ => Err(arity(builtin, 1, arguments)),
+    }
+}
+
+/// The error of a builtin called with a wrong number of arguments
+fn arity(builtin: &str, parameters: usize, arguments: &[&[&str]]) -> DefinitionError {
+    DefinitionError::Arity(builtin.to_string(), parameters, arguments.len())
+}
+
+/// The number an argument of a builtin is, once its bindings are desugared and it is evaluated
+fn number<'a>(
+    builtin: &str,
+    words: &'a [&'a str],
+    scope: &Scope<'a>,
+) -> Result<usize, DefinitionError> {
+    evaluate(builtin, &bind_words(words, scope)?)
+}
+
+/// Evaluate an argument of a builtin, which is an expression like any other (e.g. `3a4`)
+fn evaluate(builtin: &str, argument: &str) -> Result<usize, DefinitionError> {
+    Parser::new(argument.to_string())
+        .parse()
+        .map_err(|err| DefinitionError::InvalidArgument(builtin.to_string(), err))
+}
+
+/// Whether a word can name a binding
+fn is_binding_name(name: &str) -> bool {
+    name.starts_with(|char: char| char.is_ascii_lowercase())
+        && name.chars().all(Definitions::is_name_char)
+        && ![LET, IN, FN].contains(&name)
+        && !BUILTINS.contains(&name)
+        && !THEORY.contains(&name)
+        && !BIT_FIELDS.contains(&name)
+        && overflow_builtin(name).is_none()
+        && name != RAND
+}
+
+/// Split an expression into words, at whitespace and around the punctuation of bindings,
+/// functions, ranges and calls
+fn words(expression: &str) -> Vec<&str> {
+    expression
+        .split_whitespace()
+        .flat_map(|word| {
+            let mut pieces = Vec::new();
+            let mut start = 0;
+            for (position, char) in word.char_indices() {
+                let length = match char {
+                    '=' | '[' | ']' | ',' | '(' | ')' => 1,
+                    '-' if word[position..].starts_with(ARROW) => ARROW.len(),
+                    '.' if word[position..].starts_with(RANGE) => RANGE.len(),
+                    _ => continue,
+                };
+                pieces.push(&word[start..position]);
+                pieces.push(&word[position..position + length]);
+                start = position + length;
+            }
+            pieces.push(&word[start..]);
+            pieces.into_iter().filter(|piece| !piece.is_empty())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::definitions::{bind, capture, DefinitionError, Definitions};
+    use crate::parser::{ParseError, Parser};
+
+    const DEFINITIONS: &str = "# Taxes
+tax = e{amount} c 21 d 100f
+
+gross = {amount} a @tax[{amount}]
+double = {x} c 2
+ratio = fn(a, b) -> a d b
+";
+
+    fn eval(definitions: &Definitions, expression: &str) -> usize {
+        let expanded = definitions.expand(expression).unwrap();
+        Parser::new(expanded).parse().unwrap()
+    }
+
+    #[test]
+    fn test_expand() {
+        let definitions = Definitions::parse(DEFINITIONS).unwrap();
+        assert_eq!("e100c21d100f", definitions.expand("@tax[100]").unwrap());
+        assert_eq!("ee100fc21d100f", definitions.expand("@tax[e100f]").unwrap());
+        assert_eq!(
+            "eeea2fa1fc2f",
+            definitions.expand("@double[ea2fa1]").unwrap()
+        );
+        assert_eq!(21, eval(&definitions, "@tax[100]"));
+        assert_eq!(121, eval(&definitions, "@gross[100]"));
+        assert_eq!(42, eval(&definitions, "@tax[@double[50]]a@tax[100]"));
+        assert_eq!(11, eval(&definitions, "1a@double[5]"));
+        assert_eq!("3a2", definitions.expand("3a2").unwrap());
+    }
+
+    #[test]
+    fn test_errors() {
+        let definitions = Definitions::parse(DEFINITIONS).unwrap();
+        assert_eq!(
+            Err(DefinitionError::Undefined("vat".to_string())),
+            definitions.expand("@vat[1]")
+        );
+        assert_eq!(
+            Err(DefinitionError::Arity("tax".to_string(), 1, 2)),
+            definitions.expand("@tax[1,2]")
+        );
+        assert_eq!(
+            Err(DefinitionError::MalformedCall(2)),
+            definitions.expand("3a@tax")
+        );
+        assert_eq!(
+            Err(DefinitionError::MalformedCall(0)),
+            definitions.expand("@tax[1")
+        );
+        assert_eq!(
+            DefinitionError::Malformed(2, "tax".to_string()),
+            Definitions::parse("\ntax").unwrap_err()
+        );
+
+        let mut definitions = Definitions::default();
+        assert!(definitions.define("loop", "@loop[]"));
+        assert_eq!(
+            Err(DefinitionError::TooDeep("loop".to_string())),
+            definitions.expand("@loop[]")
+        );
+        assert!(!definitions.define("Tax", "1"));
+    }
+
+    #[test]
+    fn test_bind() {
+        let definitions = Definitions::parse(DEFINITIONS).unwrap();
+        assert_eq!("e3a2fce3a2f", bind("let t = 3a2 in t c t").unwrap());
+        assert_eq!(25, eval(&definitions, "let t = 3a2 in t c t"));
+        assert_eq!(
+            12,
+            eval(&definitions, "let x=2 in let y = x c 3 in y a x a x a x")
+        );
+        // Inner bindings shadow the outer ones
+        assert_eq!(9, eval(&definitions, "let x = 2 in let x = x a 1 in x c x"));
+        assert_eq!(
+            24,
+            eval(&definitions, "let a = let b = 3 in b c 2 in a c 4")
+        );
+        assert_eq!(21, eval(&definitions, "let t = 100 in @tax[t]"));
+        assert_eq!("3 a 2", bind("3 a 2").unwrap());
+        assert_eq!(
+            Err(DefinitionError::MalformedBinding("lett=3t".to_string())),
+            bind("let t = 3 t")
+        );
+        assert_eq!(
+            Err(DefinitionError::MalformedBinding("let3=3in3".to_string())),
+            bind("let 3 = 3 in 3")
+        );
+        // Every binding using the previous one 8 times would take terabytes
+        let bindings: String = (1..=12)
+            .map(|level| format!("let v{} = {} in ", level, format!("v{} ", level - 1).repeat(8)))
+            .collect();
+        let expression = format!("let v0 = 1234567890 in {}v12", bindings);
+        assert_eq!(Err(DefinitionError::TooLong(1 << 20)), bind(&expression));
+    }
+
+    #[test]
+    fn test_capture() {
+        let definitions = Definitions::parse(DEFINITIONS).unwrap();
+        assert_eq!("e3a2fce3a2f", capture("e3a2f@t c @t"));
+        assert_eq!(25, eval(&definitions, "e3a2f@t c @t"));
+        // The operand before the label is captured, not the result so far
+        assert_eq!(67, eval(&definitions, "2c21@x a @tax[@x] a @x"));
+        // Labels can be captured again, and are only visible after them
+        assert_eq!(6, eval(&definitions, "e1a1f@x c 2@x a @x"));
+        assert_eq!(
+            Err(DefinitionError::MalformedCall(0)),
+            definitions.expand("@x a 1@x")
+        );
+        assert_eq!("3 a 2", capture("3 a 2"));
+    }
+
+    #[test]
+    fn test_function() {
+        let definitions = Definitions::parse(DEFINITIONS).unwrap();
+        assert_eq!(
+            "e3c3fcee3a1fce3a1ff",
+            bind("let sq = fn(x) -> x c x in sq[3] c sq[e3a1f]").unwrap()
+        );
+        assert_eq!(
+            144,
+            eval(&definitions, "let sq = fn(x) -> x c x in sq[3] c sq[e3a1f]")
+        );
+        // Functions see the bindings visible where they are written, and may take functions
+        assert_eq!(
+            35,
+            eval(
+                &definitions,
+                "let k = 5 in let f = fn(x, y) -> x c y a k in f[5, 6]"
+            )
+        );
+        assert_eq!(
+            16,
+            eval(
+                &definitions,
+                "let sq = fn(x) -> x c x in let q = fn(x) -> sq[sq[x]] in q[2]"
+            )
+        );
+        assert_eq!(4, eval(&definitions, "@ratio[8, 2]"));
+        assert_eq!(
+            4,
+            eval(&definitions, "let r = fn(a,b)->@ratio[a,b] in r[12, 3]")
+        );
+        assert_eq!(
+            Err(DefinitionError::Arity("sq".to_string(), 1, 2)),
+            bind("let sq = fn(x) -> x c x in sq[1, 2]")
+        );
+        assert_eq!(
+            Err(DefinitionError::MalformedBinding("sqc2".to_string())),
+            bind("let sq = fn(x) -> x c x in sq c 2")
+        );
+        assert_eq!(
+            Err(DefinitionError::MalformedBinding(
+                "letsq=fn(x)xcxin1".to_string()
+            )),
+            bind("let sq = fn(x) x c x in 1")
+        );
+        let mut definitions = Definitions::default();
+        assert!(!definitions.define("sq", "fn(x -> x"));
+    }
+
+    #[test]
+    fn test_reduce() {
+        let definitions = Definitions::parse(DEFINITIONS).unwrap();
+        assert_eq!("e1a2a3f", bind("sum(1..3)").unwrap());
+        assert_eq!(5050, eval(&definitions, "sum(1..100)"));
+        assert_eq!(120, eval(&definitions, "prod(1..5)"));
+        assert_eq!(385, eval(&definitions, "sum(i, 1, 10, i c i)"));
+        assert_eq!(0, eval(&definitions, "sum(3..2)"));
+        assert_eq!(1, eval(&definitions, "prod(3..2)"));
+        assert_eq!(12, eval(&definitions, "let n = 3 in sum(1..n) c 2"));
+        assert_eq!(
+            14,
+            eval(
+                &definitions,
+                "let sq = fn(x) -> x c x in sum(i, 1, 3, sq[i])"
+            )
+        );
+        assert_eq!(
+            Err(DefinitionError::MalformedRange("sum".to_string())),
+            bind("sum(1,3)")
+        );
+        assert_eq!(24, eval(&definitions, "prod(1..3a1)"));
+        assert_eq!(
+            Err(DefinitionError::InvalidArgument(
+                "prod".to_string(),
+                ParseError::EmptyExpression
+            )),
+            bind("prod(1..)")
+        );
+        assert_eq!(
+            Err(DefinitionError::TooManyTerms("sum".to_string())),
+            bind("sum(1..100000)")
+        );
+    }
+
+    #[test]
+    fn test_statistics() {
+        let definitions = Definitions::parse(DEFINITIONS).unwrap();
+        assert_eq!(10, eval(&definitions, "sum([1, 2, 3, 4])"));
+        assert_eq!(24, eval(&definitions, "prod([1,2,3,4])"));
+        assert_eq!(2, eval(&definitions, "mean([1, 2, 3, 4])"));
+        assert_eq!(5, eval(&definitions, "mean(1..10)"));
+        assert_eq!(2, eval(&definitions, "median([4, 1, 2])"));
+        assert_eq!(5, eval(&definitions, "median([10, 1, 4, 6])"));
+        assert_eq!(1, eval(&definitions, "min([4, 1, 2])"));
+        assert_eq!(4, eval(&definitions, "max([4, 1, 2])"));
+        assert_eq!(
+            7,
+            eval(&definitions, "let n = 3 in max([1, n]) a min([n, 9]) a 1")
+        );
+        assert_eq!(0, eval(&definitions, "sum([])"));
+        assert_eq!(
+            Err(DefinitionError::Empty("mean".to_string())),
+            bind("mean([])")
+        );
+        // The arguments are expressions like any other
+        assert_eq!(3, eval(&definitions, "max([1, 2a1])"));
+        assert_eq!(
+            Err(DefinitionError::InvalidArgument(
+                "max".to_string(),
+                ParseError::MalformedExpression {
+                    position: 2,
+                    found: "a".to_string()
+                }
+            )),
+            bind("max([1, 2aa1])")
+        );
+        assert_eq!(
+            Err(DefinitionError::MalformedRange("min".to_string())),
+            bind("min(i, 1, 3, i)")
+        );
+    }
+
+    #[test]
+    fn test_theory() {
+        let definitions = Definitions::parse(DEFINITIONS).unwrap();
+        assert_eq!(1, eval(&definitions, "is_prime(97)"));
+        assert_eq!(0, eval(&definitions, "is_prime(91)"));
+        assert_eq!(25, eval(&definitions, "sum(i, 1, 100, is_prime(i))"));
+        assert_eq!(24, eval(&definitions, "totient(84)"));
+        assert_eq!(7, eval(&definitions, "max(factor(84))"));
+        assert_eq!(84, eval(&definitions, "prod(factor(84))"));
+        assert_eq!(1, eval(&definitions, "is_prime(18446744073709551557)"));
+        assert_eq!(
+            Err(DefinitionError::TooLarge(
+                "totient".to_string(),
+                10_000_000_000_000
+            )),
+            bind("totient(10000000000000)")
+        );
+        assert_eq!(
+            Err(DefinitionError::NotANumber("factor".to_string())),
+            bind("factor(84)")
+        );
+        assert_eq!(1, eval(&definitions, "is_prime(3a4)"));
+        assert_eq!(
+            Err(DefinitionError::Arity("is_prime".to_string(), 1, 2)),
+            bind("is_prime(1, 2)")
+        );
+    }
+
+    #[test]
+    fn test_bit_fields() {
+        let definitions = Definitions::parse(DEFINITIONS).unwrap();
+        assert_eq!(1, eval(&definitions, "bit(5, 2)"));
+        assert_eq!(0, eval(&definitions, "bit(5, 1)"));
+        assert_eq!(13, eval(&definitions, "bits(213, 7, 4)"));
+        assert_eq!(5, eval(&definitions, "popcount(213)"));
+        assert_eq!(
+            18,
+            eval(&definitions, "bits(213, 3, 0) c 2 a popcount(255)")
+        );
+        assert_eq!(
+            Err(DefinitionError::MalformedRange("bits".to_string())),
+            bind("bits(213, 4, 7)")
+        );
+        assert_eq!(
+            Err(DefinitionError::Arity("bit".to_string(), 2, 1)),
+            bind("bit(5)")
+        );
+    }
+
+    #[test]
+    fn test_overflow_builtins() {
+        let definitions = Definitions::parse(DEFINITIONS).unwrap();
+        assert_eq!(0, eval(&definitions, "sub_sat(3, 5)"));
+        assert_eq!(usize::MAX, eval(&definitions, "sub_wrap(3, 4)"));
+        assert_eq!(
+            usize::MAX,
+            eval(&definitions, "mul_sat(18446744073709551615, 2)")
+        );
+        assert_eq!(1, eval(&definitions, "add_wrap(18446744073709551615, 2)"));
+        assert_eq!(7, eval(&definitions, "add_sat(3, 2) a mul_wrap(1, 2)"));
+        assert_eq!(3, eval(&definitions, "add_wrap(18446744073709551615, 2c2)"));
+        assert_eq!(
+            Err(DefinitionError::Arity("add_sat".to_string(), 2, 1)),
+            bind("add_sat(3)")
+        );
+        assert_eq!(Ok("div_sat(3, 2)".to_string()), bind("div_sat(3, 2)"));
+    }
+
+    #[test]
+    fn test_combinatorics() {
+        let definitions = Definitions::parse(DEFINITIONS).unwrap();
+        assert_eq!(10, eval(&definitions, "choose(5, 2)"));
+        assert_eq!(20, eval(&definitions, "perm(5, 2)"));
+        assert_eq!(120, eval(&definitions, "fact(5)"));
+        assert_eq!(1, eval(&definitions, "fact(0)"));
+        assert_eq!(
+            32,
+            eval(&definitions, "let n = 5 in sum(k, 0, n, choose(n, k))")
+        );
+        assert_eq!(
+            Err(DefinitionError::TooLarge("fact".to_string(), 21)),
+            bind("fact(21)")
+        );
+        assert_eq!(
+            Err(DefinitionError::TooLarge("choose".to_string(), 68)),
+            bind("choose(68, 34)")
+        );
+        assert_eq!(
+            Err(DefinitionError::Arity("perm".to_string(), 2, 1)),
+            bind("perm(5)")
+        );
+    }
+
+    #[test]
+    fn test_rand() {
+        let definitions = Definitions::default().with_seed(42);
+        let expanded = definitions
+            .expand("rand(1, 6) a rand(1, 6) a rand(1, 6)")
+            .unwrap();
+        // The same seed draws the same numbers
+        assert_eq!(
+            expanded,
+            definitions
+                .expand("rand(1, 6) a rand(1, 6) a rand(1, 6)")
+                .unwrap()
+        );
+        let draws: Vec<usize> = expanded
+            .split('a')
+            .map(|draw| draw.parse().unwrap())
+            .collect();
+        assert!(draws.iter().all(|draw| (1..=6).contains(draw)));
+        assert_ne!(
+            expanded,
+            Definitions::default()
+                .with_seed(7)
+                .expand("rand(1, 6) a rand(1, 6) a rand(1, 6)")
+                .unwrap()
+        );
+        // A bound draw is the same wherever it is used
+        let expanded = definitions
+            .expand("let r = rand(1, 1000) in r b r")
+            .unwrap();
+        assert_eq!(0, Parser::new(expanded).parse().unwrap());
+        assert_eq!("5", definitions.expand("rand(5, 5)").unwrap());
+        assert!(definitions
+            .expand(&format!("rand(0, {})", usize::MAX))
+            .is_ok());
+        assert_eq!(
+            Err(DefinitionError::MalformedRange("rand".to_string())),
+            definitions.expand("rand(6, 1)")
+        );
+        assert_eq!(
+            Err(DefinitionError::MalformedBinding(
+                "letrand=1in1".to_string()
+            )),
+            definitions.expand("let rand = 1 in 1")
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+use std::ops::Range;
+
+/// The severity of a diagnostic message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Input accepted despite being suspicious, e.g. operands with leading zeros
+    Warn,
+    /// The outcome of a parse process
+    Debug,
+    /// Every step of a parse process
+    Trace,
+}
+
+/// A non-fatal notice about a span of the expression, for linters and editors showing it inline
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Notice {
+    /// Parentheses which change nothing, around a single number (e.g. `e2f`) or another group
+    /// (e.g. `ee3a2ff`), spanning from the opening to the closing parenthesis, in characters
+    RedundantParentheses(Range<usize>),
+}
+
+/// Implementation of the notices
+impl Notice {
+    /// The characters of the expression the notice is about
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Self::RedundantParentheses(span) => span.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Notice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RedundantParentheses(span) => write!(
+                f,
+                "redundant parentheses at {}..{} change nothing",
+                span.start, span.end
+            ),
+        }
+    }
+}
+
+/// Receives the diagnostic messages of the `Parser`, so that embedders with their own logging
+/// can collect them. The default sink forwards them to the `log` crate, with the `log` feature,
+/// and discards them otherwise.
+pub trait DiagnosticsSink {
+    /// Whether messages of a level are wanted, so that the others are not even formatted
+    /// # Arguments
+    ///  - level: The `Level` of the messages
+    fn enabled(&self, _level: Level) -> bool {
+        true
+    }
+
+    /// A diagnostic message was issued
+    /// # Arguments
+    ///  - level: The `Level` of the message
+    ///  - message: The message, formatted lazily
+    fn diagnostic(&self, level: Level, message: fmt::Arguments);
+
+    /// A notice was issued, when warnings are wanted. By default, it is issued as a warning
+    /// message, so that sinks wanting the span override it.
+    /// # Arguments
+    ///  - notice: The `Notice`
+    fn notice(&self, notice: &Notice) {
+        self.diagnostic(Level::Warn, format_args!("{}", notice));
+    }
+}
+
+/// The sink discarding the diagnostic messages, the default without the `log` feature
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullSink;
+
+impl DiagnosticsSink for NullSink {
+    fn enabled(&self, _level: Level) -> bool {
+        false
+    }
+
+    fn diagnostic(&self, _level: Level, _message: fmt::Arguments) {}
+}
+
+/// The sink forwarding the diagnostic messages to the `log` crate
+#[cfg(feature = "log")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LogSink;
+
+#[cfg(feature = "log")]
+impl DiagnosticsSink for LogSink {
+    fn enabled(&self, level: Level) -> bool {
+        log::log_enabled!(Self::log_level(level))
+    }
+
+    fn diagnostic(&self, level: Level, message: fmt::Arguments) {
+        log::log!(Self::log_level(level), "{}", message);
+    }
+}
+
+/// Implementation of the `log` sink
+#[cfg(feature = "log")]
+impl LogSink {
+    /// The `log` level of a diagnostic level
+    fn log_level(level: Level) -> log::Level {
+        match level {
+            Level::Warn => log::Level::Warn,
+            Level::Debug => log::Level::Debug,
+            Level::Trace => log::Level::Trace,
+        }
+    }
+}
+
+/// The sink of the parsers not given one, forwarding to the `log` crate
+#[cfg(feature = "log")]
+pub(crate) fn default_sink() -> Box<dyn DiagnosticsSink> {
+    Box::new(LogSink)
+}
+
+/// The sink of the parsers not given one, discarding the messages without the `log` feature
+#[cfg(not(feature = "log"))]
+pub(crate) fn default_sink() -> Box<dyn DiagnosticsSink> {
+    Box::new(NullSink)
+}
+
+/// Issue a diagnostic message through the sink of a parser, formatting it only if the sink wants it
+/// (e.g. `diagnostic!(self, Trace, "a = {}", acc)`)
+macro_rules! diagnostic {
+    ($parser:expr, $level:ident, $($arg:tt)+) => {
+        $parser.diagnostic($crate::diagnostics::Level::$level, format_args!($($arg)+))
+    };
+}
+
+pub(crate) use diagnostic;
@@ -0,0 +1,204 @@
+use crate::events::ParseEvent;
+use crate::operation::Operation;
+
+/// How the result of an expression whose last operation is a division is printed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DivisionFormat {
+    /// The quotient only, truncating the remainder (e.g. `3`)
+    #[default]
+    Truncate,
+    /// The quotient and the remainder (e.g. `3 r 1`)
+    Remainder,
+    /// The quotient and the reduced fraction left (e.g. `3 1/2`)
+    Fraction,
+}
+
+/// The result of an expression, with the remainder of its last operation when it is a division
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Division {
+    /// The result of the expression
+    pub quotient: usize,
+    /// The remainder of the last division, 0 if the last operation is not a division
+    pub remainder: usize,
+    /// The divisor of the last division, 1 if the last operation is not a division
+    pub divisor: usize,
+}
+
+/// Implementation of the division results
+impl Division {
+    /// Compute the division result from the events of a parse process
+    /// # Arguments
+    ///  - result: The result of the expression
+    ///  - events: The `ParseEvent`s recorded during the parse process
+    /// # Return
+    /// The `Division` of the expression
+    pub fn from_events(result: usize, events: &[ParseEvent]) -> Self {
+        // The last operation applied is the outermost one, since groups are closed before
+        let last = events.iter().rev().find_map(|event| match event {
+            ParseEvent::OperationApplied(operation, second_operand, _) => {
+                Some((*operation, *second_operand))
+            }
+            _ => None,
+        });
+        match last {
+            Some((Operation::Div(first_operand), divisor)) if divisor > 0 => Self {
+                quotient: result,
+                // A quotient rounded up leaves no remainder to print
+                remainder: result
+                    .checked_mul(divisor)
+                    .and_then(|product| first_operand.checked_sub(product))
+                    .unwrap_or(0),
+                divisor,
+            },
+            _ => Self {
+                quotient: result,
+                remainder: 0,
+                divisor: 1,
+            },
+        }
+    }
+
+    /// Render the division result
+    /// # Arguments
+    ///  - format: The `DivisionFormat` to render
+    ///  - quotient: The quotient already rendered, e.g. as a Roman numeral
+    /// # Return
+    /// The rendered result, which is the quotient alone when there is no remainder
+    pub fn render(&self, format: DivisionFormat, quotient: String) -> String {
+        match format {
+            _ if self.remainder == 0 => quotient,
+            DivisionFormat::Truncate => quotient,
+            DivisionFormat::Remainder => format!("{} r {}", quotient, self.remainder),
+            DivisionFormat::Fraction => {
+                let divisor = gcd(self.remainder, self.divisor);
+                format!(
+                    "{} {}/{}",
+                    quotient,
+                    self.remainder / divisor,
+                    self.divisor / divisor
+                )
+            }
+        }
+    }
+
+    /// Render the exact value of the division result with a number of decimal places, rounding
+    /// half up the digits beyond them. Only the output is rounded, the quotient being the result
+    /// the parser computed: a quotient it rounded up has no remainder left, and gets zero digits.
+    /// # Arguments
+    ///  - places: The number of decimal places, `0` for a whole number
+    /// # Return
+    /// The rendered result (e.g. `3.50` for `7d2` with 2 places, `4` with none)
+    pub fn round_to(&self, places: usize) -> String {
+        // The remainder is below the divisor, so that ten times it cannot overflow
+        let divisor = self.divisor as u128;
+        let mut remainder = self.remainder as u128;
+        let mut digits = Vec::with_capacity(places);
+        for _ in 0..places {
+            remainder *= 10;
+            digits.push((remainder / divisor) as u8);
+            remainder %= divisor;
+        }
+        let mut quotient = self.quotient;
+        if remainder > 0 && remainder * 2 >= divisor {
+            match digits.iter().rposition(|digit| *digit < 9) {
+                Some(position) => {
+                    digits[position] += 1;
+                    digits[position + 1..].fill(0);
+                }
+                None => {
+                    digits.fill(0);
+                    quotient = quotient.saturating_add(1);
+                }
+            }
+        }
+        match places {
+            0 => quotient.to_string(),
+            _ => {
+                let digits: String = digits
+                    .iter()
+                    .map(|digit| char::from(b'0' + digit))
+                    .collect();
+                format!("{}.{}", quotient, digits)
+            }
+        }
+    }
+}
+
+/// The greatest common divisor of two numbers
+fn gcd(a: usize, b: usize) -> usize {
+    match b {
+        0 => a,
+        b => gcd(b, a % b),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::division::{Division, DivisionFormat};
+    use crate::options::{ParserOptions, RoundingMode};
+    use crate::parser::Parser;
+
+    fn division(expression: &str) -> Division {
+        let (result, events) = Parser::new(expression.to_string()).parse_with_events();
+        Division::from_events(result.unwrap(), &events)
+    }
+
+    #[test]
+    fn test_from_events() {
+        let expected = Division {
+            quotient: 3,
+            remainder: 1,
+            divisor: 2,
+        };
+        assert_eq!(expected, division("7d2"));
+        assert_eq!(expected, division("e3a4fd2"));
+        assert_eq!(
+            Division {
+                quotient: 2,
+                remainder: 2,
+                divisor: 3
+            },
+            division("8de1a2f")
+        );
+        assert_eq!(0, division("7d2a1").remainder);
+        assert_eq!(0, division("7").remainder);
+
+        let options = ParserOptions {
+            rounding: RoundingMode::Ceil,
+            ..Default::default()
+        };
+        let (result, events) = Parser::new("7d2".to_string())
+            .with_options(options)
+            .parse_with_events();
+        assert_eq!(0, Division::from_events(result.unwrap(), &events).remainder);
+    }
+
+    #[test]
+    fn test_render() {
+        let render = |expression, format| division(expression).render(format, "Q".to_string());
+        assert_eq!("Q", render("7d2", DivisionFormat::Truncate));
+        assert_eq!("Q r 1", render("7d2", DivisionFormat::Remainder));
+        assert_eq!("Q 1/2", render("7d2", DivisionFormat::Fraction));
+        assert_eq!("Q 1/3", render("14d6", DivisionFormat::Fraction));
+        assert_eq!("Q", render("8d2", DivisionFormat::Fraction));
+    }
+
+    #[test]
+    fn test_round_to() {
+        assert_eq!("3.50", division("7d2").round_to(2));
+        assert_eq!("4", division("7d2").round_to(0));
+        assert_eq!("3.3333", division("10d3").round_to(4));
+        assert_eq!("6.667", division("20d3").round_to(3));
+        assert_eq!("2.0", division("199d100").round_to(1));
+        assert_eq!("5.000", division("3a2").round_to(3));
+        assert_eq!(
+            "0.5",
+            Division {
+                quotient: 0,
+                remainder: usize::MAX / 2 + 1,
+                divisor: usize::MAX,
+            }
+            .round_to(1)
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use crate::json;
+use crate::operation::Operation;
+use crate::parser::ParserState;
+
+/// The events the parser goes through while parsing an expression.
+/// Positions are character offsets in the expression, after Roman numerals and cell references
+/// have been expanded into decimal operands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseEvent {
+    /// A character of the expression was consumed (position, character)
+    Consumed(usize, char),
+    /// The parser moved to another state (previous state, new state)
+    StateChanged(ParserState, ParserState),
+    /// An operand was completely read (position of its first digit, value)
+    OperandParsed(usize, usize),
+    /// An operation was applied to its second operand (operation, second operand, result)
+    OperationApplied(Operation, usize, usize),
+    /// A parenthesis was opened, entering a nested expression (nesting depth)
+    GroupOpened(usize),
+    /// A parenthesis was closed, leaving a nested expression (nesting depth, result)
+    GroupClosed(usize, usize),
+}
+
+/// Implementation of the parse events
+impl ParseEvent {
+    /// Serialize the event as a JSON object
+    /// # Return
+    /// The JSON text, having the event name in the `event` field
+    pub fn to_json(self) -> String {
+        match self {
+            Self::Consumed(position, char) => format!(
+                "{{\"event\":\"consumed\",\"position\":{},\"char\":{}}}",
+                position,
+                json::string(&char.to_string())
+            ),
+            Self::StateChanged(from, to) => format!(
+                "{{\"event\":\"state_changed\",\"from\":\"{:?}\",\"to\":\"{:?}\"}}",
+                from, to
+            ),
+            Self::OperandParsed(position, value) => format!(
+                "{{\"event\":\"operand_parsed\",\"position\":{},\"value\":{}}}",
+                position, value
+            ),
+            Self::OperationApplied(operation, second_operand, result) => format!(
+                "{{\"event\":\"operation_applied\",\"operation\":\"{:?}\",\"second_operand\":{},\"result\":{}}}",
+                operation, second_operand, result
+            ),
+            Self::GroupOpened(depth) => {
+                format!("{{\"event\":\"group_opened\",\"depth\":{}}}", depth)
+            }
+            Self::GroupClosed(depth, result) => format!(
+                "{{\"event\":\"group_closed\",\"depth\":{},\"result\":{}}}",
+                depth, result
+            ),
+        }
+    }
+}
+
+/// Serialize a sequence of events as a JSON array, one event per line
+/// # Arguments
+///  - events: The events to serialize
+/// # Return
+/// The JSON text
+pub fn to_json(events: &[ParseEvent]) -> String {
+    let events: Vec<String> = events
+        .iter()
+        .map(|event| format!("  {}", event.to_json()))
+        .collect();
+    format!("[\n{}\n]", events.join(",\n"))
+}
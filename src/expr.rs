@@ -0,0 +1,749 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::operation::{codes::*, Operation, OperationError};
+
+/// The initial value of the 64 bit FNV-1a hash
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+/// The multiplier of the 64 bit FNV-1a hash
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+/// The operand of a pattern matching any subtree
+const WILDCARD: char = '_';
+
+/// An arithmetic expression tree, which can be built programmatically, rendered and evaluated
+/// ```
+/// # use arithmetic_parser::expr::Expr;
+/// let expr = Expr::num(3).add(Expr::num(2)).mul(4);
+/// assert_eq!("3a2c4", expr.to_code());
+/// assert_eq!("(3 + 2) * 4", expr.to_string());
+/// assert_eq!(Ok(20), expr.eval());
+/// ```
+/// Expressions are ordered structurally: numbers first, by value, then operations, by operation
+/// code and operands, then errors, by position. Structurally different expressions are unequal
+/// even when they evaluate alike, see `canonical` to compare them regardless of operand order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Expr {
+    /// A numeric operand
+    Num(usize),
+    /// A binary operation (operation code, first operand, second operand)
+    Binary(char, Box<Expr>, Box<Expr>),
+    /// An invalid region of a parsed expression in place of an operand (start position, end position)
+    Error(usize, usize),
+}
+
+/// An expression whose `_` operands are wildcards, each matching any subtree, e.g. `_c0` matches
+/// every multiplication by zero. Operations are applied from left to right as in expressions, so
+/// `_c0` also matches `3a2c0`, with the wildcard standing for `3a2`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern(Expr);
+
+/// Implementation of the patterns
+impl Pattern {
+    /// Parses a pattern, ignoring whitespace so that `_ c 0` reads as `_c0`
+    /// # Arguments
+    ///  - pattern: The coded pattern, having decimal operands and wildcards
+    /// # Return
+    /// An `Option` having the `Pattern` if every operand is a number, a group or a wildcard,
+    /// `None` otherwise
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let chars: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+        let code: String = chars.iter().collect();
+        let expr = Expr::parse_tolerant(&code);
+        // The wildcards are the only invalid operands, which the tree keeps as `Error` nodes
+        expr.errors()
+            .iter()
+            .all(|&(start, end)| chars[start..end] == [WILDCARD])
+            .then_some(Self(expr))
+    }
+
+    /// The number of wildcards of the pattern
+    pub fn wildcards(&self) -> usize {
+        self.0.errors().len()
+    }
+
+    /// Builds the expression of a template, its wildcards taking the captured subtrees from left
+    /// to right. The wildcards left without a subtree stay wildcards.
+    fn fill(template: &Expr, captures: &mut impl Iterator<Item = Expr>) -> Expr {
+        match template {
+            Expr::Error(..) => captures.next().unwrap_or_else(|| template.clone()),
+            Expr::Binary(code, first, second) => {
+                let first = Self::fill(first, captures);
+                Expr::Binary(
+                    *code,
+                    Box::new(first),
+                    Box::new(Self::fill(second, captures)),
+                )
+            }
+            expr => expr.clone(),
+        }
+    }
+
+    /// Whether the pattern matches an expression, collecting the subtrees its wildcards stand
+    /// for from left to right
+    fn matches<'a>(pattern: &Expr, expr: &'a Expr, captures: &mut Vec<&'a Expr>) -> bool {
+        match (pattern, expr) {
+            (Expr::Error(..), expr) => {
+                captures.push(expr);
+                true
+            }
+            (Expr::Num(wanted), Expr::Num(value)) => wanted == value,
+            (
+                Expr::Binary(wanted, pattern_first, pattern_second),
+                Expr::Binary(code, first, second),
+            ) => {
+                wanted == code
+                    && Self::matches(pattern_first, first, captures)
+                    && Self::matches(pattern_second, second, captures)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<usize> for Expr {
+    fn from(value: usize) -> Self {
+        Self::Num(value)
+    }
+}
+
+/// Implementation of the expression tree
+// The builder methods are named after the operations, like `gcd` and `lcm` which have no operator
+#[allow(clippy::should_implement_trait)]
+impl Expr {
+    /// Creates a numeric operand
+    pub fn num(value: usize) -> Self {
+        Self::Num(value)
+    }
+
+    /// Creates the addition of this expression and another
+    pub fn add(self, other: impl Into<Expr>) -> Self {
+        self.binary(OPCODE_ADD, other)
+    }
+
+    /// Creates the subtraction of another expression from this one
+    pub fn sub(self, other: impl Into<Expr>) -> Self {
+        self.binary(OPCODE_SUB, other)
+    }
+
+    /// Creates the multiplication of this expression by another
+    pub fn mul(self, other: impl Into<Expr>) -> Self {
+        self.binary(OPCODE_MUL, other)
+    }
+
+    /// Creates the division of this expression by another
+    pub fn div(self, other: impl Into<Expr>) -> Self {
+        self.binary(OPCODE_DIV, other)
+    }
+
+    /// Creates the greatest common divisor of this expression and another
+    pub fn gcd(self, other: impl Into<Expr>) -> Self {
+        self.binary(OPCODE_GCD, other)
+    }
+
+    /// Creates the least common multiple of this expression and another
+    pub fn lcm(self, other: impl Into<Expr>) -> Self {
+        self.binary(OPCODE_LCM, other)
+    }
+
+    /// Creates a binary operation having this expression as first operand
+    fn binary(self, code: char, other: impl Into<Expr>) -> Self {
+        Self::Binary(code, Box::new(self), Box::new(other.into()))
+    }
+
+    /// Evaluates the expression
+    /// # Return
+    /// A `Result` having the value of the expression if valid, `OperationError` otherwise
+    pub fn eval(&self) -> Result<usize, OperationError> {
+        match self {
+            Self::Num(value) => Ok(*value),
+            Self::Binary(code, first, second) => {
+                Operation::from_result(*code, first.eval()?)?.apply_result(second.eval()?)
+            }
+            Self::Error(start, end) => Err(OperationError::InvalidOperand {
+                start: *start,
+                end: *end,
+            }),
+        }
+    }
+
+    /// Parses an expression without ever failing, for editors validating partial input. Each
+    /// operand spans up to the next operation code, or closing parenthesis of an open group, and
+    /// becomes an `Error` node if it is not a valid number or group: the rest of the tree is built
+    /// around it.
+    /// # Arguments
+    ///  - expression: The coded expression, having decimal operands
+    /// # Return
+    /// The expression tree, whose positions are character offsets in the expression
+    pub fn parse_tolerant(expression: &str) -> Self {
+        let chars: Vec<char> = expression.chars().collect();
+        let mut position = 0;
+        Self::tolerant_sequence(&chars, &mut position, 0)
+    }
+
+    /// Parses operands separated by operation codes, up to the end of the group at the given depth
+    fn tolerant_sequence(chars: &[char], position: &mut usize, depth: usize) -> Self {
+        let mut expr = Self::tolerant_operand(chars, position, depth);
+        while let Some(&code) = chars.get(*position) {
+            if !Self::is_operation(code) {
+                break;
+            }
+            *position += 1;
+            let second = Self::tolerant_operand(chars, position, depth);
+            expr = Self::Binary(code, Box::new(expr), Box::new(second));
+        }
+        expr
+    }
+
+    /// Parses an operand, either a number or a group, or an `Error` spanning the invalid region
+    fn tolerant_operand(chars: &[char], position: &mut usize, depth: usize) -> Self {
+        let start = *position;
+        let operand = match chars.get(start) {
+            Some(char) if char.is_ascii_digit() => {
+                while chars.get(*position).is_some_and(char::is_ascii_digit) {
+                    *position += 1;
+                }
+                let digits: String = chars[start..*position].iter().collect();
+                digits.parse().ok().map(Self::Num)
+            }
+            Some(&OPCODE_OPEN) => {
+                *position += 1;
+                let group = Self::tolerant_sequence(chars, position, depth + 1);
+                match chars.get(*position) {
+                    Some(&OPCODE_CLOSE) => {
+                        *position += 1;
+                        Some(group)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        let mut invalid = operand.is_none();
+        while let Some(&char) = chars.get(*position) {
+            if Self::is_operation(char) || (char == OPCODE_CLOSE && depth > 0) {
+                break;
+            }
+            *position += 1;
+            invalid = true;
+        }
+        match operand {
+            Some(operand) if !invalid => operand,
+            _ => Self::Error(start, *position),
+        }
+    }
+
+    /// Whether a character is the code of a binary operation
+    fn is_operation(char: char) -> bool {
+        matches!(
+            char,
+            OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD | OPCODE_LCM
+        )
+    }
+
+    /// Evaluates every part of the expression free of errors, leaving only the `Error` nodes and
+    /// the operations that depend on them, or fail
+    /// # Return
+    /// The partially evaluated expression
+    pub fn fold(&self) -> Self {
+        if let Ok(value) = self.eval() {
+            return Self::Num(value);
+        }
+        match self {
+            Self::Binary(code, first, second) => {
+                Self::Binary(*code, Box::new(first.fold()), Box::new(second.fold()))
+            }
+            expr => expr.clone(),
+        }
+    }
+
+    /// Finds the subtrees matching a pattern, e.g. `_c0` for the multiplications by zero.
+    /// The tree keeps no track of the text it was parsed from, so the spans are character ranges
+    /// in the output of `to_code`, not in that text: redundant parentheses and leading zeros, for
+    /// instance, are not part of the code.
+    /// # Arguments
+    ///  - pattern: The `Pattern` to look for
+    /// # Return
+    /// The span in the output of `to_code` and the subtree of every match, from left to right,
+    /// a match enclosing others coming before them
+    pub fn find_all(&self, pattern: &Pattern) -> Vec<(Range<usize>, &Expr)> {
+        let mut found = Vec::new();
+        self.find_at(pattern, 0, &mut found);
+        found
+    }
+
+    /// Replaces the subtrees matching a pattern, e.g. `_c1` by `_` to drop the multiplications by
+    /// one. The operands are rewritten before the operations applied to them, so that `3c1c1`
+    /// becomes `3` at once.
+    /// # Arguments
+    ///  - pattern: The `Pattern` to look for
+    ///  - template: The `Pattern` replacing the matches, whose wildcards take, from left to right,
+    ///    the subtrees matched by those of the pattern
+    /// # Return
+    /// The rewritten expression
+    pub fn replace(&self, pattern: &Pattern, template: &Pattern) -> Self {
+        let expr = match self {
+            Self::Binary(code, first, second) => Self::Binary(
+                *code,
+                Box::new(first.replace(pattern, template)),
+                Box::new(second.replace(pattern, template)),
+            ),
+            expr => expr.clone(),
+        };
+        let mut captures = Vec::new();
+        if !Pattern::matches(&pattern.0, &expr, &mut captures) {
+            return expr;
+        }
+        let mut captures = captures.into_iter().cloned();
+        Pattern::fill(&template.0, &mut captures)
+    }
+
+    /// Finds the subtrees matching a pattern in the node, whose code starts at the given position
+    fn find_at<'a>(
+        &'a self,
+        pattern: &Pattern,
+        start: usize,
+        found: &mut Vec<(Range<usize>, &'a Expr)>,
+    ) {
+        if Pattern::matches(&pattern.0, self, &mut Vec::new()) {
+            found.push((start..start + self.code_len(), self));
+        }
+        if let Self::Binary(_, first, second) = self {
+            first.find_at(pattern, start, found);
+            // The operation code follows the first operand, then a group opens if needed
+            let group = matches!(second.as_ref(), Self::Binary(..)) as usize;
+            second.find_at(pattern, start + first.code_len() + 1 + group, found);
+        }
+    }
+
+    /// The number of characters of the code of the expression, as rendered by `to_code`
+    fn code_len(&self) -> usize {
+        match self {
+            Self::Num(value) => value.to_string().len(),
+            Self::Error(..) => 1,
+            Self::Binary(_, first, second) => {
+                let group = matches!(second.as_ref(), Self::Binary(..)) as usize;
+                first.code_len() + 1 + second.code_len() + 2 * group
+            }
+        }
+    }
+
+    /// Collects the invalid regions of the expression
+    /// # Return
+    /// The (start position, end position) of every `Error` node, from left to right
+    pub fn errors(&self) -> Vec<(usize, usize)> {
+        match self {
+            Self::Num(_) => Vec::new(),
+            Self::Binary(_, first, second) => {
+                let mut errors = first.errors();
+                errors.extend(second.errors());
+                errors
+            }
+            Self::Error(start, end) => vec![(*start, *end)],
+        }
+    }
+
+    /// The canonical form of the expression, where the operands of the commutative operations
+    /// (addition, multiplication, greatest common divisor and least common multiple) are sorted
+    /// by their code, so that e.g. `3a2` and `2a3` have the same canonical form
+    /// # Return
+    /// The canonical expression, which evaluates as this one
+    pub fn canonical(&self) -> Self {
+        match self {
+            Self::Binary(code, first, second) => {
+                let (mut first, mut second) = (first.canonical(), second.canonical());
+                let commutative =
+                    matches!(*code, OPCODE_ADD | OPCODE_MUL | OPCODE_GCD | OPCODE_LCM);
+                if commutative && first.to_code() > second.to_code() {
+                    (first, second) = (second, first);
+                }
+                Self::Binary(*code, Box::new(first), Box::new(second))
+            }
+            expr => expr.clone(),
+        }
+    }
+
+    /// A fingerprint of the canonical form of the expression, which is stable across runs, builds
+    /// and versions, so that caches can key results by it. It is the 64 bit FNV-1a hash of the
+    /// canonical code, where the `Error` nodes are all alike whatever their positions.
+    /// # Return
+    /// The fingerprint
+    pub fn fingerprint(&self) -> u64 {
+        self.canonical()
+            .to_code()
+            .bytes()
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            })
+    }
+
+    /// Renders the expression with the operation codes understood by the `Parser`.
+    /// Operations are applied from left to right, so only a second operand which is itself
+    /// an operation needs to be enclosed in parentheses.
+    /// # Return
+    /// The coded expression
+    pub fn to_code(&self) -> String {
+        match self {
+            Self::Num(value) => value.to_string(),
+            Self::Error(..) => "?".to_string(),
+            Self::Binary(code, first, second) => match second.as_ref() {
+                Self::Num(_) | Self::Error(..) => {
+                    format!("{}{}{}", first.to_code(), code, second.to_code())
+                }
+                _ => format!(
+                    "{}{}{}{}{}",
+                    first.to_code(),
+                    code,
+                    OPCODE_OPEN,
+                    second.to_code(),
+                    OPCODE_CLOSE
+                ),
+            },
+        }
+    }
+
+    /// Renders the tree one node per line, each indented by two spaces below its parent, for
+    /// snapshot tests. Unlike `Debug`, the rendering is part of the API, so it stays the same when
+    /// the enum changes.
+    /// # Return
+    /// The rendered tree, e.g. `Binary c\n  Binary a\n    Num 3\n    Num 2\n  Num 4` for `3a2c4`
+    pub fn to_pretty_debug(&self) -> String {
+        let mut lines = Vec::new();
+        self.pretty_debug(0, &mut lines);
+        lines.join("\n")
+    }
+
+    /// Renders the node at the given depth and its operands below it
+    fn pretty_debug(&self, depth: usize, lines: &mut Vec<String>) {
+        let indent = "  ".repeat(depth);
+        match self {
+            Self::Num(value) => lines.push(format!("{}Num {}", indent, value)),
+            Self::Error(start, end) => lines.push(format!("{}Error {}..{}", indent, start, end)),
+            Self::Binary(code, first, second) => {
+                lines.push(format!("{}Binary {}", indent, code));
+                first.pretty_debug(depth + 1, lines);
+                second.pretty_debug(depth + 1, lines);
+            }
+        }
+    }
+
+    /// Renders the expression in conventional notation like `Display`, wrapping the operations
+    /// which do not fit a width before their operators. The operations of a parenthesized operand
+    /// are indented by two spaces more than its parentheses, so that the nesting shows.
+    /// # Arguments
+    ///  - max_width: The largest number of characters of a line, exceeded only by numbers
+    /// # Return
+    /// The rendered expression, e.g. `(3 + 2) * 4` on one line, or for a width of 5:
+    /// `(\n  3\n  + 2\n)\n* 4`
+    pub fn to_wrapped(&self, max_width: usize) -> String {
+        let mut lines = Vec::new();
+        self.wrap(0, max_width, &mut lines);
+        lines.join("\n")
+    }
+
+    /// Renders the node at the given depth, on a single line if it fits the width
+    fn wrap(&self, depth: usize, max_width: usize, lines: &mut Vec<String>) {
+        let indent = "  ".repeat(depth);
+        let flat = self.to_string();
+        let Self::Binary(code, first, second) = self else {
+            lines.push(format!("{}{}", indent, flat));
+            return;
+        };
+        if indent.len() + flat.len() <= max_width {
+            lines.push(format!("{}{}", indent, flat));
+            return;
+        }
+        let (symbol, precedence) = Self::symbol(*code);
+        if first.precedence() < precedence {
+            first.wrap_group("", depth, max_width, lines);
+        } else {
+            first.wrap(depth, max_width, lines);
+        }
+        if second.precedence() > precedence {
+            lines.push(format!("{}{} {}", indent, symbol, second));
+        } else {
+            second.wrap_group(&format!("{} ", symbol), depth, max_width, lines);
+        }
+    }
+
+    /// Renders the node in parentheses at the given depth, following a prefix, on a single line
+    /// if it fits the width
+    fn wrap_group(&self, prefix: &str, depth: usize, max_width: usize, lines: &mut Vec<String>) {
+        let indent = "  ".repeat(depth);
+        let line = format!("{}{}({})", indent, prefix, self);
+        if line.len() <= max_width {
+            lines.push(line);
+        } else {
+            lines.push(format!("{}{}(", indent, prefix));
+            self.wrap(depth + 1, max_width, lines);
+            lines.push(format!("{})", indent));
+        }
+    }
+
+    /// The conventional symbol and precedence of an operation code
+    fn symbol(code: char) -> (&'static str, u8) {
+        match code {
+            OPCODE_ADD => ("+", 1),
+            OPCODE_SUB => ("-", 1),
+            OPCODE_MUL => ("*", 2),
+            OPCODE_DIV => ("/", 2),
+            OPCODE_GCD => ("gcd", 3),
+            OPCODE_LCM => ("lcm", 3),
+            _ => ("?", 0),
+        }
+    }
+
+    /// The precedence of the expression in conventional notation, the highest for operands
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Num(_) | Self::Error(..) => u8::MAX,
+            Self::Binary(code, ..) => Self::symbol(*code).1,
+        }
+    }
+}
+
+/// Renders the expression in conventional notation, where multiplication and division
+/// take precedence over addition and subtraction
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Num(value) => write!(f, "{}", value),
+            Self::Error(..) => write!(f, "?"),
+            Self::Binary(code, first, second) => {
+                let (symbol, precedence) = Self::symbol(*code);
+                if first.precedence() < precedence {
+                    write!(f, "({})", first)?;
+                } else {
+                    write!(f, "{}", first)?;
+                }
+                write!(f, " {} ", symbol)?;
+                if second.precedence() <= precedence {
+                    write!(f, "({})", second)
+                } else {
+                    write!(f, "{}", second)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeSet, HashSet};
+    use std::ops::Range;
+
+    use crate::expr::{Expr, Pattern};
+    use crate::operation::OperationError::{InvalidOperand, OverflowError};
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_builder() {
+        let expr = Expr::num(3).add(Expr::num(2)).mul(4);
+        assert_eq!("3a2c4", expr.to_code());
+        assert_eq!("(3 + 2) * 4", expr.to_string());
+        assert_eq!(Ok(20), expr.eval());
+
+        let expr = Expr::num(3).add(Expr::num(4).mul(66)).sub(32);
+        assert_eq!("3ae4c66fb32", expr.to_code());
+        assert_eq!("3 + 4 * 66 - 32", expr.to_string());
+        assert_eq!(Ok(235), expr.eval());
+
+        let expr = Expr::num(100)
+            .div(Expr::num(10).div(2))
+            .sub(Expr::num(3).sub(1));
+        assert_eq!("100de10d2fbe3b1f", expr.to_code());
+        assert_eq!("100 / (10 / 2) - (3 - 1)", expr.to_string());
+        assert_eq!(Ok(18), expr.eval());
+
+        let expr = Expr::num(2).mul(Expr::num(9).lcm(12)).gcd(48);
+        assert_eq!("2ce9h12fg48", expr.to_code());
+        assert_eq!("(2 * 9 lcm 12) gcd 48", expr.to_string());
+        assert_eq!(Ok(24), expr.eval());
+
+        assert_eq!(Err(OverflowError), Expr::num(1).div(0).eval());
+    }
+
+    #[test]
+    fn test_code_round_trip() {
+        let exprs = [
+            Expr::num(3).add(Expr::num(2)).mul(4),
+            Expr::num(3).add(Expr::num(4).mul(66)).sub(32),
+            Expr::num(100)
+                .div(Expr::num(10).div(2))
+                .sub(Expr::num(3).sub(1)),
+            Expr::num(2).mul(Expr::num(3).mul(Expr::num(4).add(1))),
+        ];
+        for expr in exprs {
+            let parser = Parser::new(expr.to_code());
+            assert_eq!(
+                expr.eval(),
+                Ok(parser.parse().unwrap()),
+                "{}",
+                expr.to_code()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_tolerant() {
+        let expr = Expr::parse_tolerant("e3a2fc4");
+        assert_eq!(Expr::num(3).add(2).mul(4), expr);
+        assert!(expr.errors().is_empty());
+
+        let expr = Expr::parse_tolerant("3x1aeqc2fb1");
+        assert_eq!(Expr::Error(0, 3).add(Expr::Error(5, 6).mul(2)).sub(1), expr);
+        assert_eq!(vec![(0, 3), (5, 6)], expr.errors());
+        assert_eq!("? + ? * 2 - 1", expr.to_string());
+        assert_eq!(Err(InvalidOperand { start: 0, end: 3 }), expr.eval());
+
+        let expr = Expr::parse_tolerant("e3a2fc4ae1b");
+        assert_eq!(Expr::num(20).add(Expr::Error(8, 11)), expr.fold());
+
+        assert_eq!(
+            Expr::Error(0, 0).add(Expr::Error(1, 1)),
+            Expr::parse_tolerant("a")
+        );
+        assert_eq!(
+            Expr::num(3).add(Expr::Error(2, 4)),
+            Expr::parse_tolerant("3a2f")
+        );
+    }
+
+    #[test]
+    fn test_pretty_debug() {
+        assert_eq!(
+            "Binary c\n  Binary a\n    Num 3\n    Num 2\n  Num 4",
+            Expr::num(3).add(2).mul(4).to_pretty_debug()
+        );
+        assert_eq!(
+            "Binary b
+  Binary a
+    Error 0..3
+    Binary c
+      Error 5..6
+      Num 2
+  Num 1",
+            Expr::parse_tolerant("3x1aeqc2fb1").to_pretty_debug()
+        );
+        assert_eq!("Num 7", Expr::num(7).to_pretty_debug());
+    }
+
+    #[test]
+    fn test_wrapped() {
+        let expr = Expr::num(3).add(2).mul(4);
+        assert_eq!("(3 + 2) * 4", expr.to_wrapped(11));
+        assert_eq!("(3 + 2)\n* 4", expr.to_wrapped(10));
+        assert_eq!("(\n  3\n  + 2\n)\n* 4", expr.to_wrapped(5));
+        let expr = Expr::num(1000)
+            .add(2000)
+            .sub(300)
+            .mul(Expr::num(12).add(Expr::num(4).gcd(6)));
+        assert_eq!(
+            "(
+  1000 + 2000
+  - 300
+)
+* (
+  12 + 4 gcd 6
+)",
+            expr.to_wrapped(14)
+        );
+        assert_eq!("(1000 + 2000 - 300)\n* (12 + 4 gcd 6)", expr.to_wrapped(20));
+        assert_eq!("123456", Expr::num(123456).to_wrapped(3));
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let expr = Expr::num(3).add(Expr::num(2).mul(4));
+        assert_eq!(Expr::num(2).mul(4).add(3), expr.canonical());
+        assert_eq!(expr.fingerprint(), Expr::num(4).mul(2).add(3).fingerprint());
+        assert_ne!(
+            Expr::num(3).sub(2).fingerprint(),
+            Expr::num(2).sub(3).fingerprint()
+        );
+        assert_eq!(
+            Expr::parse_tolerant("3ax").fingerprint(),
+            Expr::parse_tolerant("3ayy").fingerprint()
+        );
+        // The fingerprint never changes, whatever the build
+        assert_eq!(0xaf63_ad4c_8601_9caf, Expr::num(0).fingerprint());
+    }
+
+    #[test]
+    fn test_collections() {
+        let exprs = [
+            Expr::num(3).add(2),
+            Expr::num(2).add(3),
+            Expr::num(3).add(2),
+            Expr::num(10),
+            Expr::Error(0, 1),
+        ];
+        let unique: HashSet<&Expr> = exprs.iter().collect();
+        assert_eq!(4, unique.len());
+        let canonical: HashSet<Expr> = exprs.iter().map(Expr::canonical).collect();
+        assert_eq!(3, canonical.len());
+        let sorted: Vec<Expr> = exprs
+            .into_iter()
+            .collect::<BTreeSet<Expr>>()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            vec![
+                Expr::num(10),
+                Expr::num(2).add(3),
+                Expr::num(3).add(2),
+                Expr::Error(0, 1),
+            ],
+            sorted
+        );
+    }
+
+    #[test]
+    fn test_find_all() {
+        let pattern = Pattern::parse("_ c 0").unwrap();
+        assert_eq!(1, pattern.wildcards());
+        let expr = Expr::parse_tolerant("3a2c0ae4c0f");
+        assert_eq!(
+            vec![
+                (0..5, &Expr::num(3).add(2).mul(0)),
+                (7..10, &Expr::num(4).mul(0)),
+            ],
+            expr.find_all(&pattern)
+        );
+        assert_eq!("3a2c0", &expr.to_code()[0..5]);
+        assert_eq!("4c0", &expr.to_code()[7..10]);
+        // The spans are in the code, whatever the text parsed
+        let expr = Expr::parse_tolerant("ee3a2fc0fae04c0f");
+        assert_eq!("3a2c0ae4c0f", expr.to_code());
+        let found: Vec<Range<usize>> = expr.find_all(&pattern).into_iter().map(|m| m.0).collect();
+        assert_eq!(vec![0..5, 7..10], found);
+
+        let expr = Expr::parse_tolerant("1a2a3");
+        let pattern = Pattern::parse("_a_").unwrap();
+        assert_eq!(2, pattern.wildcards());
+        let found: Vec<Range<usize>> = expr.find_all(&pattern).into_iter().map(|m| m.0).collect();
+        assert_eq!(vec![0..5, 0..3], found);
+        assert!(expr.find_all(&Pattern::parse("_b_").unwrap()).is_empty());
+        assert!(expr.find_all(&Pattern::parse("1a3").unwrap()).is_empty());
+
+        assert_eq!(None, Pattern::parse("_ax"));
+        assert_eq!(None, Pattern::parse("__c1"));
+    }
+
+    #[test]
+    fn test_replace() {
+        let pattern = Pattern::parse("_ c 1").unwrap();
+        let template = Pattern::parse("_").unwrap();
+        let expr = Expr::parse_tolerant("3c1c1ae4c1f");
+        assert_eq!(Expr::num(3).add(4), expr.replace(&pattern, &template));
+
+        let pattern = Pattern::parse("_a_").unwrap();
+        let template = Pattern::parse("_ c 2 b _").unwrap();
+        let expr = Expr::num(5).add(Expr::num(6).mul(7));
+        assert_eq!(
+            Expr::num(5).mul(2).sub(Expr::num(6).mul(7)),
+            expr.replace(&pattern, &template)
+        );
+        let unchanged = Expr::num(5).sub(6);
+        assert_eq!(unchanged, unchanged.replace(&pattern, &template));
+    }
+}
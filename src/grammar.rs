@@ -0,0 +1,290 @@
+use crate::operation::codes::*;
+use crate::options::{LeadingZeroPolicy, ParserOptions, TrailingInputPolicy};
+
+/// The operation codes, in the order the grammar lists them
+const OPERATIONS: [char; 6] = [
+    OPCODE_ADD, OPCODE_SUB, OPCODE_MUL, OPCODE_DIV, OPCODE_GCD, OPCODE_LCM,
+];
+
+/// The grammar of the expressions a parser accepts, written in ISO EBNF. Only the syntax enabled by
+/// the options appears, and the limits they set are noted as comments.
+/// # Arguments
+///  - options: The `ParserOptions` of the parser
+///  - cells: Whether the parser resolves cell references
+/// # Return
+/// The EBNF text, one rule per line
+pub fn ebnf(options: &ParserOptions, cells: bool) -> String {
+    let radix = options.radix.clamp(2, 36);
+    let mut rules = Vec::new();
+    if let Some(max_length) = options.max_length {
+        rules.push(format!("(* At most {} characters *)", max_length));
+    }
+    rules.push(match options.trailing_input {
+        TrailingInputPolicy::Lenient => "input = expression , { ? any character ? } ;".to_string(),
+        _ => "input = expression ;".to_string(),
+    });
+    if let Some(max_operations) = options.max_operations {
+        rules.push(format!("(* At most {} operations *)", max_operations));
+    }
+    rules.push("expression = operand , { operation , operand } ;".to_string());
+    let mut operands = vec!["number , [ \"%\" ]", "group"];
+    if options.roman_numerals {
+        operands.push("roman");
+    }
+    if cells {
+        operands.push("cell");
+    }
+    rules.push(format!("operand = {} ;", operands.join(" | ")));
+    if let Some(max_depth) = options.max_depth {
+        rules.push(format!("(* Groups nest at most {} deep *)", max_depth));
+    }
+    rules.push(format!(
+        "group = {} , expression , {} ;",
+        code(OPCODE_OPEN, options),
+        code(OPCODE_CLOSE, options)
+    ));
+    let operations: Vec<String> = OPERATIONS.iter().map(|c| code(*c, options)).collect();
+    rules.push(format!("operation = {} ;", operations.join(" | ")));
+    if let Some(max_digits) = options.max_digits {
+        rules.push(format!("(* Numbers have at most {} digits *)", max_digits));
+    }
+    rules.push(match options.leading_zeros {
+        LeadingZeroPolicy::Reject => "number = \"0\" | digit - \"0\" , { digit } ;".to_string(),
+        _ => "number = digit , { digit } ;".to_string(),
+    });
+    let digits: Vec<String> = (0..radix)
+        .filter_map(|digit| char::from_digit(digit, radix))
+        .map(|digit| quoted(digit.to_ascii_uppercase()))
+        .collect();
+    rules.push(format!("digit = {} ;", digits.join(" | ")));
+    if options.roman_numerals {
+        let symbols: Vec<String> = "IVXLCDM".chars().map(quoted).collect();
+        rules.push("roman = symbol , { symbol } ;".to_string());
+        rules.push(format!("symbol = {} ;", symbols.join(" | ")));
+    }
+    if cells {
+        rules.push("cell = letter , { letter } , row ;".to_string());
+        rules.push("row = decimal , { decimal } ;".to_string());
+        let letters: Vec<String> = ('A'..='Z').map(quoted).collect();
+        rules.push(format!("letter = {} ;", letters.join(" | ")));
+        let decimals: Vec<String> = ('0'..='9').map(quoted).collect();
+        rules.push(format!("decimal = {} ;", decimals.join(" | ")));
+    }
+    if radix > 10 && (options.roman_numerals || cells) {
+        rules
+            .push("(* Uppercase letters which are digits are read as numbers first *)".to_string());
+    }
+    if options.case_insensitive_opcodes {
+        rules.push(
+            "(* Uppercase operation codes are read before any other uppercase letter *)"
+                .to_string(),
+        );
+    }
+    rules.join("\n")
+}
+
+/// The roman numeral symbols, in the order the grammar lists them
+const SYMBOLS: [char; 7] = ['I', 'V', 'X', 'L', 'C', 'D', 'M'];
+
+/// What a partial expression may continue with
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expecting {
+    /// An operand, at the start of the expression, a group or after an operation
+    Operand,
+    /// More digits of a number, having the given count of digits and being `0` or not
+    Number(usize, bool),
+    /// More symbols of a Roman numeral
+    Roman,
+    /// An operation or the end of a group, after a percentage or a group
+    Operation,
+}
+
+/// The tokens which may legally follow a partial expression with the options of a parser, for
+/// shell completion scripts suggesting them while an expression is typed. Cell references are
+/// not suggested, their letters being unbounded.
+/// # Arguments
+///  - partial: The expression typed so far
+///  - options: The `ParserOptions` of the parser
+/// # Return
+/// The next tokens in the order the grammar lists them, empty if the partial expression cannot be
+/// continued into a valid one
+pub fn next_tokens(partial: &str, options: &ParserOptions) -> Vec<char> {
+    let radix = options.radix.clamp(2, 36);
+    let is_code = |char: char, code: char| {
+        char == code || (options.case_insensitive_opcodes && char == code.to_ascii_uppercase())
+    };
+    let is_digit = |char: char| !char.is_ascii_lowercase() && char.is_digit(radix);
+    let is_symbol = |char: char| options.roman_numerals && SYMBOLS.contains(&char);
+    let mut expecting = Expecting::Operand;
+    let mut depth = 0;
+    for char in partial.chars() {
+        let operand_ended = !matches!(expecting, Expecting::Operand);
+        expecting = match expecting {
+            _ if OPERATIONS.iter().any(|code| is_code(char, *code)) && operand_ended => {
+                Expecting::Operand
+            }
+            Expecting::Operand
+                if is_code(char, OPCODE_OPEN)
+                    && options.max_depth.is_none_or(|max| depth < max) =>
+            {
+                depth += 1;
+                Expecting::Operand
+            }
+            _ if is_code(char, OPCODE_CLOSE) && operand_ended && depth > 0 => {
+                depth -= 1;
+                Expecting::Operation
+            }
+            Expecting::Operand if is_digit(char) => Expecting::Number(1, char == '0'),
+            Expecting::Number(digits, zero)
+                if is_digit(char) && continues(digits, zero, options) =>
+            {
+                Expecting::Number(digits + 1, false)
+            }
+            Expecting::Number(..) if char == PERCENT => Expecting::Operation,
+            Expecting::Operand | Expecting::Roman if is_symbol(char) => Expecting::Roman,
+            _ => return Vec::new(),
+        };
+    }
+    let digits = (0..radix)
+        .filter_map(|digit| char::from_digit(digit, radix))
+        .map(|digit| digit.to_ascii_uppercase());
+    let symbols = SYMBOLS
+        .into_iter()
+        .filter(|char| is_symbol(*char) && !is_digit(*char));
+    let mut tokens = Vec::new();
+    match expecting {
+        Expecting::Operand => {
+            tokens.extend(digits);
+            tokens.extend(symbols);
+            if options.max_depth.is_none_or(|max| depth < max) {
+                tokens.push(OPCODE_OPEN);
+            }
+            return tokens;
+        }
+        Expecting::Number(count, zero) => {
+            if continues(count, zero, options) {
+                tokens.extend(digits);
+            }
+            tokens.push(PERCENT);
+        }
+        Expecting::Roman => tokens.extend(symbols),
+        Expecting::Operation => {}
+    }
+    tokens.extend(OPERATIONS);
+    if depth > 0 {
+        tokens.push(OPCODE_CLOSE);
+    }
+    tokens
+}
+
+/// Whether a number may take one more digit
+fn continues(digits: usize, zero: bool, options: &ParserOptions) -> bool {
+    !(zero && options.leading_zeros == LeadingZeroPolicy::Reject)
+        && options.max_digits.is_none_or(|max| digits < max)
+}
+
+/// The terminal of an operation code, in either case if the options accept uppercase codes
+fn code(code: char, options: &ParserOptions) -> String {
+    match options.case_insensitive_opcodes {
+        true => format!(
+            "( {} | {} )",
+            quoted(code),
+            quoted(code.to_ascii_uppercase())
+        ),
+        false => quoted(code),
+    }
+}
+
+/// A character as an EBNF terminal
+fn quoted(char: char) -> String {
+    format!("\"{}\"", char)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::grammar::{ebnf, next_tokens};
+    use crate::options::{LeadingZeroPolicy, Limits, ParserOptions};
+
+    #[test]
+    fn test_ebnf() {
+        assert_eq!(
+            "input = expression ;
+expression = operand , { operation , operand } ;
+operand = number , [ \"%\" ] | group ;
+group = \"e\" , expression , \"f\" ;
+operation = \"a\" | \"b\" | \"c\" | \"d\" | \"g\" | \"h\" ;
+number = digit , { digit } ;
+digit = \"0\" | \"1\" | \"2\" | \"3\" | \"4\" | \"5\" | \"6\" | \"7\" | \"8\" | \"9\" ;",
+            ebnf(&ParserOptions::default(), false)
+        );
+        let options = ParserOptions {
+            radix: 2,
+            roman_numerals: true,
+            case_insensitive_opcodes: true,
+            leading_zeros: LeadingZeroPolicy::Reject,
+            ..ParserOptions::default()
+        }
+        .with_limits(Limits::untrusted());
+        let grammar = ebnf(&options, true);
+        assert!(grammar.starts_with("(* At most 4096 characters *)\ninput = expression ;"));
+        assert!(grammar.contains("operand = number , [ \"%\" ] | group | roman | cell ;"));
+        assert!(grammar.contains("(* Groups nest at most 64 deep *)"));
+        assert!(grammar.contains("group = ( \"e\" | \"E\" ) , expression , ( \"f\" | \"F\" ) ;"));
+        assert!(grammar.contains("number = \"0\" | digit - \"0\" , { digit } ;"));
+        assert!(grammar.contains("digit = \"0\" | \"1\" ;"));
+        assert!(
+            grammar.contains("symbol = \"I\" | \"V\" | \"X\" | \"L\" | \"C\" | \"D\" | \"M\" ;")
+        );
+        assert!(grammar.contains("cell = letter , { letter } , row ;"));
+        assert!(!grammar.contains("read as numbers first"));
+        let options = ParserOptions {
+            radix: 16,
+            roman_numerals: true,
+            ..ParserOptions::default()
+        };
+        let grammar = ebnf(&options, false);
+        assert!(grammar.contains("| \"9\" | \"A\" | \"B\" | \"C\" | \"D\" | \"E\" | \"F\" ;"));
+        assert!(
+            grammar.ends_with("(* Uppercase letters which are digits are read as numbers first *)")
+        );
+    }
+
+    #[test]
+    fn test_next_tokens() {
+        let options = ParserOptions::default();
+        let tokens = |partial: &str| {
+            next_tokens(partial, &options)
+                .into_iter()
+                .collect::<String>()
+        };
+        assert_eq!("0123456789e", tokens(""));
+        assert_eq!("0123456789%abcdgh", tokens("12"));
+        assert_eq!("0123456789e", tokens("12a"));
+        assert_eq!("0123456789%abcdghf", tokens("e3a2"));
+        assert_eq!("abcdghf", tokens("ee3a2f"));
+        assert_eq!("abcdgh", tokens("3%"));
+        assert_eq!("", tokens("3aa"));
+        assert_eq!("", tokens("3f"));
+        assert_eq!("", tokens("e3fe"));
+        assert_eq!("", tokens("3x"));
+        let options = ParserOptions {
+            radix: 2,
+            roman_numerals: true,
+            case_insensitive_opcodes: true,
+            leading_zeros: LeadingZeroPolicy::Reject,
+            max_digits: Some(3),
+            max_depth: Some(1),
+            ..ParserOptions::default()
+        };
+        let tokens = |partial: &str| {
+            next_tokens(partial, &options)
+                .into_iter()
+                .collect::<String>()
+        };
+        assert_eq!("01IVXLCDMe", tokens(""));
+        assert_eq!("01IVXLCDM", tokens("E1A"));
+        assert_eq!("%abcdghf", tokens("E0"));
+        assert_eq!("%abcdgh", tokens("101"));
+        assert_eq!("IVXLCDMabcdgh", tokens("XI"));
+    }
+}
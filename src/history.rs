@@ -0,0 +1,118 @@
+use std::fmt;
+
+/// The character introducing a reference to an earlier result (e.g. `$3a5`)
+const REFERENCE: char = '$';
+
+/// Errors that resolving the references to earlier results can cause
+#[derive(Debug, PartialEq)]
+pub enum HistoryError {
+    /// A reference is not written as `$index` (position)
+    Malformed(usize),
+    /// A reference is to an expression which is not before the current one (index)
+    Unknown(usize),
+    /// A reference is to an expression which failed or has no result (index)
+    Failed(usize),
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(position) => write!(f, "Malformed({})", position),
+            Self::Unknown(index) => write!(f, "Unknown({})", index),
+            Self::Failed(index) => write!(f, "Failed({})", index),
+        }
+    }
+}
+
+/// The results of the expressions evaluated so far in a batch, which the following expressions
+/// can reference by their index, from 1
+#[derive(Debug, Default)]
+pub struct History {
+    /// The result of every expression, `None` if it failed or was skipped
+    results: Vec<Option<usize>>,
+}
+
+/// Implementation of the result history
+impl History {
+    /// Record the result of the next expression
+    /// # Arguments
+    ///  - result: The result, `None` if the expression failed or was skipped
+    pub fn push(&mut self, result: Option<usize>) {
+        self.results.push(result);
+    }
+
+    /// Replace the references to earlier results (e.g. `$3`) with their values
+    /// # Arguments
+    ///  - expression: The expression having the references
+    /// # Return
+    /// A `Result` having the expression without references if they all resolve, `HistoryError`
+    /// otherwise
+    pub fn resolve(&self, expression: &str) -> Result<String, HistoryError> {
+        if !expression.contains(REFERENCE) {
+            return Ok(expression.to_string());
+        }
+        self.resolve_sources(expression)
+            .map(|(resolved, _)| resolved)
+    }
+
+    /// Replace the references to earlier results with their values, as `resolve`, also telling
+    /// where each character of the resolved expression comes from, so that the positions in the
+    /// resolved expression can be reported in the expression as written
+    /// # Arguments
+    ///  - expression: The expression having the references
+    /// # Return
+    /// A `Result` having the expression without references and the position in the expression of
+    /// every character of it, the one of its reference for the digits of a value, if they all
+    /// resolve, `HistoryError` otherwise
+    pub fn resolve_sources(&self, expression: &str) -> Result<(String, Vec<usize>), HistoryError> {
+        let mut resolved = String::with_capacity(expression.len());
+        let mut sources = Vec::with_capacity(expression.len());
+        let mut chars = expression.char_indices().enumerate().peekable();
+        while let Some((source, (position, char))) = chars.next() {
+            if char != REFERENCE {
+                resolved.push(char);
+                sources.push(source);
+                continue;
+            }
+            let mut digits = String::new();
+            while let Some((_, (_, digit))) = chars.next_if(|(_, (_, c))| c.is_ascii_digit()) {
+                digits.push(digit);
+            }
+            let index = digits
+                .parse::<usize>()
+                .map_err(|_| HistoryError::Malformed(position))?;
+            let result = match index.checked_sub(1).map(|index| self.results.get(index)) {
+                Some(Some(result)) => result.ok_or(HistoryError::Failed(index))?,
+                _ => return Err(HistoryError::Unknown(index)),
+            };
+            let value = result.to_string();
+            resolved.push_str(&value);
+            sources.extend(std::iter::repeat_n(source, value.len()));
+        }
+        Ok((resolved, sources))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::history::{History, HistoryError};
+
+    #[test]
+    fn test_resolve() {
+        let mut history = History::default();
+        history.push(Some(5));
+        history.push(None);
+        history.push(Some(20));
+        assert_eq!(Ok("3a2".to_string()), history.resolve("3a2"));
+        assert_eq!(Ok("20a5".to_string()), history.resolve("$3a5"));
+        assert_eq!(Ok("e5c20fd5".to_string()), history.resolve("e$1c$3fd$1"));
+        assert_eq!(Err(HistoryError::Failed(2)), history.resolve("$2a1"));
+        assert_eq!(Err(HistoryError::Unknown(4)), history.resolve("$4a1"));
+        assert_eq!(Err(HistoryError::Unknown(0)), history.resolve("$0a1"));
+        assert_eq!(Err(HistoryError::Malformed(2)), history.resolve("3a$"));
+        assert_eq!(
+            Ok(("e20a5f".to_string(), vec![0, 1, 1, 3, 4, 6])),
+            history.resolve_sources("e$3a$1f")
+        );
+    }
+}
@@ -0,0 +1,73 @@
+/// Quote and escape a text as a JSON string
+/// # Arguments
+///  - value: The text to quote
+/// # Return
+/// The JSON string literal
+pub fn string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for char in value.chars() {
+        match char {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            char if char.is_control() => quoted.push_str(&format!("\\u{:04x}", char as u32)),
+            char => quoted.push(char),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Parse a JSON string literal, as produced by `string`
+/// # Arguments
+///  - literal: The quoted and escaped text
+/// # Return
+/// An `Option` having the unescaped text if the literal is valid, `None` otherwise
+pub fn parse_string(literal: &str) -> Option<String> {
+    let inner = literal.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => match chars.next()? {
+                '"' => value.push('"'),
+                '\\' => value.push('\\'),
+                '/' => value.push('/'),
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    value.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                _ => return None,
+            },
+            '"' => return None,
+            char => value.push(char),
+        }
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::json::{parse_string, string};
+
+    #[test]
+    fn test_string() {
+        assert_eq!("\"3a2\"", string("3a2"));
+        assert_eq!("\"\\\"\\\\\\n\\u0001\"", string("\"\\\n\u{1}"));
+    }
+
+    #[test]
+    fn test_parse_string() {
+        for value in ["3a2", "\"\\\n\u{1}", "été"] {
+            assert_eq!(Some(value.to_string()), parse_string(&string(value)));
+        }
+        assert_eq!(None, parse_string("3a2"));
+        assert_eq!(None, parse_string("\"a\"b\""));
+    }
+}
@@ -0,0 +1,206 @@
+use std::ops::Range;
+
+use crate::operation::codes::*;
+
+/// The kinds of token of a coded expression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    /// A run of decimal digits
+    Number,
+    /// A binary operation code (operation code)
+    Operation(char),
+    /// An opening parenthesis
+    Open,
+    /// A closing parenthesis
+    Close,
+    /// The percent suffix of a number
+    Percent,
+    /// A character foreign to the expression syntax
+    Invalid,
+}
+
+/// A token of the expression, spanning the characters from `start` (included) to `end` (excluded)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token {
+    /// The kind of token
+    pub kind: TokenKind,
+    /// The position of the first character
+    pub start: usize,
+    /// The position following the last character
+    pub end: usize,
+}
+
+/// A lexer which is fed the expression over time, e.g. keystroke by keystroke, and after every
+/// edit re-lexes only the region around it, keeping the spans of the other tokens up to date
+#[derive(Debug, Default)]
+pub struct Lexer {
+    /// The characters of the expression
+    text: Vec<char>,
+    /// The tokens of the expression, in order
+    tokens: Vec<Token>,
+}
+
+/// Implementation of the incremental lexer
+impl Lexer {
+    /// Creates a lexer having an empty expression
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The expression fed so far
+    pub fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    /// The tokens of the expression fed so far
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Append a chunk to the expression
+    /// # Arguments
+    ///  - chunk: The characters to append
+    /// # Return
+    /// The range of the tokens lexed again, in the updated tokens
+    pub fn push(&mut self, chunk: &str) -> Range<usize> {
+        let length = self.text.len();
+        self.edit(length..length, chunk)
+    }
+
+    /// Replace a region of the expression, lexing again from the token preceding the region until
+    /// the tokens realign with the ones following it
+    /// # Arguments
+    ///  - region: The character positions to replace, empty to insert
+    ///  - replacement: The characters replacing the region, empty to delete
+    /// # Return
+    /// The range of the tokens lexed again, in the updated tokens
+    pub fn edit(&mut self, region: Range<usize>, replacement: &str) -> Range<usize> {
+        let end = region.end.min(self.text.len());
+        let start = region.start.min(end);
+        let replacement: Vec<char> = replacement.chars().collect();
+        let inserted_end = start + replacement.len();
+        self.text.splice(start..end, replacement);
+
+        // A number ending where the region starts may continue into the replacement
+        let first = self.tokens.partition_point(|token| token.end < start);
+        let mut position = self
+            .tokens
+            .get(first)
+            .map_or(start, |token| token.start.min(start));
+        let mut last = first;
+        let mut lexed = Vec::new();
+        while position < self.text.len() {
+            if position >= inserted_end {
+                // The following tokens are unchanged once a boundary matches one of them
+                let old = position + end - inserted_end;
+                last += self.tokens[last..].partition_point(|token| token.start < old);
+                if self
+                    .tokens
+                    .get(last)
+                    .is_some_and(|token| token.start == old)
+                {
+                    break;
+                }
+            }
+            let token = self.lex(position);
+            position = token.end;
+            lexed.push(token);
+        }
+        if position >= self.text.len() {
+            last = self.tokens.len();
+        }
+        for token in &mut self.tokens[last..] {
+            token.start = token.start + inserted_end - end;
+            token.end = token.end + inserted_end - end;
+        }
+        let relexed = first..first + lexed.len();
+        self.tokens.splice(first..last, lexed);
+        relexed
+    }
+
+    /// Lex the token starting at a position
+    fn lex(&self, start: usize) -> Token {
+        let kind =
+            match self.text[start] {
+                char if char.is_ascii_digit() => TokenKind::Number,
+                code @ (OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD
+                | OPCODE_LCM) => TokenKind::Operation(code),
+                OPCODE_OPEN => TokenKind::Open,
+                OPCODE_CLOSE => TokenKind::Close,
+                PERCENT => TokenKind::Percent,
+                _ => TokenKind::Invalid,
+            };
+        let mut end = start + 1;
+        if kind == TokenKind::Number {
+            while self.text.get(end).is_some_and(char::is_ascii_digit) {
+                end += 1;
+            }
+        }
+        Token { kind, start, end }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lexer::{Lexer, Token, TokenKind};
+
+    /// Lex the whole expression from scratch
+    fn lex(expression: &str) -> Vec<Token> {
+        let mut lexer = Lexer::new();
+        lexer.push(expression);
+        lexer.tokens().to_vec()
+    }
+
+    #[test]
+    fn test_lex() {
+        let token = |kind, start, end| Token { kind, start, end };
+        assert_eq!(
+            vec![
+                token(TokenKind::Open, 0, 1),
+                token(TokenKind::Number, 1, 3),
+                token(TokenKind::Operation('a'), 3, 4),
+                token(TokenKind::Invalid, 4, 5),
+                token(TokenKind::Close, 5, 6),
+            ],
+            lex("e12axf")
+        );
+        assert_eq!(
+            vec![
+                token(TokenKind::Number, 0, 3),
+                token(TokenKind::Operation('a'), 3, 4),
+                token(TokenKind::Number, 4, 6),
+                token(TokenKind::Percent, 6, 7),
+            ],
+            lex("200a10%")
+        );
+    }
+
+    #[test]
+    fn test_keystrokes() {
+        let mut lexer = Lexer::new();
+        for char in "e12a3fc45".chars() {
+            lexer.push(&char.to_string());
+        }
+        assert_eq!(lex("e12a3fc45"), lexer.tokens());
+        // Typing a digit only lexes the number it extends
+        assert_eq!(6..7, lexer.push("6"));
+        assert_eq!("e12a3fc456", lexer.text());
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut lexer = Lexer::new();
+        lexer.push("12a34c56b78");
+        // Replacing an operation merges the numbers around it, shifting the following tokens
+        assert_eq!(2..3, lexer.edit(5..6, ""));
+        assert_eq!(lex("12a3456b78"), lexer.tokens());
+        // Splitting a number only lexes it again
+        assert_eq!(2..5, lexer.edit(5..5, "d"));
+        assert_eq!(lex("12a34d56b78"), lexer.tokens());
+        // Editing the first token leaves the others in place
+        assert_eq!(0..1, lexer.edit(0..2, "9"));
+        assert_eq!(lex("9a34d56b78"), lexer.tokens());
+        assert_eq!(0..0, lexer.edit(0..10, ""));
+        assert!(lexer.tokens().is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+//! Parser of arithmetic expressions coded with letters, e.g. `3a2c4` for `(3 + 2) * 4`, applying
+//! the operations strictly from left to right. The command line application is a thin wrapper
+//! around this library.
+
+pub mod bits;
+pub mod cache;
+pub mod cell;
+pub mod check;
+pub mod constant;
+pub mod csv;
+pub mod definitions;
+pub mod diagnostics;
+pub mod division;
+pub mod events;
+pub mod expr;
+pub mod grammar;
+pub mod history;
+pub mod json;
+pub mod lexer;
+pub mod messages;
+pub mod notation;
+pub mod operation;
+pub mod options;
+pub mod parser;
+pub mod repair;
+pub mod replay;
+pub mod report;
+pub mod rewrite;
+pub mod roman;
+pub mod stats;
+#[cfg(feature = "async")]
+pub mod task;
+pub mod teach;
+pub mod telemetry;
+pub mod template;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod theory;
+pub mod watch;
+
+pub use operation::{Operation, OperationError};
+pub use parser::{ParseError, Parser};
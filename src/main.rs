@@ -1,8 +1,31 @@
-use crate::parser::{ParseError, Parser};
-use std::env;
+use arithmetic_parser::cache::ResultCache;
+use arithmetic_parser::check::{Problem, Severity, Warnings};
+use arithmetic_parser::csv::CsvError;
+use arithmetic_parser::definitions::{DefinitionError, Definitions};
+use arithmetic_parser::diagnostics::{DiagnosticsSink, Level, LogSink, Notice};
+use arithmetic_parser::division::{Division, DivisionFormat};
+use arithmetic_parser::expr::Pattern;
+use arithmetic_parser::history::{History, HistoryError};
+use arithmetic_parser::options::ParserOptions;
+use arithmetic_parser::repair::{repair, suggest_balance};
+use arithmetic_parser::replay::{Recording, ReplayError};
+use arithmetic_parser::stats::Stats;
+use arithmetic_parser::telemetry::Timings;
+use arithmetic_parser::template::TemplateError;
+use arithmetic_parser::watch::Watcher;
+use arithmetic_parser::{
+    bits, check, csv, events, grammar, messages, notation, rewrite, roman, teach, template, watch,
+};
+use arithmetic_parser::{OperationError, ParseError, Parser};
+use cli::{Arguments, Command};
+use log::LevelFilter;
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+use std::process::ExitCode;
+use std::rc::Rc;
+use std::{env, fmt, io, thread};
 
-mod operation;
-mod parser;
+mod cli;
 
 /// Defines the errors this application can throw
 #[derive(Debug)]
@@ -11,29 +34,604 @@ enum ApplicationError {
     Parser(ParseError),
     /// Illegal arguments passed to the program
     IllegalArgs,
+    /// An option received a missing or invalid value (option, value)
+    InvalidArgument(String, String),
+    /// The result cannot be rendered in the requested output format (result)
+    UnrepresentableResult(usize),
+    /// Error processing a CSV
+    Csv(CsvError),
+    /// Error rendering a template
+    Template(TemplateError),
+    /// Error reading or replaying a recording
+    Replay(ReplayError),
+    /// Error loading or calling the named formulas
+    Definitions(DefinitionError),
+    /// Error referencing the result of an earlier expression
+    History(HistoryError),
+    /// Error reading the input or writing the output
+    Io(io::Error),
 }
 
-fn main() -> Result<(), ApplicationError> {
-    env_logger::init();
+impl fmt::Display for ApplicationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parser(err) => write!(f, "Parser({}, {:?})", err.error_code(), err),
+            Self::IllegalArgs => write!(f, "IllegalArgs"),
+            Self::InvalidArgument(option, value) => {
+                write!(f, "InvalidArgument({:?}, {:?})", option, value)
+            }
+            Self::UnrepresentableResult(result) => write!(f, "UnrepresentableResult({})", result),
+            Self::Csv(err) => write!(f, "Csv({})", err),
+            Self::Template(err) => write!(f, "Template({})", err),
+            Self::Replay(err) => write!(f, "Replay({})", err),
+            Self::Definitions(err) => write!(f, "Definitions({})", err),
+            Self::History(err) => write!(f, "History({})", err),
+            Self::Io(err) => write!(f, "Io({})", err),
+        }
+    }
+}
 
+fn main() -> ExitCode {
     // Show help if no argument is passed
     let mut args = env::args();
     let bin_path = args.next().unwrap_or(env!("CARGO_PKG_NAME").to_string());
     if args.len() < 1 {
         println!(
-            "{} {} - Usage: {} <expression>",
+            "{} {} - Usage: {} [options] <expression>\n       {} csv --column <name> [options] < input.csv\n       {} render [options] < template.txt\n       {} replay <recording>\n       {} grammar [options]\n       {} check [options] <file|directory|glob>...\n       {} rewrite --from <pattern> --to <template> [<file|directory|glob>...]\n{}",
             env!("CARGO_PKG_NAME"),
             env!("CARGO_PKG_VERSION"),
-            bin_path
+            bin_path,
+            bin_path,
+            bin_path,
+            bin_path,
+            bin_path,
+            bin_path,
+            bin_path,
+            cli::OPTIONS_HELP
         );
     }
+    let arguments = match Arguments::parse(args) {
+        Ok(arguments) => arguments,
+        Err(err) => {
+            env_logger::init();
+            eprintln!("Error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    // The verbosity flags take precedence over RUST_LOG
+    let mut logger = env_logger::Builder::from_default_env();
+    match arguments.verbosity {
+        0 => {}
+        1 => {
+            logger.filter_level(LevelFilter::Debug);
+        }
+        _ => {
+            logger.filter_level(LevelFilter::Trace);
+        }
+    }
+    logger.init();
+    // When the exit status carries the result, failures use a status no result can take
+    let failure = match arguments.exit_result {
+        true => ExitCode::from(EXIT_RESULT_FAILURE),
+        false => ExitCode::FAILURE,
+    };
+    match run(arguments) {
+        Ok(status) => status,
+        Err(ApplicationError::Parser(err)) => {
+            let catalog = messages::catalog(&locale());
+            eprintln!("Error: {} {}", err.error_code(), catalog.parse_error(&err));
+            failure
+        }
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            failure
+        }
+    }
+}
+
+/// The locale of the messages, read from the environment variables in POSIX precedence order
+fn locale() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_default()
+}
+
+/// The exit status of failures when the exit status carries the result
+const EXIT_RESULT_FAILURE: u8 = 255;
+
+fn run(arguments: Arguments) -> Result<ExitCode, ApplicationError> {
+    let timings = arguments.time.then(|| Rc::new(Timings::default()));
+    let status = match arguments.command {
+        Command::Evaluate => match &arguments.watch {
+            Some(path) => watch(path, &arguments, timings.as_ref()),
+            None => evaluate(&arguments, timings.as_ref()),
+        },
+        Command::Csv => {
+            let column = arguments
+                .column
+                .as_deref()
+                .ok_or(ApplicationError::InvalidArgument(
+                    "--column".to_string(),
+                    String::new(),
+                ))?;
+            let mut cache = arguments
+                .cache
+                .map(|capacity| ResultCache::new(capacity, &arguments.options));
+            let errors = csv::process(
+                io::stdin().lock(),
+                io::stdout().lock(),
+                (column, &arguments.result_column),
+                &arguments.options,
+                |result| render(result, &arguments).ok(),
+                timings.as_ref(),
+                cache.as_mut(),
+            )
+            .map_err(ApplicationError::Csv)?;
+            for (row, err) in errors {
+                eprintln!("Row {}: {} {:?}", row, err.error_code(), err);
+            }
+            if let Some(cache) = cache.filter(|_| arguments.stats) {
+                eprintln!("{}", cache.stats());
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Render => {
+            let mut text = String::new();
+            io::stdin()
+                .read_to_string(&mut text)
+                .map_err(ApplicationError::Io)?;
+            let rendered = template::render(&text, &arguments.options, |result| {
+                render(result, &arguments).ok()
+            })
+            .map_err(ApplicationError::Template)?;
+            print!("{}", rendered);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Replay => {
+            let path = arguments
+                .expressions
+                .first()
+                .ok_or(ApplicationError::IllegalArgs)?;
+            let text = std::fs::read_to_string(path).map_err(ApplicationError::Io)?;
+            let recording = Recording::from_text(&text).map_err(ApplicationError::Replay)?;
+            println!("{}", recording.replay().map_err(ApplicationError::Replay)?);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Check => check(&arguments, timings.as_ref()),
+        Command::Rewrite => rewrite(&arguments),
+        Command::Grammar => {
+            println!("{}", build_parser(String::new(), None, &arguments, None).grammar());
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Complete => {
+            // Without an argument, the partial expression is read from the standard input
+            let partial = match arguments.expressions.first() {
+                Some(partial) => partial.clone(),
+                None => {
+                    let mut text = String::new();
+                    io::stdin()
+                        .read_to_string(&mut text)
+                        .map_err(ApplicationError::Io)?;
+                    text.trim_end_matches(['\r', '\n']).to_string()
+                }
+            };
+            for token in grammar::next_tokens(&partial, &arguments.options) {
+                println!("{}", token);
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+    };
+    if let Some(timings) = timings {
+        eprintln!("{}", timings);
+    }
+    status
+}
+
+/// Evaluate the expression passed as argument and print its result
+fn evaluate(
+    arguments: &Arguments,
+    timings: Option<&Rc<Timings>>,
+) -> Result<ExitCode, ApplicationError> {
+    // Shells split unquoted expressions at spaces, so the pieces can be joined back
+    let (previous, expression) = match (arguments.join, arguments.chain) {
+        (true, _) if !arguments.expressions.is_empty() => (
+            &[] as &[String],
+            Some(arguments.expressions.concat()),
+        ),
+        (_, true) => match arguments.expressions.split_last() {
+            Some((last, previous)) => (previous, Some(last.clone())),
+            None => (&[] as &[String], None),
+        },
+        _ => (&[] as &[String], arguments.expressions.first().cloned()),
+    };
     // If some expression is present, instantiate the parse and attempt to parse it
-    if let Some(expression) = args.next() {
-        let parser = Parser::new(expression);
-        let result = parser.parse().map_err(ApplicationError::Parser)?;
-        println!("{}", result);
-        Ok(())
+    if let Some(expression) = expression {
+        let definitions = load_definitions(arguments)?;
+        let prepared = |expression: &str| prepare(expression, &definitions, arguments);
+        // Chained expressions feed their result forward, as the first operand of the next one,
+        // and can reference any earlier result by its position (e.g. `$1`)
+        let mut initial = None;
+        let mut history = History::default();
+        let warnings = Rc::new(Warnings::default());
+        for expression in previous {
+            let expression = history.resolve(expression).map_err(ApplicationError::History)?;
+            let expression = prepared(&expression)?;
+            let parser = build_parser(expression, initial, arguments, timings)
+                .with_diagnostics(Reported(Rc::clone(&warnings)));
+            let result = parser.parse();
+            print_warnings(&warnings);
+            initial = Some(result.map_err(ApplicationError::Parser)?);
+            history.push(initial);
+        }
+        let expression = history.resolve(&expression).map_err(ApplicationError::History)?;
+        let expression = prepared(&expression)?;
+        let parser = build_parser(expression.clone(), initial, arguments, timings)
+            .with_diagnostics(Reported(Rc::clone(&warnings)));
+        let recorded = arguments.events.is_some()
+            || arguments.record.is_some()
+            || arguments.teach
+            || arguments.division != DivisionFormat::Truncate
+            || arguments.round.is_some();
+        let (result, events) = match (recorded, arguments.stats) {
+            (true, _) => {
+                let (result, events) = parser.parse_with_events();
+                if let Some(path) = &arguments.events {
+                    write_file(path, &events::to_json(&events))?;
+                }
+                if let Some(path) = &arguments.record {
+                    let recording = Recording::new(
+                        expression.clone(),
+                        initial,
+                        arguments.options.clone(),
+                        &arguments.cells,
+                        &events,
+                        &result,
+                    );
+                    write_file(path, &recording.to_text())?;
+                }
+                if arguments.stats {
+                    eprintln!("{}", Stats::from_events(&events));
+                }
+                if arguments.teach {
+                    eprintln!("{}", teach::narrate(&events));
+                }
+                (result, events)
+            }
+            (false, true) => {
+                let (result, stats) = parser.parse_with_stats();
+                eprintln!("{}", stats);
+                (result, Vec::new())
+            }
+            (false, false) => (parser.parse(), Vec::new()),
+        };
+        print_warnings(&warnings);
+        let result = match result {
+            Err(err @ ParseError::InvalidOperation { source: OperationError::OverflowError })
+                if arguments.wrapping =>
+            {
+                explain_overflow(&err, expression.clone(), initial, arguments)?
+            }
+            Err(err @ ParseError::UnbalancedParenthesis { .. }) => {
+                for suggestion in suggest_balance(&expression) {
+                    eprintln!("Hint: {}", suggestion);
+                }
+                return Err(ApplicationError::Parser(err));
+            }
+            result => result.map_err(ApplicationError::Parser)?,
+        };
+        if arguments.exit_result {
+            return u8::try_from(result)
+                .ok()
+                .filter(|status| *status != EXIT_RESULT_FAILURE)
+                .map(ExitCode::from)
+                .ok_or(ApplicationError::UnrepresentableResult(result));
+        }
+        if !arguments.quiet {
+            let signed_width = arguments.options.signed_width;
+            // The remainders of the signed divisions are not tracked, so they are not printed
+            let division = match signed_width {
+                Some(_) => Division::from_events(result, &[]),
+                None => Division::from_events(result, &events),
+            };
+            // Roman numerals have no decimal places
+            let rendered = match arguments.round {
+                Some(places) if !arguments.roman_output && signed_width.is_none() => {
+                    division.round_to(places)
+                }
+                _ => division.render(arguments.division, render(result, arguments)?),
+            };
+            match arguments.bits {
+                Some(format) => {
+                    // Signed integers have the bits of their width, whatever their value
+                    let width = arguments.width.or(signed_width);
+                    let pattern = bits::pattern(result, format, width)
+                        .ok_or(ApplicationError::UnrepresentableResult(result))?;
+                    println!("{} = {}", rendered, pattern);
+                }
+                None => println!("{}", rendered),
+            }
+        }
+        Ok(ExitCode::SUCCESS)
     } else {
         Err(ApplicationError::IllegalArgs)
     }
 }
+
+/// Evaluate the expressions of a formula file every time it changes, printing their results
+fn watch(
+    path: &Path,
+    arguments: &Arguments,
+    timings: Option<&Rc<Timings>>,
+) -> Result<ExitCode, ApplicationError> {
+    let definitions = load_definitions(arguments)?;
+    let catalog = messages::catalog(&locale());
+    let evaluate = |expression: &str| {
+        let expression =
+            prepare(expression, &definitions, arguments).map_err(|err| err.to_string())?;
+        let warnings = Rc::new(Warnings::default());
+        let result = build_parser(expression, None, arguments, timings)
+            .with_diagnostics(Rc::clone(&warnings))
+            .parse()
+            .map_err(|err| format!("{} {}", err.error_code(), catalog.parse_error(&err)))?;
+        let mut rendered = render(result, arguments).map_err(|err| err.to_string())?;
+        // The warnings follow the result inline, e.g. `5 (warning: operand 07 has leading zeros)`
+        for warning in warnings.take() {
+            rendered = format!("{} (warning: {})", rendered, warning.message);
+        }
+        Ok((result, rendered))
+    };
+    let mut watcher = Watcher::new(path);
+    loop {
+        if let Some(text) = watcher.poll().map_err(ApplicationError::Io)? {
+            println!("{}\n", watch::report(&text, evaluate, io::stdout().is_terminal()));
+        }
+        thread::sleep(watch::POLL_INTERVAL);
+    }
+}
+
+/// Check the expressions of the files, directories and globs passed as arguments, printing every
+/// problem found as JSON and a summary of every file on the standard error
+fn check(
+    arguments: &Arguments,
+    timings: Option<&Rc<Timings>>,
+) -> Result<ExitCode, ApplicationError> {
+    if arguments.expressions.is_empty() {
+        return Err(ApplicationError::IllegalArgs);
+    }
+    let definitions = load_definitions(arguments)?;
+    let catalog = messages::catalog(&locale());
+    let error = |column, code, message| Problem {
+        column,
+        code,
+        message,
+        severity: Severity::Error,
+    };
+    let evaluate = |expression: &str| {
+        let expression = prepare(expression, &definitions, arguments)
+            .map_err(|err| error(None, None, err.to_string()))?;
+        let warnings = Rc::new(Warnings::default());
+        let result = build_parser(expression, None, arguments, timings)
+            .with_diagnostics(Rc::clone(&warnings))
+            .parse()
+            .map_err(|err| {
+                error(err.position(), Some(err.error_code()), catalog.parse_error(&err))
+            })?;
+        Ok((result, warnings.take()))
+    };
+    let mut findings = Vec::new();
+    let mut failing = 0;
+    let files = check::files(&arguments.expressions).map_err(ApplicationError::Io)?;
+    for path in &files {
+        let text = std::fs::read_to_string(path).map_err(ApplicationError::Io)?;
+        let report = check::check(&path.to_string_lossy(), &text, evaluate);
+        eprintln!("{}", report);
+        if report.count(Severity::Error) > 0 {
+            failing += 1;
+        }
+        findings.extend(report.findings);
+    }
+    eprintln!("{} files checked, {} failing", files.len(), failing);
+    println!("{}", check::to_json(&findings));
+    match failing {
+        0 => Ok(ExitCode::SUCCESS),
+        _ => Ok(ExitCode::FAILURE),
+    }
+}
+
+/// Rewrite the expressions of the files, directories and globs passed as arguments in place,
+/// printing how many changed in every file on the standard error, or those of the standard input
+/// to the standard output
+fn rewrite(arguments: &Arguments) -> Result<ExitCode, ApplicationError> {
+    let pattern = |option: &str, value: &Option<String>| {
+        let value = value.clone().ok_or(ApplicationError::IllegalArgs)?;
+        Pattern::parse(&value).ok_or(ApplicationError::InvalidArgument(option.to_string(), value))
+    };
+    let from = pattern("--from", &arguments.from)?;
+    let to = pattern("--to", &arguments.to)?;
+    // A wildcard of the template without a matched subtree would stay in the rewritten code
+    if to.wildcards() > from.wildcards() {
+        let value = arguments.to.clone().unwrap_or_default();
+        return Err(ApplicationError::InvalidArgument("--to".to_string(), value));
+    }
+    if arguments.expressions.is_empty() {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(ApplicationError::Io)?;
+        print!("{}", rewrite::rewrite(&text, &from, &to).0);
+        return Ok(ExitCode::SUCCESS);
+    }
+    for path in check::files(&arguments.expressions).map_err(ApplicationError::Io)? {
+        let text = std::fs::read_to_string(&path).map_err(ApplicationError::Io)?;
+        let (rewritten, count) = rewrite::rewrite(&text, &from, &to);
+        if count > 0 {
+            std::fs::write(&path, rewritten).map_err(ApplicationError::Io)?;
+        }
+        eprintln!("{}: {} rewritten", path.to_string_lossy(), count);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Load the named formulas of the definitions file, if any
+fn load_definitions(arguments: &Arguments) -> Result<Definitions, ApplicationError> {
+    let definitions = match &arguments.defs {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).map_err(ApplicationError::Io)?;
+            Definitions::parse(&text).map_err(ApplicationError::Definitions)?
+        }
+        None => Definitions::default(),
+    };
+    Ok(match arguments.seed {
+        Some(seed) => definitions.with_seed(seed),
+        None => definitions,
+    })
+}
+
+/// Prepare an expression for parsing, expanding the calls to named formulas before any repair
+fn prepare(
+    expression: &str,
+    definitions: &Definitions,
+    arguments: &Arguments,
+) -> Result<String, ApplicationError> {
+    let expression = definitions
+        .expand(expression)
+        .map_err(ApplicationError::Definitions)?;
+    Ok(match arguments.fix {
+        true => fix(&expression),
+        false => expression,
+    })
+}
+
+/// Build the parser of an expression as the arguments require
+fn build_parser(
+    expression: String,
+    initial: Option<usize>,
+    arguments: &Arguments,
+    timings: Option<&Rc<Timings>>,
+) -> Parser {
+    let mut parser = Parser::new(expression).with_options(arguments.options.clone());
+    if let Some(initial) = initial {
+        parser = parser.with_initial(initial);
+    }
+    if !arguments.cells.is_empty() {
+        parser = parser.with_cell_resolver(arguments.cells.clone());
+    }
+    if let Some(timings) = timings {
+        parser = parser.with_telemetry(Rc::clone(timings));
+    }
+    parser
+}
+
+/// The sink of the evaluated expressions, collecting the warnings and notices to print them,
+/// which the log would hide unless verbose, and logging the other messages
+struct Reported(Rc<Warnings>);
+
+impl DiagnosticsSink for Reported {
+    fn enabled(&self, level: Level) -> bool {
+        level == Level::Warn || LogSink.enabled(level)
+    }
+
+    fn diagnostic(&self, level: Level, message: fmt::Arguments) {
+        match level {
+            Level::Warn => self.0.diagnostic(level, message),
+            _ => LogSink.diagnostic(level, message),
+        }
+    }
+
+    fn notice(&self, notice: &Notice) {
+        self.0.notice(notice);
+    }
+}
+
+/// Print the warnings collected while evaluating an expression on the standard error
+fn print_warnings(warnings: &Warnings) {
+    for warning in warnings.take() {
+        eprintln!("Warning: {}", warning.message);
+    }
+}
+
+/// Explain an overflow to students, printing the checked error followed by the result wrapping
+/// around, as the hardware computes it
+fn explain_overflow(
+    err: &ParseError,
+    expression: String,
+    initial: Option<usize>,
+    arguments: &Arguments,
+) -> Result<usize, ApplicationError> {
+    let catalog = messages::catalog(&locale());
+    eprintln!("Checked: {} {}", err.error_code(), catalog.parse_error(err));
+    let options = ParserOptions {
+        wrapping: true,
+        ..arguments.options.clone()
+    };
+    let result = build_parser(expression, initial, arguments, None)
+        .with_options(options)
+        .parse()
+        .map_err(ApplicationError::Parser)?;
+    match arguments.options.signed_width {
+        Some(width) => {
+            let (min, max) = (bits::signed(1 << (width - 1), width), (1u64 << (width - 1)) - 1);
+            eprintln!(
+                "Wrapped: {}, the exact result modulo 2^{}, since signed integers of {} bits start \
+                 again from {} past {}, and from {} below {}",
+                bits::signed(result, width),
+                width,
+                width,
+                min,
+                max,
+                max,
+                min
+            );
+        }
+        None => eprintln!(
+            "Wrapped: {}, the exact result modulo 2^{}, since unsigned integers of {} bits start \
+             again from 0 past {}, and from {} below 0",
+            result,
+            usize::BITS,
+            usize::BITS,
+            usize::MAX,
+            usize::MAX
+        ),
+    }
+    Ok(result)
+}
+
+/// Apply the safe repairs to an expression, reporting them on the standard error
+fn fix(expression: &str) -> String {
+    let (repaired, fixes) = repair(expression);
+    for fix in fixes {
+        eprintln!("Fixed: {}", fix);
+    }
+    repaired
+}
+
+/// Write a text to a file, terminated by a line break
+fn write_file(path: &Path, text: &str) -> Result<(), ApplicationError> {
+    std::fs::write(path, format!("{}\n", text)).map_err(ApplicationError::Io)
+}
+
+/// Render a result in the output format selected by the arguments
+fn render(result: usize, arguments: &Arguments) -> Result<String, ApplicationError> {
+    // Negative results are written as their magnitude following a minus sign
+    if let Some(width) = arguments.options.signed_width {
+        let value = bits::signed(result, width);
+        if value < 0 {
+            let magnitude = value.unsigned_abs() as usize;
+            return match (arguments.roman_output, arguments.notation) {
+                (true, _) => Err(ApplicationError::UnrepresentableResult(result)),
+                (false, Some(notation)) => {
+                    Ok(format!("-{}", notation::format(magnitude, notation)))
+                }
+                (false, None) => Ok(value.to_string()),
+            };
+        }
+    }
+    if arguments.roman_output {
+        roman::to_roman(result).ok_or(ApplicationError::UnrepresentableResult(result))
+    } else if let Some(notation) = arguments.notation {
+        Ok(notation::format(result, notation))
+    } else {
+        Ok(result.to_string())
+    }
+}
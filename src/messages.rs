@@ -0,0 +1,323 @@
+use std::fmt;
+
+use crate::operation::OperationError;
+use crate::parser::ParseError;
+
+/// Produces the human readable messages of the errors, in a given language
+pub trait MessageCatalog {
+    /// Describe a parse error
+    /// # Arguments
+    ///  - error: The `ParseError` to describe
+    /// # Return
+    /// The message
+    fn parse_error(&self, error: &ParseError) -> String;
+
+    /// Describe an operation error
+    /// # Arguments
+    ///  - error: The `OperationError` to describe
+    /// # Return
+    /// The message
+    fn operation_error(&self, error: &OperationError) -> String;
+}
+
+/// The messages in English, used when no other language matches
+pub struct English;
+
+impl MessageCatalog for English {
+    fn parse_error(&self, error: &ParseError) -> String {
+        match error {
+            ParseError::EmptyExpression => "the expression is empty".to_string(),
+            ParseError::ParseDigitError { operand, message } => {
+                format!("the operand {} is not valid: {}", operand, message)
+            }
+            ParseError::InvalidOperation { source } => self.operation_error(source),
+            ParseError::MalformedExpression { found: char, .. } => {
+                format!("the character {} is not valid here", char)
+            }
+            ParseError::UnbalancedParenthesis { parenthesis: code, .. } => {
+                format!(
+                    "the parentheses are unbalanced, there are too many {}",
+                    code
+                )
+            }
+            ParseError::UnexpectedSymbol {
+                found: symbol,
+                state,
+                ..
+            } => {
+                format!(
+                    "the symbol {} is not expected while reading {:?}",
+                    symbol, state
+                )
+            }
+            ParseError::IllegalState { reason } => {
+                format!("the parser reached an illegal state: {}", reason)
+            }
+            ParseError::LeadingZero { operand } => {
+                format!("the operand {} has leading zeros", operand)
+            }
+            ParseError::InvalidRomanNumeral { numeral } => {
+                format!("{} is not a valid Roman numeral", numeral)
+            }
+            ParseError::UnresolvedCell { reference } => {
+                format!("the cell {} is not defined", reference)
+            }
+            ParseError::InvalidRadix { radix } => {
+                format!("the radix {} is not between 2 and 36", radix)
+            }
+            ParseError::InvalidWidth { width } => {
+                format!("the width {} is not 8, 16, 32 or 64 bits", width)
+            }
+            ParseError::TrailingInput {
+                position,
+                found: input,
+            } => {
+                format!(
+                    "the input {} at position {} follows a complete expression",
+                    input, position
+                )
+            }
+            ParseError::OperandTooLong {
+                position,
+                max_digits,
+            } => format!(
+                "the operand at position {} has more than {} digits",
+                position, max_digits
+            ),
+            ParseError::NestingTooDeep {
+                position,
+                max_depth,
+            } => format!(
+                "the parenthesis at position {} nests deeper than {}",
+                position, max_depth
+            ),
+            ParseError::ExpressionTooLong { max_length } => {
+                format!("the expression has more than {} characters", max_length)
+            }
+            ParseError::Cancelled { position } => {
+                format!("the parse process was cancelled at position {}", position)
+            }
+            ParseError::DepthLimitExceeded {
+                position,
+                max_depth,
+            } => format!(
+                "the parenthesis at position {} nests deeper than the parser supports ({})",
+                position, max_depth
+            ),
+            ParseError::InvalidBinding { source } => {
+                format!("the bindings cannot be desugared: {}", source)
+            }
+            ParseError::TooManyOperations {
+                position,
+                max_operations,
+            } => format!(
+                "the operation at position {} is beyond the {} operations allowed",
+                position, max_operations
+            ),
+        }
+    }
+
+    fn operation_error(&self, error: &OperationError) -> String {
+        match error {
+            OperationError::InvalidFirstOperand { operand, message } => {
+                format!("the first operand {} is not valid: {}", operand, message)
+            }
+            OperationError::InvalidSecondOperand { operand, message } => {
+                format!("the second operand {} is not valid: {}", operand, message)
+            }
+            OperationError::InvalidOperationCode { code } => {
+                format!("{} is not an operation code", code)
+            }
+            OperationError::OverflowError => {
+                "the operation overflows or divides by zero".to_string()
+            }
+            OperationError::InvalidOperand { start, end } => {
+                format!("the operand from {} to {} is not valid", start, end)
+            }
+        }
+    }
+}
+
+/// The messages in Italian
+pub struct Italian;
+
+impl MessageCatalog for Italian {
+    fn parse_error(&self, error: &ParseError) -> String {
+        match error {
+            ParseError::EmptyExpression => "l'espressione è vuota".to_string(),
+            ParseError::ParseDigitError { operand, message } => {
+                format!("l'operando {} non è valido: {}", operand, message)
+            }
+            ParseError::InvalidOperation { source } => self.operation_error(source),
+            ParseError::MalformedExpression { found: char, .. } => {
+                format!("il carattere {} non è valido in questa posizione", char)
+            }
+            ParseError::UnbalancedParenthesis { parenthesis: code, .. } => {
+                format!("le parentesi non sono bilanciate, ci sono troppi {}", code)
+            }
+            ParseError::UnexpectedSymbol {
+                found: symbol,
+                state,
+                ..
+            } => {
+                format!(
+                    "il simbolo {} non è atteso durante la lettura di {:?}",
+                    symbol, state
+                )
+            }
+            ParseError::IllegalState { reason } => {
+                format!("il parser ha raggiunto uno stato illegale: {}", reason)
+            }
+            ParseError::LeadingZero { operand } => {
+                format!("l'operando {} ha zeri iniziali", operand)
+            }
+            ParseError::InvalidRomanNumeral { numeral } => {
+                format!("{} non è un numero romano valido", numeral)
+            }
+            ParseError::UnresolvedCell { reference } => {
+                format!("la cella {} non è definita", reference)
+            }
+            ParseError::InvalidRadix { radix } => {
+                format!("la base {} non è compresa tra 2 e 36", radix)
+            }
+            ParseError::InvalidWidth { width } => {
+                format!("la larghezza {} non è di 8, 16, 32 o 64 bit", width)
+            }
+            ParseError::TrailingInput {
+                position,
+                found: input,
+            } => format!(
+                "l'input {} alla posizione {} segue un'espressione completa",
+                input, position
+            ),
+            ParseError::OperandTooLong {
+                position,
+                max_digits,
+            } => format!(
+                "l'operando alla posizione {} ha più di {} cifre",
+                position, max_digits
+            ),
+            ParseError::NestingTooDeep {
+                position,
+                max_depth,
+            } => format!(
+                "la parentesi alla posizione {} annida più di {} livelli",
+                position, max_depth
+            ),
+            ParseError::ExpressionTooLong { max_length } => {
+                format!("l'espressione ha più di {} caratteri", max_length)
+            }
+            ParseError::Cancelled { position } => {
+                format!("l'analisi è stata annullata alla posizione {}", position)
+            }
+            ParseError::DepthLimitExceeded {
+                position,
+                max_depth,
+            } => format!(
+                "la parentesi alla posizione {} annida più di quanto il parser supporti ({})",
+                position, max_depth
+            ),
+            ParseError::InvalidBinding { source } => {
+                format!("i binding non possono essere espansi: {}", source)
+            }
+            ParseError::TooManyOperations {
+                position,
+                max_operations,
+            } => format!(
+                "l'operazione alla posizione {} supera le {} operazioni consentite",
+                position, max_operations
+            ),
+        }
+    }
+
+    fn operation_error(&self, error: &OperationError) -> String {
+        match error {
+            OperationError::InvalidFirstOperand { operand, message } => {
+                format!("il primo operando {} non è valido: {}", operand, message)
+            }
+            OperationError::InvalidSecondOperand { operand, message } => {
+                format!("il secondo operando {} non è valido: {}", operand, message)
+            }
+            OperationError::InvalidOperationCode { code } => {
+                format!("{} non è un codice di operazione", code)
+            }
+            OperationError::OverflowError => {
+                "l'operazione va in overflow o divide per zero".to_string()
+            }
+            OperationError::InvalidOperand { start, end } => {
+                format!("l'operando da {} a {} non è valido", start, end)
+            }
+        }
+    }
+}
+
+/// Select the message catalog of a locale
+/// # Arguments
+///  - locale: The locale, in the POSIX format of the `LANG` variable (e.g. `it_IT.UTF-8`)
+/// # Return
+/// The `MessageCatalog` of the locale language, `English` if it has none
+pub fn catalog(locale: &str) -> &'static dyn MessageCatalog {
+    let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+    match language {
+        "it" => &Italian,
+        _ => &English,
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", English.parse_error(self))
+    }
+}
+
+impl fmt::Display for OperationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", English.operation_error(self))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::messages::catalog;
+    use crate::operation::OperationError;
+    use crate::parser::ParseError;
+
+    #[test]
+    fn test_catalog() {
+        let error = ParseError::UnresolvedCell {
+            reference: "A1".to_string(),
+        };
+        assert_eq!("the cell A1 is not defined", error.to_string());
+        assert_eq!(
+            "the cell A1 is not defined",
+            catalog("C").parse_error(&error)
+        );
+        assert_eq!(
+            "la cella A1 non è definita",
+            catalog("it_IT.UTF-8").parse_error(&error)
+        );
+        let error = ParseError::IllegalState {
+            reason: "Result not available".to_string(),
+        };
+        assert_eq!(
+            "the parser reached an illegal state: Result not available",
+            error.to_string()
+        );
+        assert_eq!(
+            "il parser ha raggiunto uno stato illegale: Result not available",
+            catalog("it_IT.UTF-8").parse_error(&error)
+        );
+
+        let error = ParseError::InvalidOperation {
+            source: OperationError::OverflowError,
+        };
+        assert_eq!(
+            "the operation overflows or divides by zero",
+            error.to_string()
+        );
+        assert_eq!(
+            "l'operazione va in overflow o divide per zero",
+            catalog("it").parse_error(&error)
+        );
+    }
+}
@@ -0,0 +1,69 @@
+/// How a result is written, for reading large results at a glance
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Notation {
+    /// The digits grouped by thousands (e.g. `1,234,567`)
+    Grouped,
+    /// A mantissa below 10 and the exponent of 10 (e.g. `1.234567e6`)
+    Scientific,
+    /// A mantissa below 1000 and an exponent multiple of 3 (e.g. `12.345e3`)
+    Engineering,
+}
+
+/// Write a value in a notation, exactly: the mantissas keep all the significant digits
+/// # Arguments
+///  - value: The value to write
+///  - notation: The `Notation` to write it in
+/// # Return
+/// The written value
+pub fn format(value: usize, notation: Notation) -> String {
+    let digits = value.to_string();
+    match notation {
+        Notation::Grouped => {
+            let mut groups: Vec<&str> = (0..digits.len())
+                .rev()
+                .step_by(3)
+                .map(|end| &digits[end.saturating_sub(2)..=end])
+                .collect();
+            groups.reverse();
+            groups.join(",")
+        }
+        Notation::Scientific => exponential(&digits, 1),
+        Notation::Engineering => exponential(&digits, 1 + (digits.len() - 1) % 3),
+    }
+}
+
+/// Write the digits of a value with a mantissa having the given number of integer digits
+fn exponential(digits: &str, integer_digits: usize) -> String {
+    let (integer, fraction) = digits.split_at(integer_digits);
+    let fraction = fraction.trim_end_matches('0');
+    let exponent = digits.len() - integer_digits;
+    match fraction {
+        "" => format!("{}e{}", integer, exponent),
+        fraction => format!("{}.{}e{}", integer, fraction, exponent),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::notation::{format, Notation};
+
+    #[test]
+    fn test_format() {
+        assert_eq!("1,234,567", format(1234567, Notation::Grouped));
+        assert_eq!("12,345", format(12345, Notation::Grouped));
+        assert_eq!("999", format(999, Notation::Grouped));
+        assert_eq!("0", format(0, Notation::Grouped));
+        assert_eq!("1.234567e6", format(1234567, Notation::Scientific));
+        assert_eq!("1e3", format(1000, Notation::Scientific));
+        assert_eq!("7e0", format(7, Notation::Scientific));
+        assert_eq!("0e0", format(0, Notation::Scientific));
+        assert_eq!("1.234567e6", format(1234567, Notation::Engineering));
+        assert_eq!("12.345e3", format(12345, Notation::Engineering));
+        assert_eq!("100e0", format(100, Notation::Engineering));
+        assert_eq!("120e3", format(120000, Notation::Engineering));
+        assert_eq!(
+            "18.446744073709551615e18",
+            format(usize::MAX, Notation::Engineering)
+        );
+    }
+}
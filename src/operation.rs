@@ -1,4 +1,8 @@
-use log::trace;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::bits;
+use crate::options::RoundingMode;
 
 pub mod codes {
     /// Operation code for addition
@@ -13,21 +17,67 @@ pub mod codes {
     pub const OPCODE_OPEN: char = 'e';
     /// Operation code for closed parenthesis
     pub const OPCODE_CLOSE: char = 'f';
+    /// Operation code for greatest common divisor
+    pub const OPCODE_GCD: char = 'g';
+    /// Operation code for least common multiple
+    pub const OPCODE_LCM: char = 'h';
+    /// Suffix of the operands which are percentages
+    pub const PERCENT: char = '%';
 }
 
 use codes::*;
 
-/// Errors that the Operation instantiation and application can cause
+/// Errors that the Operation instantiation and application can cause. More may be added, so
+/// matches need a wildcard arm
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum OperationError {
-    /// The first operand is invalid (character, error message)
-    InvalidFirstOperand(String, String),
-    /// The second operand is invalid (character, error message)
-    InvalidSecondOperand(String, String),
-    /// The operation code is invalid (invalid code)
-    InvalidOperationCode(char),
+    /// The first operand is invalid (the `operand` read and the error `message`)
+    InvalidFirstOperand { operand: String, message: String },
+    /// The second operand is invalid (the `operand` read and the error `message`)
+    InvalidSecondOperand { operand: String, message: String },
+    /// The operation code is invalid (the invalid `code`)
+    InvalidOperationCode { code: char },
     /// The operation application overflows
     OverflowError,
+    /// An operand is an invalid region of the expression (from `start` to `end` position)
+    InvalidOperand { start: usize, end: usize },
+}
+
+/// Implementation of the operation errors
+impl OperationError {
+    /// The stable code identifying the kind of error, which never changes across versions
+    /// # Return
+    /// The code, from `E101` upwards
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            Self::InvalidFirstOperand { .. } => "E101",
+            Self::InvalidSecondOperand { .. } => "E102",
+            Self::InvalidOperationCode { .. } => "E103",
+            Self::OverflowError => "E104",
+            Self::InvalidOperand { .. } => "E105",
+        }
+    }
+
+    /// The position in the expression where the error was detected, when the error reports it
+    /// # Return
+    /// An `Option` having the position, `None` if the error does not report one
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            Self::InvalidOperand { start, .. } => Some(*start),
+            _ => None,
+        }
+    }
+
+    /// The character which caused the error, when the error reports it
+    /// # Return
+    /// An `Option` having the character, `None` if the error does not report one
+    pub fn offending_char(&self) -> Option<char> {
+        match self {
+            Self::InvalidOperationCode { code } => Some(*code),
+            _ => None,
+        }
+    }
 }
 
 /// Enumeration of all possible arithmetical operations
@@ -41,6 +91,10 @@ pub enum Operation {
     Mul(usize),
     /// Division (first operand)
     Div(usize),
+    /// Greatest common divisor (first operand)
+    Gcd(usize),
+    /// Least common multiple (first operand)
+    Lcm(usize),
 }
 
 /// Implementation of an arithmetical operation
@@ -52,10 +106,13 @@ impl Operation {
     /// # Return
     /// A `Result` having an `Operation` if valid, `OperationError` otherwise
     pub fn from(code: char, first_operand: String) -> Result<Self, OperationError> {
-        let parsed = first_operand
-            .parse::<usize>()
-            .map_err(|err| OperationError::InvalidFirstOperand(first_operand, err.to_string()))?;
-        trace!("parsed={}", parsed);
+        let parsed =
+            first_operand
+                .parse::<usize>()
+                .map_err(|err| OperationError::InvalidFirstOperand {
+                    operand: first_operand,
+                    message: err.to_string(),
+                })?;
         Self::from_result(code, parsed)
     }
 
@@ -66,12 +123,62 @@ impl Operation {
     /// # Return
     /// A `Result` having an `Operation` if valid, `OperationError` otherwise
     pub fn from_result(code: char, first_operand: usize) -> Result<Self, OperationError> {
+        Self::from_code(code, first_operand).ok_or(OperationError::InvalidOperationCode { code })
+    }
+
+    /// Creates the `Operation` from a code and its first operand, in a `const` context
+    /// # Arguments
+    ///  - code: An char defined as `OPCODE` constant in the `codes` module
+    ///  - first_operand: The first operand
+    /// # Return
+    /// An `Option` having an `Operation` if the code is valid, `None` otherwise
+    pub const fn from_code(code: char, first_operand: usize) -> Option<Self> {
         match code {
-            OPCODE_ADD => Ok(Operation::Add(first_operand)),
-            OPCODE_SUB => Ok(Operation::Sub(first_operand)),
-            OPCODE_MUL => Ok(Operation::Mul(first_operand)),
-            OPCODE_DIV => Ok(Operation::Div(first_operand)),
-            code => Err(OperationError::InvalidOperationCode(code)),
+            OPCODE_ADD => Some(Operation::Add(first_operand)),
+            OPCODE_SUB => Some(Operation::Sub(first_operand)),
+            OPCODE_MUL => Some(Operation::Mul(first_operand)),
+            OPCODE_DIV => Some(Operation::Div(first_operand)),
+            OPCODE_GCD => Some(Operation::Gcd(first_operand)),
+            OPCODE_LCM => Some(Operation::Lcm(first_operand)),
+            _ => None,
+        }
+    }
+
+    /// The operation code of the `Operation`
+    /// # Return
+    /// A char defined as `OPCODE` constant in the `codes` module
+    pub const fn code(&self) -> char {
+        match self {
+            Self::Add(_) => OPCODE_ADD,
+            Self::Sub(_) => OPCODE_SUB,
+            Self::Mul(_) => OPCODE_MUL,
+            Self::Div(_) => OPCODE_DIV,
+            Self::Gcd(_) => OPCODE_GCD,
+            Self::Lcm(_) => OPCODE_LCM,
+        }
+    }
+
+    /// The first operand of the `Operation`
+    pub const fn first_operand(&self) -> usize {
+        match self {
+            Self::Add(first_operand)
+            | Self::Sub(first_operand)
+            | Self::Mul(first_operand)
+            | Self::Div(first_operand)
+            | Self::Gcd(first_operand)
+            | Self::Lcm(first_operand) => *first_operand,
+        }
+    }
+
+    /// The arithmetic symbol of the `Operation` (e.g. `+`)
+    pub(crate) const fn symbol(&self) -> &'static str {
+        match self {
+            Self::Add(_) => "+",
+            Self::Sub(_) => "-",
+            Self::Mul(_) => "*",
+            Self::Div(_) => "/",
+            Self::Gcd(_) => "gcd",
+            Self::Lcm(_) => "lcm",
         }
     }
 
@@ -81,12 +188,27 @@ impl Operation {
     /// # Return
     /// A `Result` having a the arithmetic result of the operation if valid, `OperationError` otherwise
     pub fn apply(&self, second_operand: String) -> Result<usize, OperationError> {
-        trace!("{:?} {}", self, second_operand);
-        let parsed = second_operand
-            .parse::<usize>()
-            .map_err(|err| OperationError::InvalidSecondOperand(second_operand, err.to_string()))?;
-        trace!("parsed={}", parsed);
-        self.apply_result(parsed)
+        self.apply_rounded(second_operand, RoundingMode::default())
+    }
+
+    /// Applies the `Operation` using a second operand, rounding divisions as requested
+    /// # Arguments
+    ///  - second_operand: A string to be parsed as second operand of the operation
+    ///  - rounding: How a division rounds its quotient
+    /// # Return
+    /// A `Result` having a the arithmetic result of the operation if valid, `OperationError` otherwise
+    pub fn apply_rounded(
+        &self,
+        second_operand: String,
+        rounding: RoundingMode,
+    ) -> Result<usize, OperationError> {
+        let parsed = second_operand.parse::<usize>().map_err(|err| {
+            OperationError::InvalidSecondOperand {
+                operand: second_operand,
+                message: err.to_string(),
+            }
+        })?;
+        self.apply_result_rounded(parsed, rounding)
     }
 
     /// Applies the `Operation` using a previous result as second operand
@@ -95,20 +217,366 @@ impl Operation {
     /// # Return
     /// A `Result` having a the arithmetic result of the operation if valid, `OperationError` otherwise
     pub fn apply_result(&self, second_operand: usize) -> Result<usize, OperationError> {
-        trace!("{:?} {}", self, second_operand);
+        self.apply_result_rounded(second_operand, RoundingMode::default())
+    }
+
+    /// Applies the `Operation` using a previous result as second operand, rounding divisions as
+    /// requested
+    /// # Arguments
+    ///  - second_operand:  The previous result
+    ///  - rounding: How a division rounds its quotient
+    /// # Return
+    /// A `Result` having a the arithmetic result of the operation if valid, `OperationError` otherwise
+    pub fn apply_result_rounded(
+        &self,
+        second_operand: usize,
+        rounding: RoundingMode,
+    ) -> Result<usize, OperationError> {
+        let result = self
+            .checked_apply(second_operand)
+            .ok_or(OperationError::OverflowError)?;
+        // Operands are natural numbers, so only rounding up differs from truncating
+        let Self::Div(first_operand) = self else {
+            return Ok(result);
+        };
+        let remainder = first_operand % second_operand;
+        let up = match rounding {
+            RoundingMode::Trunc | RoundingMode::Floor | RoundingMode::Euclid => false,
+            RoundingMode::Ceil => remainder > 0,
+            RoundingMode::Round => remainder >= second_operand - remainder,
+        };
+        match up {
+            true => result.checked_add(1).ok_or(OperationError::OverflowError),
+            false => Ok(result),
+        }
+    }
+
+    /// Applies the `Operation` to a second operand, wrapping around at the bounds of `usize` as
+    /// the hardware does, e.g. `0 - 1` is `usize::MAX`
+    /// # Arguments
+    ///  - second_operand: The second operand
+    /// # Return
+    /// An `Option` having the wrapped result of the operation, `None` for a division by zero
+    pub const fn wrapping_apply(&self, second_operand: usize) -> Option<usize> {
         match self {
-            Self::Add(first_operand) => first_operand
-                .checked_add(second_operand)
-                .ok_or(OperationError::OverflowError),
-            Self::Sub(first_operand) => first_operand
-                .checked_sub(second_operand)
-                .ok_or(OperationError::OverflowError),
-            Self::Mul(first_operand) => first_operand
-                .checked_mul(second_operand)
-                .ok_or(OperationError::OverflowError),
-            Self::Div(first_operand) => first_operand
-                .checked_div(second_operand)
-                .ok_or(OperationError::OverflowError),
+            Self::Add(first_operand) => Some(first_operand.wrapping_add(second_operand)),
+            Self::Sub(first_operand) => Some(first_operand.wrapping_sub(second_operand)),
+            Self::Mul(first_operand) => Some(first_operand.wrapping_mul(second_operand)),
+            Self::Lcm(first_operand) if *first_operand > 0 => Some(
+                (second_operand / gcd(*first_operand, second_operand)).wrapping_mul(*first_operand),
+            ),
+            _ => self.checked_apply(second_operand),
         }
     }
+
+    /// Applies the `Operation` to a second operand as signed two's-complement integers of a width,
+    /// the operands and the result being their bit patterns (e.g. `0xFE` for `-2` in 8 bits).
+    /// Divisions round their quotient towards zero unless asked otherwise, e.g. `-7 / 2` is `-3`
+    /// # Arguments
+    ///  - second_operand: The bit pattern of the second operand
+    ///  - width: The number of bits of the integers, from 1 to 64
+    ///  - rounding: How a division rounds its quotient
+    /// # Return
+    /// A `Result` having the bit pattern of the result if it fits the width, `OperationError`
+    /// otherwise (e.g. for `-128 / -1` in 8 bits)
+    pub fn signed_apply(
+        &self,
+        second_operand: usize,
+        width: u32,
+        rounding: RoundingMode,
+    ) -> Result<usize, OperationError> {
+        let bound = 1i128 << (width - 1);
+        match self.signed_exact(second_operand, width, rounding) {
+            Some(exact) if (-bound..bound).contains(&exact) => {
+                Ok(bits::from_signed(exact as i64, width))
+            }
+            _ => Err(OperationError::OverflowError),
+        }
+    }
+
+    /// Applies the `Operation` to a second operand as signed two's-complement integers of a width,
+    /// wrapping around at the bounds of the width as the hardware does, e.g. `127 + 1` is `-128`
+    /// and `-128 / -1` is `-128` in 8 bits
+    /// # Arguments
+    ///  - second_operand: The bit pattern of the second operand
+    ///  - width: The number of bits of the integers, from 1 to 64
+    ///  - rounding: How a division rounds its quotient
+    /// # Return
+    /// An `Option` having the bit pattern of the wrapped result, `None` for a division by zero
+    pub fn signed_wrapping_apply(
+        &self,
+        second_operand: usize,
+        width: u32,
+        rounding: RoundingMode,
+    ) -> Option<usize> {
+        // Truncating keeps the low bits, which are those of the wrapped result
+        self.signed_exact(second_operand, width, rounding)
+            .map(|exact| bits::from_signed(exact as i64, width))
+    }
+
+    /// The exact result of the `Operation` applied to the signed integers of a width having the
+    /// bit patterns of the operands, `None` for a division by zero. Operands of 64 bits at most
+    /// cannot overflow `i128`
+    fn signed_exact(
+        &self,
+        second_operand: usize,
+        width: u32,
+        rounding: RoundingMode,
+    ) -> Option<i128> {
+        let first_operand = bits::signed(self.first_operand(), width) as i128;
+        let second_operand = bits::signed(second_operand, width) as i128;
+        let divisor = || {
+            gcd(
+                first_operand.unsigned_abs() as usize,
+                second_operand.unsigned_abs() as usize,
+            ) as i128
+        };
+        match self {
+            Self::Add(_) => Some(first_operand + second_operand),
+            Self::Sub(_) => Some(first_operand - second_operand),
+            Self::Mul(_) => Some(first_operand * second_operand),
+            Self::Div(_) => signed_div(first_operand, second_operand, rounding),
+            Self::Gcd(_) => Some(divisor()),
+            // The least common multiple of 0 and any number is 0
+            Self::Lcm(_) if first_operand == 0 || second_operand == 0 => Some(0),
+            Self::Lcm(_) => Some((first_operand / divisor() * second_operand).abs()),
+        }
+    }
+
+    /// Applies the `Operation` to a second operand, clamping at the bounds of `usize`, e.g. `0 - 1`
+    /// is `0`
+    /// # Arguments
+    ///  - second_operand: The second operand
+    /// # Return
+    /// An `Option` having the clamped result of the operation, `None` for a division by zero
+    pub const fn saturating_apply(&self, second_operand: usize) -> Option<usize> {
+        match self {
+            Self::Add(first_operand) => Some(first_operand.saturating_add(second_operand)),
+            Self::Sub(first_operand) => Some(first_operand.saturating_sub(second_operand)),
+            Self::Mul(first_operand) => Some(first_operand.saturating_mul(second_operand)),
+            Self::Lcm(first_operand) if *first_operand > 0 => Some(
+                (second_operand / gcd(*first_operand, second_operand))
+                    .saturating_mul(*first_operand),
+            ),
+            _ => self.checked_apply(second_operand),
+        }
+    }
+
+    /// Applies the `Operation` to a second operand, in a `const` context
+    /// # Arguments
+    ///  - second_operand: The second operand
+    /// # Return
+    /// An `Option` having the arithmetic result of the operation if valid, `None` if it overflows
+    pub const fn checked_apply(&self, second_operand: usize) -> Option<usize> {
+        match self {
+            Self::Add(first_operand) => first_operand.checked_add(second_operand),
+            Self::Sub(first_operand) => first_operand.checked_sub(second_operand),
+            Self::Mul(first_operand) => first_operand.checked_mul(second_operand),
+            Self::Div(first_operand) => first_operand.checked_div(second_operand),
+            Self::Gcd(first_operand) => Some(gcd(*first_operand, second_operand)),
+            // The least common multiple of 0 and any number is 0
+            Self::Lcm(0) => Some(0),
+            Self::Lcm(first_operand) => {
+                (second_operand / gcd(*first_operand, second_operand)).checked_mul(*first_operand)
+            }
+        }
+    }
+}
+
+/// The greatest common divisor of two numbers, by the Euclidean algorithm. That of 0 and any
+/// number is the number itself
+const fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b > 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// The quotient of two signed numbers, rounded as requested, `None` if the divisor is 0
+const fn signed_div(dividend: i128, divisor: i128, rounding: RoundingMode) -> Option<i128> {
+    let Some(quotient) = dividend.checked_div(divisor) else {
+        return None;
+    };
+    let remainder = dividend % divisor;
+    // The sign of the exact quotient, which truncating rounds towards zero
+    let sign = dividend.signum() * divisor.signum();
+    let rounded = match rounding {
+        RoundingMode::Floor if remainder != 0 && sign < 0 => quotient - 1,
+        RoundingMode::Ceil if remainder != 0 && sign > 0 => quotient + 1,
+        RoundingMode::Round if remainder.abs() * 2 >= divisor.abs() => quotient + sign,
+        RoundingMode::Euclid => dividend.div_euclid(divisor),
+        _ => quotient,
+    };
+    Some(rounded)
+}
+
+/// Writes the first operand followed by the symbol of the operation (e.g. `3 +`)
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.first_operand(), self.symbol())
+    }
+}
+
+/// Reads an operation written by `Display` (e.g. `3 +`)
+impl FromStr for Operation {
+    type Err = OperationError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (first_operand, symbol) =
+            text.rsplit_once(' ')
+                .ok_or_else(|| OperationError::InvalidFirstOperand {
+                    operand: text.to_string(),
+                    message: String::new(),
+                })?;
+        let code = match symbol {
+            "+" => OPCODE_ADD,
+            "-" => OPCODE_SUB,
+            "*" => OPCODE_MUL,
+            "/" => OPCODE_DIV,
+            "gcd" => OPCODE_GCD,
+            "lcm" => OPCODE_LCM,
+            _ => {
+                let invalid = symbol.chars().next().unwrap_or(' ');
+                return Err(OperationError::InvalidOperationCode { code: invalid });
+            }
+        };
+        Self::from(code, first_operand.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::operation::{Operation, OperationError};
+    use crate::options::RoundingMode;
+
+    #[test]
+    fn test_display() {
+        let operations = [
+            Operation::Add(3),
+            Operation::Sub(0),
+            Operation::Mul(12),
+            Operation::Div(7),
+            Operation::Gcd(12),
+            Operation::Lcm(4),
+        ];
+        assert_eq!("3 +", Operation::Add(3).to_string());
+        assert_eq!("7 /", Operation::Div(7).to_string());
+        assert_eq!("12 gcd", Operation::Gcd(12).to_string());
+        for operation in operations {
+            assert_eq!(Ok(operation), operation.to_string().parse());
+            assert_eq!(
+                Some(operation),
+                Operation::from_code(operation.code(), operation.first_operand())
+            );
+        }
+        assert_eq!(
+            Err(OperationError::InvalidOperationCode { code: '%' }),
+            "3 %".parse::<Operation>()
+        );
+        assert!(matches!(
+            "x +".parse::<Operation>(),
+            Err(OperationError::InvalidFirstOperand { .. })
+        ));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let error = OperationError::InvalidOperationCode { code: '%' };
+        assert_eq!(
+            (None, Some('%')),
+            (error.position(), error.offending_char())
+        );
+        let error = OperationError::InvalidOperand { start: 3, end: 5 };
+        assert_eq!((Some(3), None), (error.position(), error.offending_char()));
+    }
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(Ok(6), Operation::Gcd(12).apply_result(18));
+        assert_eq!(Ok(36), Operation::Lcm(12).apply_result(18));
+        assert_eq!(Ok(7), Operation::Gcd(0).apply_result(7));
+        assert_eq!(Ok(0), Operation::Lcm(0).apply_result(7));
+        assert_eq!(Ok(0), Operation::Lcm(7).apply_result(0));
+        assert_eq!(
+            Err(OperationError::OverflowError),
+            Operation::Lcm(usize::MAX).apply_result(2)
+        );
+    }
+
+    #[test]
+    fn test_wrapping_apply() {
+        assert_eq!(Some(usize::MAX), Operation::Sub(0).wrapping_apply(1));
+        assert_eq!(Some(1), Operation::Add(usize::MAX).wrapping_apply(2));
+        assert_eq!(
+            Some(usize::MAX - 1),
+            Operation::Mul(usize::MAX).wrapping_apply(2)
+        );
+        assert_eq!(Some(3), Operation::Div(7).wrapping_apply(2));
+        assert_eq!(None, Operation::Div(7).wrapping_apply(0));
+    }
+
+    #[test]
+    fn test_signed_apply() {
+        let trunc = RoundingMode::Trunc;
+        assert_eq!(Ok(0xFE), Operation::Sub(3).signed_apply(5, 8, trunc));
+        assert_eq!(Ok(0xFD), Operation::Div(0xF9).signed_apply(2, 8, trunc));
+        assert_eq!(
+            Ok(0xFC),
+            Operation::Div(0xF9).signed_apply(2, 8, RoundingMode::Floor)
+        );
+        assert_eq!(
+            Ok(4),
+            Operation::Div(0xF9).signed_apply(0xFE, 8, RoundingMode::Ceil)
+        );
+        assert_eq!(Ok(6), Operation::Gcd(0xF4).signed_apply(0xEE, 8, trunc));
+        assert_eq!(Ok(12), Operation::Lcm(0xFC).signed_apply(6, 8, trunc));
+        assert_eq!(
+            Err(OperationError::OverflowError),
+            Operation::Div(0x80).signed_apply(0xFF, 8, trunc)
+        );
+        assert_eq!(
+            Err(OperationError::OverflowError),
+            Operation::Add(0x7FFF).signed_apply(1, 16, trunc)
+        );
+        assert_eq!(
+            Err(OperationError::OverflowError),
+            Operation::Div(3).signed_apply(0, 8, trunc)
+        );
+        assert_eq!(
+            Some(0x80),
+            Operation::Div(0x80).signed_wrapping_apply(0xFF, 8, trunc)
+        );
+        assert_eq!(
+            Some(0x8000),
+            Operation::Add(0x7FFF).signed_wrapping_apply(1, 16, trunc)
+        );
+        assert_eq!(
+            Some(0),
+            Operation::Mul(usize::MAX).signed_wrapping_apply(0, 64, trunc)
+        );
+        assert_eq!(
+            Some(1 << 63),
+            Operation::Div(1 << 63).signed_wrapping_apply(usize::MAX, 64, trunc)
+        );
+        assert_eq!(None, Operation::Div(3).signed_wrapping_apply(0, 8, trunc));
+    }
+
+    #[test]
+    fn test_saturating_apply() {
+        assert_eq!(Some(0), Operation::Sub(0).saturating_apply(1));
+        assert_eq!(
+            Some(usize::MAX),
+            Operation::Add(usize::MAX).saturating_apply(2)
+        );
+        assert_eq!(
+            Some(usize::MAX),
+            Operation::Mul(usize::MAX).saturating_apply(2)
+        );
+        assert_eq!(
+            Some(usize::MAX),
+            Operation::Lcm(usize::MAX).saturating_apply(2)
+        );
+        assert_eq!(Some(5), Operation::Sub(7).saturating_apply(2));
+        assert_eq!(None, Operation::Div(7).saturating_apply(0));
+    }
 }
@@ -0,0 +1,168 @@
+/// Policy applied to operands written with leading zeros (e.g. `007`)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LeadingZeroPolicy {
+    /// Leading zeros are silently accepted
+    #[default]
+    Accept,
+    /// Leading zeros are accepted, but a warning is logged
+    Warn,
+    /// Leading zeros are rejected with a `ParseError::LeadingZero`
+    Reject,
+}
+
+/// How input following a complete expression (e.g. the stray `f` in `3a2fc4`) is treated
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TrailingInputPolicy {
+    /// The trailing input is parsed as the rest of the expression, failing with the usual errors
+    #[default]
+    Unchecked,
+    /// The trailing input is rejected with a `ParseError::TrailingInput` reporting its span
+    Strict,
+    /// The parse process stops at the end of the first complete expression
+    Lenient,
+}
+
+/// How divisions round quotients which are not whole (e.g. `7d2`)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RoundingMode {
+    /// Toward zero (e.g. `3`)
+    #[default]
+    Trunc,
+    /// Toward negative infinity, the same as `Trunc` for the natural numbers
+    Floor,
+    /// Toward positive infinity (e.g. `4`)
+    Ceil,
+    /// To the nearest quotient, halves away from zero (e.g. `4`, but `2` for `7d3`)
+    Round,
+    /// The Euclidean quotient, leaving a non-negative remainder, the same as `Floor` for the
+    /// natural numbers
+    Euclid,
+}
+
+/// The order the operations of an expression are applied in, stated explicitly so that the
+/// results stored by downstream systems record which semantics produced them. More orders may be
+/// added, so matches need a wildcard arm
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[non_exhaustive]
+pub enum EvalOrder {
+    /// Every operation of a group is applied to the result so far, from left to right, whatever
+    /// its conventional precedence (e.g. `3a2c4` is `20`)
+    #[default]
+    LeftToRight,
+}
+
+/// Options tuning the behaviour of the `Parser`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserOptions {
+    /// How operands having leading zeros are treated
+    pub leading_zeros: LeadingZeroPolicy,
+    /// Whether operands may be written as uppercase Roman numerals (e.g. `XIVaII`)
+    pub roman_numerals: bool,
+    /// The base, between 2 and 36, of the numeric operands. Digits above 9 are uppercase letters,
+    /// which take precedence over Roman numerals and cell references
+    pub radix: u32,
+    /// How input following a complete expression is treated
+    pub trailing_input: TrailingInputPolicy,
+    /// The largest number of digits an operand may be written with, unlimited if `None`
+    pub max_digits: Option<usize>,
+    /// The deepest parenthesis nesting allowed, unlimited if `None`
+    pub max_depth: Option<usize>,
+    /// The largest number of characters an expression may have, unlimited if `None`
+    pub max_length: Option<usize>,
+    /// The largest number of operations an expression may apply, percentages included, unlimited
+    /// if `None`
+    pub max_operations: Option<usize>,
+    /// How divisions round their quotients
+    pub rounding: RoundingMode,
+    /// Whether uppercase operation codes (e.g. `3A2`) are accepted. They take precedence over the
+    /// digits above 9, the Roman numerals and the cell references sharing their letters
+    pub case_insensitive_opcodes: bool,
+    /// Whether the operations overflowing wrap around at the bounds of `usize` instead of failing,
+    /// for demonstrating integer overflow
+    pub wrapping: bool,
+    /// The width, one of `bits::WIDTHS`, of the signed two's-complement integers the operands and
+    /// the results are, if emulated (e.g. `Some(8)` for `i8`). The results, as well as the values
+    /// of the events, are then the bit patterns of the integers (e.g. `254` for `-2` in 8 bits),
+    /// which `bits::signed` reads back. The integers are unsigned `usize` if `None`
+    pub signed_width: Option<u32>,
+    /// The order the operations are applied in, only `EvalOrder::LeftToRight` for now
+    pub eval_order: EvalOrder,
+    /// Whether the parentheses which change nothing (e.g. `e2f`) are reported to the diagnostics
+    /// sink as `Notice`s
+    pub redundant_parentheses: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            leading_zeros: LeadingZeroPolicy::default(),
+            roman_numerals: false,
+            radix: 10,
+            trailing_input: TrailingInputPolicy::default(),
+            max_digits: None,
+            max_depth: None,
+            max_length: None,
+            max_operations: None,
+            rounding: RoundingMode::default(),
+            case_insensitive_opcodes: false,
+            wrapping: false,
+            signed_width: None,
+            eval_order: EvalOrder::default(),
+            redundant_parentheses: false,
+        }
+    }
+}
+
+/// The limits bounding the work a parse process does, set together on the `ParserOptions`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Limits {
+    /// The largest number of digits an operand may be written with, unlimited if `None`
+    pub max_digits: Option<usize>,
+    /// The deepest parenthesis nesting allowed, unlimited if `None`
+    pub max_depth: Option<usize>,
+    /// The largest number of characters an expression may have, unlimited if `None`
+    pub max_length: Option<usize>,
+    /// The largest number of operations an expression may apply, percentages included, unlimited
+    /// if `None`
+    pub max_operations: Option<usize>,
+}
+
+/// Implementation of the limit presets
+impl Limits {
+    /// No limits, the default of the `Parser`, which still fails on parenthesis nesting deeper
+    /// than `MAX_RECURSION`
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Limits for untrusted input, e.g. formulas submitted to a web service. Operands up to the
+    /// largest `usize` are allowed, while the length, the nesting and the operations applied are
+    /// kept well below what a hostile input needs. The length applies once the bindings are
+    /// desugared, so it also bounds the memory used, and the nesting bounds the recursion
+    pub fn untrusted() -> Self {
+        Self {
+            max_digits: Some(20),
+            max_depth: Some(64),
+            max_length: Some(4096),
+            max_operations: Some(1024),
+        }
+    }
+}
+
+/// Implementation of the parser options
+impl ParserOptions {
+    /// Set all the limits of the options at once
+    /// # Arguments
+    ///  - limits: The `Limits` to set, e.g. `Limits::untrusted()`
+    /// # Return
+    /// The `ParserOptions` having the given limits
+    pub fn with_limits(self, limits: Limits) -> Self {
+        Self {
+            max_digits: limits.max_digits,
+            max_depth: limits.max_depth,
+            max_length: limits.max_length,
+            max_operations: limits.max_operations,
+            ..self
+        }
+    }
+}
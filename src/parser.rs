@@ -1,27 +1,175 @@
+use std::cell::RefCell;
+use std::cmp::Ordering as CmpOrdering;
 use std::iter::Peekable;
 use crate::parser::ParseError::{EmptyExpression, IllegalState, UnbalancedParenthesis};
-use log::{debug, trace};
 use std::str::Chars;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::num::NonZeroUsize;
+use std::{panic, thread};
+use std::time::Instant;
+use std::fmt;
+use std::str::FromStr;
 
+use crate::bits::{self, WIDTHS};
+use crate::cell::CellResolver;
+use crate::constant::{self, ConstEvalError};
+use crate::definitions::{self, DefinitionError, Definitions};
+use crate::diagnostics::{self, diagnostic, DiagnosticsSink, Level, Notice};
+use crate::events::ParseEvent;
+use crate::grammar;
 use crate::operation::{codes::*, Operation, OperationError};
+use crate::options::{LeadingZeroPolicy, Limits, ParserOptions, TrailingInputPolicy};
+use crate::report::{EvaluationReport, Limit};
+use crate::roman;
+use crate::stats::Stats;
+use crate::telemetry::{Phase, Telemetry};
 
-/// Errors that the parsing process can cause
+/// The deepest parenthesis nesting any parse process recurses into, whatever the options, so that
+/// deep inputs fail with `ParseError::DepthLimitExceeded` well before exhausting a thread stack
+pub const MAX_RECURSION: usize = 128;
+
+/// Errors that the parsing process can cause. More may be added, so matches need a wildcard arm
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum ParseError {
     /// The expression to parse is empty
     EmptyExpression,
-    /// There is an error converting an operand from string to unsigned integer (operand, error message)
-    ParseDigitError(String, String),
+    /// There is an error converting an operand from string to unsigned integer
+    ParseDigitError { operand: String, message: String },
     /// The instantiation or application of an operation failed (`OperationError` for further information)
-    InvalidOperation(OperationError),
-    /// The expression is not arithmetically correct (invalid character)
-    MalformedExpression(String),
-    /// The number of parenthesis in the expression does not equal (open/close parenthesis operation code to indicate)
-    UnbalancedParenthesis(String),
-    /// The parser encountered an unexpected symbol (unexpected character, parser state, current operation)
-    UnexpectedSymbol(String, ParserState, Option<Operation>),
+    InvalidOperation { source: OperationError },
+    /// The expression is not arithmetically correct (invalid character at `position`)
+    MalformedExpression { position: usize, found: String },
+    /// The number of parenthesis in the expression does not equal (open/close parenthesis operation code to indicate,
+    /// `position` of the first unmatched one)
+    UnbalancedParenthesis { position: usize, parenthesis: String },
+    /// The parser encountered an unexpected symbol at `position` in the given state
+    UnexpectedSymbol {
+        position: usize,
+        found: String,
+        state: ParserState,
+        operation: Option<Operation>,
+    },
     /// The parser ended in an illegal state
-    IllegalState(String),
+    IllegalState { reason: String },
+    /// The operand has leading zeros and the options reject them
+    LeadingZero { operand: String },
+    /// The operand is not a well-formed Roman numeral
+    InvalidRomanNumeral { numeral: String },
+    /// The cell reference could not be resolved
+    UnresolvedCell { reference: String },
+    /// The radix of the operands is not between 2 and 36
+    InvalidRadix { radix: u32 },
+    /// Input follows a complete expression and the options reject it
+    TrailingInput { position: usize, found: String },
+    /// An operand has more digits than the options allow (`position` of its first digit)
+    OperandTooLong { position: usize, max_digits: usize },
+    /// The parenthesis nesting is deeper than the options allow (`position` of the parenthesis)
+    NestingTooDeep { position: usize, max_depth: usize },
+    /// The expression has more characters than the options allow
+    ExpressionTooLong { max_length: usize },
+    /// The parse process was cancelled through its cancellation token (`position` reached)
+    Cancelled { position: usize },
+    /// The parenthesis nesting is deeper than `MAX_RECURSION`, whatever the options (`position` of
+    /// the parenthesis)
+    DepthLimitExceeded { position: usize, max_depth: usize },
+    /// The local bindings, the builtins or the calls to named formulas of the expression could not
+    /// be desugared (`DefinitionError` for further information)
+    InvalidBinding { source: Box<DefinitionError> },
+    /// The expression applies more operations than the options allow (`position` of the first
+    /// operation over the limit)
+    TooManyOperations { position: usize, max_operations: usize },
+    /// The width of the signed integers is not one of `bits::WIDTHS`
+    InvalidWidth { width: u32 },
+}
+
+/// Implementation of the parse errors
+impl ParseError {
+    /// The stable code identifying the kind of error, which never changes across versions.
+    /// Invalid operations have the code of their `OperationError`.
+    /// # Return
+    /// The code, from `E001` upwards
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            EmptyExpression => "E001",
+            ParseError::ParseDigitError { .. } => "E002",
+            ParseError::InvalidOperation { source } => source.error_code(),
+            ParseError::MalformedExpression { .. } => "E003",
+            UnbalancedParenthesis { .. } => "E004",
+            ParseError::UnexpectedSymbol { .. } => "E005",
+            IllegalState { .. } => "E006",
+            ParseError::LeadingZero { .. } => "E007",
+            ParseError::InvalidRomanNumeral { .. } => "E008",
+            ParseError::UnresolvedCell { .. } => "E009",
+            ParseError::InvalidRadix { .. } => "E010",
+            ParseError::TrailingInput { .. } => "E011",
+            ParseError::OperandTooLong { .. } => "E012",
+            ParseError::NestingTooDeep { .. } => "E013",
+            ParseError::ExpressionTooLong { .. } => "E014",
+            ParseError::Cancelled { .. } => "E015",
+            ParseError::DepthLimitExceeded { .. } => "E016",
+            ParseError::InvalidBinding { .. } => "E017",
+            ParseError::TooManyOperations { .. } => "E018",
+            ParseError::InvalidWidth { .. } => "E019",
+        }
+    }
+
+    /// The position in the expression as written where the error was detected, when the error
+    /// reports it. The position is in the desugared expression if the expression has local
+    /// bindings or builtins, since they have no counterpart in the expression as written.
+    /// # Return
+    /// An `Option` having the position, `None` if the error does not report one
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            ParseError::InvalidOperation { source } => source.position(),
+            ParseError::MalformedExpression { position, .. }
+            | UnbalancedParenthesis { position, .. }
+            | ParseError::UnexpectedSymbol { position, .. }
+            | ParseError::TrailingInput { position, .. }
+            | ParseError::OperandTooLong { position, .. }
+            | ParseError::NestingTooDeep { position, .. }
+            | ParseError::Cancelled { position }
+            | ParseError::DepthLimitExceeded { position, .. }
+            | ParseError::TooManyOperations { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+
+    /// Move the position of an error detected in the expanded expression to the character of the
+    /// expression as written which the offending one comes from
+    /// # Arguments
+    ///  - expansion: The `Expansion` the error was detected in
+    /// # Return
+    /// The `ParseError` with its position in the expression as written
+    fn relocated(mut self, expansion: &Expansion) -> Self {
+        match &mut self {
+            ParseError::MalformedExpression { position, .. }
+            | UnbalancedParenthesis { position, .. }
+            | ParseError::UnexpectedSymbol { position, .. }
+            | ParseError::Cancelled { position }
+            | ParseError::DepthLimitExceeded { position, .. }
+            | ParseError::TooManyOperations { position, .. } => {
+                *position = expansion.source(*position)
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// The character of the expression which caused the error, when the error reports it
+    /// # Return
+    /// An `Option` having the character, `None` if the error does not report one
+    pub fn offending_char(&self) -> Option<char> {
+        match self {
+            ParseError::InvalidOperation { source } => source.offending_char(),
+            ParseError::MalformedExpression { found: symbol, .. }
+            | UnbalancedParenthesis { parenthesis: symbol, .. }
+            | ParseError::UnexpectedSymbol { found: symbol, .. }
+            | ParseError::TrailingInput { found: symbol, .. } => symbol.chars().next(),
+            _ => None,
+        }
+    }
 }
 
 /// The legal states the parser can go through
@@ -37,10 +185,143 @@ pub enum ParserState {
     CloseParenthesis,
 }
 
+/// Writes the state as the expected input (e.g. `second operand`)
+impl fmt::Display for ParserState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParserState::FirstOperand => write!(f, "first operand"),
+            ParserState::Operation => write!(f, "operation"),
+            ParserState::SecondOperand => write!(f, "second operand"),
+            ParserState::CloseParenthesis => write!(f, "close parenthesis"),
+        }
+    }
+}
+
+/// Reads a state written by `Display` (e.g. `second operand`)
+impl FromStr for ParserState {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "first operand" => Ok(ParserState::FirstOperand),
+            "operation" => Ok(ParserState::Operation),
+            "second operand" => Ok(ParserState::SecondOperand),
+            "close parenthesis" => Ok(ParserState::CloseParenthesis),
+            _ => Err(IllegalState { reason: format!("Unknown parser state {}", text) }),
+        }
+    }
+}
+
 /// The parser structure
 pub struct Parser {
     /// The expression to parse
     expression: String,
+    /// The options tuning the parse process
+    options: ParserOptions,
+    /// The resolver of cell references, if they are enabled
+    cell_resolver: Option<Box<dyn CellResolver>>,
+    /// The named formulas and functions the expression can call, if any
+    definitions: Option<Definitions>,
+    /// The events collected during the parse process, if they are recorded
+    events: RefCell<Option<Vec<ParseEvent>>>,
+    /// The hooks notified about the parse process
+    telemetry: Option<Box<dyn Telemetry>>,
+    /// The token which, once set, cancels the parse process
+    cancellation: Option<Arc<AtomicBool>>,
+    /// The result the parse process starts from, if any
+    initial: Option<usize>,
+    /// The sink receiving the diagnostic messages
+    diagnostics: Box<dyn DiagnosticsSink>,
+}
+
+/// Iterator over the characters of an expression, keeping track of the position
+#[derive(Clone)]
+struct Cursor<'a> {
+    /// The characters left to consume
+    chars: Peekable<Chars<'a>>,
+    /// The number of characters consumed so far
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Create a cursor at the beginning of an expression
+    fn new(expression: &'a str) -> Self {
+        Self {
+            chars: expression.chars().peekable(),
+            position: 0,
+        }
+    }
+
+    /// Look at the next character without consuming it
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let char = self.chars.next()?;
+        self.position += 1;
+        Some(char)
+    }
+}
+
+/// An expression rewritten into plain decimal operands, knowing for each of its characters where
+/// the character it comes from is in the expression as written
+struct Expansion {
+    /// The rewritten expression
+    text: String,
+    /// The position in the expression as written of every character of the rewritten one
+    sources: Vec<usize>,
+    /// The number of characters of the expression as written
+    end: usize,
+}
+
+impl Expansion {
+    /// Start an empty expansion of an expression
+    /// # Arguments
+    ///  - end: The number of characters of the expression as written
+    fn new(end: usize) -> Self {
+        Self {
+            text: String::new(),
+            sources: Vec::new(),
+            end,
+        }
+    }
+
+    /// Append a character coming from a position of the expression as written
+    fn push(&mut self, char: char, source: usize) {
+        self.text.push(char);
+        self.sources.push(source);
+    }
+
+    /// Append characters all coming from a position of the expression as written, e.g. the
+    /// decimal digits of a Roman numeral starting there
+    fn push_str(&mut self, text: &str, source: usize) {
+        for char in text.chars() {
+            self.push(char, source);
+        }
+    }
+
+    /// Append characters copied from the expression as written, from a position onwards
+    fn push_verbatim(&mut self, text: &str, start: usize) {
+        for (offset, char) in text.chars().enumerate() {
+            self.push(char, start + offset);
+        }
+    }
+
+    /// Remove the last character, along with its position in the expression as written
+    fn pop(&mut self) -> Option<(char, usize)> {
+        Some((self.text.pop()?, self.sources.pop()?))
+    }
+
+    /// The position in the expression as written of a character of the rewritten one, the end of
+    /// the expression as written past its last character
+    fn source(&self, position: usize) -> usize {
+        self.sources.get(position).copied().unwrap_or(self.end)
+    }
 }
 
 /// The parser implementation
@@ -53,105 +334,909 @@ impl Parser {
     pub fn new(expression: String) -> Self {
         Self {
             expression,
+            options: ParserOptions::default(),
+            cell_resolver: None,
+            definitions: None,
+            events: RefCell::new(None),
+            telemetry: None,
+            cancellation: None,
+            initial: None,
+            diagnostics: diagnostics::default_sink(),
         }
     }
 
+    /// Replace the options of the parser
+    /// # Arguments
+    ///  - options: The options tuning the parse process
+    /// # Return
+    /// The `Parser` using the given options
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Bound the work of the parse process, e.g. with `Limits::untrusted()` for untrusted input.
+    /// The limits replace the ones of the options set before.
+    /// # Arguments
+    ///  - limits: The `Limits` of the parse process
+    /// # Return
+    /// The `Parser` enforcing the given limits
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.options = self.options.with_limits(limits);
+        self
+    }
+
+    /// Enable cell references (e.g. `A1aB2`) as operands
+    /// # Arguments
+    ///  - resolver: The `CellResolver` providing the values of the cells
+    /// # Return
+    /// The `Parser` resolving cell references through the given resolver
+    pub fn with_cell_resolver(mut self, resolver: impl CellResolver + 'static) -> Self {
+        self.cell_resolver = Some(Box::new(resolver));
+        self
+    }
+
+    /// Enable the calls to named formulas and functions (e.g. `@tax[100]`), expanded along with
+    /// the local bindings before parsing
+    /// # Arguments
+    ///  - definitions: The `Definitions` of the formulas, e.g. loaded from a definitions file
+    /// # Return
+    /// The `Parser` expanding the calls to the given formulas
+    pub fn with_definitions(mut self, definitions: Definitions) -> Self {
+        self.definitions = Some(definitions);
+        self
+    }
+
+    /// Notify the parse process to a `Telemetry` implementation
+    /// # Arguments
+    ///  - telemetry: The hooks to notify
+    /// # Return
+    /// The `Parser` notifying the given hooks
+    pub fn with_telemetry(mut self, telemetry: impl Telemetry + 'static) -> Self {
+        self.telemetry = Some(Box::new(telemetry));
+        self
+    }
+
+    /// Send the diagnostic messages to a sink other than the default one, the `log` crate with
+    /// the `log` feature
+    /// # Arguments
+    ///  - sink: The `DiagnosticsSink` receiving the messages
+    /// # Return
+    /// The `Parser` issuing its diagnostic messages to the given sink
+    pub fn with_diagnostics(mut self, sink: impl DiagnosticsSink + 'static) -> Self {
+        self.diagnostics = Box::new(sink);
+        self
+    }
+
+    /// Start the parse process from a result, which becomes the implicit first operand of an
+    /// expression starting with an operation code (e.g. `c4` multiplies it by 4)
+    /// # Arguments
+    ///  - result: The result to start from, typically the one of a previous expression
+    /// # Return
+    /// The `Parser` starting from the given result
+    pub fn with_initial(mut self, result: usize) -> Self {
+        self.initial = Some(result);
+        self
+    }
+
+    /// Allow another thread to cancel the parse process, which then fails with
+    /// `ParseError::Cancelled` at the next character
+    /// # Arguments
+    ///  - token: The flag to set to cancel the parse process
+    /// # Return
+    /// The `Parser` checking the given token
+    pub fn with_cancellation(mut self, token: Arc<AtomicBool>) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
     /// Parse process
     /// # Return
     /// A `Result` having the expression result if valid, `ParseError` otherwise
     pub fn parse(&self) -> Result<usize, ParseError> {
-        let mut data: Peekable<Chars> = self.expression.chars().peekable();
-        let open_brackets = data.clone().filter(|c| *c == OPCODE_OPEN).count();
-        let closed_brackets = data.clone().filter(|c| *c == OPCODE_CLOSE).count();
-        match (open_brackets, closed_brackets) {
-            (open_brackets, closed_brackets) if open_brackets > closed_brackets => Err(UnbalancedParenthesis(OPCODE_OPEN.to_string())),
-            (open_brackets, closed_brackets) if closed_brackets > open_brackets => Err(UnbalancedParenthesis(OPCODE_CLOSE.to_string())),
-            _ => {
-                let mut result = None;
-                while data.clone().count() > 0 {
-                    let res = self.parse_internal(&mut data, result)?;
-                    result = Some(res);
+        self.parse_prefix().map(|(result, _)| result)
+    }
+
+    /// Parse process, also reporting how much of the expression was consumed. The whole expression
+    /// is consumed unless `TrailingInputPolicy::Lenient` stops the parse process at the end of the
+    /// first complete expression.
+    /// # Return
+    /// A `Result` having the expression result and the number of characters consumed from the
+    /// expression if valid, `ParseError` otherwise
+    pub fn parse_prefix(&self) -> Result<(usize, usize), ParseError> {
+        let start = Instant::now();
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.parse_started(&self.expression);
+        }
+        let result = self.parse_expression();
+        if let Some(telemetry) = &self.telemetry {
+            match &result {
+                Ok((result, _)) => telemetry.parse_finished(*result, start.elapsed()),
+                Err(err) => telemetry.error_raised(err, start.elapsed()),
+            }
+        }
+        result
+    }
+
+    /// Parse the expression once expanded, up to the trailing input if it is ignored. The trailing
+    /// input is found before the expansion, so that its position is in the expression as written.
+    fn parse_expression(&self) -> Result<(usize, usize), ParseError> {
+        let start = Instant::now();
+        let radix = self.options.radix;
+        if !(2..=36).contains(&radix) {
+            return Err(ParseError::InvalidRadix { radix });
+        }
+        if let Some(width) = self.options.signed_width.filter(|width| !WIDTHS.contains(width)) {
+            return Err(ParseError::InvalidWidth { width });
+        }
+        // The local bindings, the builtins and the calls are plain operations once desugared, so
+        // that the limits apply to what is evaluated
+        let expression = match &self.definitions {
+            Some(formulas) => formulas.expand(&self.expression),
+            None => definitions::bind(&self.expression),
+        };
+        let expression =
+            expression.map_err(|source| ParseError::InvalidBinding { source: Box::new(source) })?;
+        self.check_limits(&expression)?;
+        let length = expression.chars().count();
+        let consumed = match (self.options.trailing_input, self.trailing_input(&expression)) {
+            (TrailingInputPolicy::Strict, Some(start)) => {
+                let content = expression.chars().skip(start).collect();
+                return Err(ParseError::TrailingInput { position: start, found: content });
+            }
+            (TrailingInputPolicy::Lenient, Some(start)) => {
+                diagnostic!(self, Warn,
+                    "ignoring trailing input after {} of {} characters", start, length);
+                start
+            }
+            _ => length,
+        };
+        let prefix: String = expression.chars().take(consumed).collect();
+        let expansion = self.expand(prefix)?;
+        self.check_parentheses(&expression);
+        let start = self.phase_finished(Phase::Lex, start);
+        // The errors are detected in the expansion, but reported where the expression as written
+        // has the offending character
+        if let Some(err) = Self::unbalanced_parenthesis(expansion.text.chars()) {
+            return Err(err.relocated(&expansion));
+        }
+        self.check_operations(&expansion.text).map_err(|err| err.relocated(&expansion))?;
+        self.check_signed_operands(&expansion.text)?;
+        let start = self.phase_finished(Phase::Parse, start);
+        let mut data = Cursor::new(&expansion.text);
+        let mut result = self.initial;
+        while data.clone().count() > 0 {
+            let res = self
+                .parse_internal(&mut data, result, 0)
+                .map_err(|err| err.relocated(&expansion))?;
+            result = Some(res);
+        }
+        self.phase_finished(Phase::Eval, start);
+        result.map(|result| (result, consumed)).ok_or(EmptyExpression)
+    }
+
+    /// Check that an expression has as many open parentheses as closed ones
+    /// # Arguments
+    ///  - expression: The characters of the expression
+    /// # Return
+    /// An `Option` having `ParseError::UnbalancedParenthesis` at the first unmatched parenthesis
+    /// of the kind in excess if unbalanced, `None` otherwise
+    fn unbalanced_parenthesis(expression: impl Iterator<Item = char>) -> Option<ParseError> {
+        let mut opened = Vec::new();
+        let mut unmatched = Vec::new();
+        for (position, char) in expression.enumerate() {
+            match char {
+                OPCODE_OPEN => opened.push(position),
+                OPCODE_CLOSE if opened.pop().is_none() => unmatched.push(position),
+                _ => {}
+            }
+        }
+        match opened.len().cmp(&unmatched.len()) {
+            CmpOrdering::Greater => Some(UnbalancedParenthesis {
+                position: opened[0],
+                parenthesis: OPCODE_OPEN.to_string(),
+            }),
+            CmpOrdering::Less => Some(UnbalancedParenthesis {
+                position: unmatched[0],
+                parenthesis: OPCODE_CLOSE.to_string(),
+            }),
+            CmpOrdering::Equal => None,
+        }
+    }
+
+    /// Notify the end of a phase, if telemetry is enabled
+    /// # Arguments
+    ///  - phase: The `Phase` finished
+    ///  - start: When the phase started
+    /// # Return
+    /// When the next phase starts
+    fn phase_finished(&self, phase: Phase, start: Instant) -> Instant {
+        if let Some(telemetry) = &self.telemetry {
+            telemetry.phase_finished(phase, start.elapsed());
+        }
+        Instant::now()
+    }
+
+    /// Find where the trailing input of the expression starts: the first unmatched closing
+    /// parenthesis or character foreign to the expression syntax, following a complete expression.
+    /// Percentages and the uppercase letters of the notations enabled by the options are operands.
+    /// # Arguments
+    ///  - expression: The expression, once desugared
+    /// # Return
+    /// An `Option` having the position of the trailing input if any, `None` otherwise
+    fn trailing_input(&self, expression: &str) -> Option<usize> {
+        let letters = self.options.roman_numerals
+            || self.cell_resolver.is_some()
+            || self.options.radix > 10;
+        let is_operand = |c: char| {
+            c.is_ascii_digit() || c == PERCENT || (letters && c.is_ascii_uppercase())
+        };
+        let mut depth = 0usize;
+        let mut last: Option<char> = None;
+        for (position, char) in expression.chars().enumerate() {
+            // Uppercase opcodes take precedence over the notations, as in the expansion
+            let char = match char.to_ascii_lowercase() {
+                code @ (OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD
+                | OPCODE_LCM | OPCODE_OPEN | OPCODE_CLOSE)
+                    if self.options.case_insensitive_opcodes => code,
+                _ => char,
+            };
+            let foreign = match char {
+                OPCODE_OPEN => {
+                    depth += 1;
+                    false
                 }
-                result.ok_or(EmptyExpression)
+                OPCODE_CLOSE if depth > 0 => {
+                    depth -= 1;
+                    false
+                }
+                OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD | OPCODE_LCM => false,
+                char => !is_operand(char),
+            };
+            if foreign {
+                let complete =
+                    matches!(last, Some(last) if last == OPCODE_CLOSE || is_operand(last));
+                return (complete && depth == 0).then_some(position);
+            }
+            last = Some(char);
+        }
+        None
+    }
+
+    /// Parse many independent expressions, distributing them across one thread per available core
+    /// # Arguments
+    ///  - expressions: The expressions to parse
+    ///  - options: The options tuning every parse process
+    /// # Return
+    /// The `Result` of every expression, in the order of the expressions
+    pub fn eval_many(
+        expressions: &[&str],
+        options: &ParserOptions,
+    ) -> Vec<Result<usize, ParseError>> {
+        Self::in_parallel(expressions, |expression| {
+            Parser::new(expression.to_string())
+                .with_options(options.clone())
+                .parse()
+        })
+    }
+
+    /// Parse an expression against many sets of cells, e.g. a formula over the rows of a table,
+    /// distributing them across one thread per available core
+    /// # Arguments
+    ///  - expression: The expression to parse, referencing the cells
+    ///  - contexts: The cell resolvers, one per evaluation
+    ///  - options: The options tuning every parse process
+    /// # Return
+    /// The `Result` of the expression against every context, in the order of the contexts
+    pub fn eval_batch<C>(
+        expression: &str,
+        contexts: &[C],
+        options: &ParserOptions,
+    ) -> Vec<Result<usize, ParseError>>
+    where
+        C: CellResolver + Clone + Sync + 'static,
+    {
+        Self::in_parallel(contexts, |context| {
+            Parser::new(expression.to_string())
+                .with_options(options.clone())
+                .with_cell_resolver(context.clone())
+                .parse()
+        })
+    }
+
+    /// Parse once per item, distributing the items across one thread per available core
+    fn in_parallel<T, F>(items: &[T], parse: F) -> Vec<Result<usize, ParseError>>
+    where
+        T: Sync,
+        F: Fn(&T) -> Result<usize, ParseError> + Sync,
+    {
+        let workers = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let chunk_size = items.len().div_ceil(workers).max(1);
+        let parse = &parse;
+        thread::scope(|scope| {
+            let workers: Vec<_> = items
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || chunk.iter().map(parse).collect::<Vec<_>>()))
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_else(|err| panic::resume_unwind(err)))
+                .collect()
+        })
+    }
+
+    /// Fast path parsing an ASCII expression directly from its bytes, for throughput-sensitive
+    /// batch workloads. Only decimal operands, operation codes and parentheses are accepted and the
+    /// parser options do not apply. Well-formed expressions yield the same result as `parse`.
+    /// # Arguments
+    ///  - bytes: The expression to parse
+    /// # Return
+    /// A `Result` having the expression result if valid, `ParseError` otherwise
+    pub fn parse_bytes(bytes: &[u8]) -> Result<usize, ParseError> {
+        constant::eval_bytes(bytes).map_err(|err| match err {
+            ConstEvalError::Empty => EmptyExpression,
+            ConstEvalError::UnexpectedByte(position) if bytes[position] == OPCODE_CLOSE as u8 => {
+                UnbalancedParenthesis { position, parenthesis: OPCODE_CLOSE.to_string() }
+            }
+            ConstEvalError::UnexpectedByte(position) => ParseError::MalformedExpression {
+                position,
+                found: String::from_utf8_lossy(&bytes[position..=position]).to_string(),
+            },
+            ConstEvalError::TooDeep(position) => {
+                ParseError::DepthLimitExceeded { position, max_depth: MAX_RECURSION }
+            }
+            // Closing parentheses in excess are unexpected bytes, so only open ones are left
+            ConstEvalError::UnexpectedEnd => {
+                Self::unbalanced_parenthesis(bytes.iter().map(|b| *b as char)).unwrap_or_else(|| {
+                    IllegalState {
+                        reason: "Expression ended while an operand was expected".to_string(),
+                    }
+                })
+            }
+            ConstEvalError::OperandTooLarge(position) => {
+                let digits = bytes[position..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .map(|b| *b as char)
+                    .collect();
+                ParseError::ParseDigitError {
+                    operand: digits,
+                    message: "number too large to fit in target type".to_string(),
+                }
+            }
+            ConstEvalError::Overflow(_) => {
+                ParseError::InvalidOperation { source: OperationError::OverflowError }
+            }
+        })
+    }
+
+    /// Parse process, recording the events the parser goes through
+    /// # Return
+    /// A tuple having the `Result` of the parse process and the `ParseEvent`s up to its end
+    pub fn parse_with_events(&self) -> (Result<usize, ParseError>, Vec<ParseEvent>) {
+        self.events.replace(Some(Vec::new()));
+        let result = self.parse();
+        let events = self.events.take().unwrap_or_default();
+        (result, events)
+    }
+
+    /// Parse process, collecting statistics about the work performed
+    /// # Return
+    /// A tuple having the `Result` of the parse process and the `Stats` up to its end
+    pub fn parse_with_stats(&self) -> (Result<usize, ParseError>, Stats) {
+        let (result, events) = self.parse_with_events();
+        (result, Stats::from_events(&events))
+    }
+
+    /// Parse process, reporting how the result was produced, for auditing systems storing it
+    /// # Return
+    /// The `EvaluationReport` of the parse process, having its `Result`
+    pub fn parse_report(&self) -> EvaluationReport {
+        let (value, stats) = self.parse_with_stats();
+        let limits_hit = value.as_ref().err().and_then(Limit::of).into_iter().collect();
+        EvaluationReport {
+            value,
+            ops_count: stats.operations(),
+            depth: stats.max_depth,
+            mode: self.options.eval_order,
+            limits_hit,
+        }
+    }
+
+    /// The grammar of the expressions the parser accepts with its options and cell resolver
+    /// # Return
+    /// The grammar in EBNF, one rule per line
+    pub fn grammar(&self) -> String {
+        grammar::ebnf(&self.options, self.cell_resolver.is_some())
+    }
+
+    /// Issue a diagnostic message, if the sink wants its level
+    fn diagnostic(&self, level: Level, message: fmt::Arguments) {
+        if self.diagnostics.enabled(level) {
+            self.diagnostics.diagnostic(level, message);
+        }
+    }
+
+    /// Record an event, if events are being recorded
+    fn emit(&self, event: ParseEvent) {
+        if let Some(events) = self.events.borrow_mut().as_mut() {
+            events.push(event);
+        }
+    }
+
+    /// Expand the operand notations enabled by the options into plain decimal operands
+    /// # Arguments
+    ///  - expression: The part of the expression to parse, without the trailing input ignored
+    /// # Return
+    /// A `Result` having the `Expansion` of the expression if valid, `ParseError` otherwise
+    fn expand(&self, expression: String) -> Result<Expansion, ParseError> {
+        let radix = self.options.radix;
+        // Uppercase opcodes take precedence over the notations below, so they are lowered first
+        let expression = match self.options.case_insensitive_opcodes {
+            true => expression
+                .chars()
+                .map(|c| match c.to_ascii_lowercase() {
+                    code @ (OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD
+                    | OPCODE_LCM | OPCODE_OPEN | OPCODE_CLOSE) => code,
+                    _ => c,
+                })
+                .collect(),
+            false => expression,
+        };
+        let mut expanded = Expansion::new(expression.chars().count());
+        if !self.options.roman_numerals && self.cell_resolver.is_none() && radix == 10 {
+            expanded.push_verbatim(&expression, 0);
+            return Self::percentages(expanded);
+        }
+        // Opcodes are lowercase, so only uppercase letters can be digits above 9
+        let is_radix_digit = |c: &char| {
+            c.is_ascii_digit() || (c.is_ascii_uppercase() && c.is_digit(radix))
+        };
+        let mut data = expression.chars().enumerate().peekable();
+        while let Some((start, char)) = data.next() {
+            if radix != 10 && is_radix_digit(&char) {
+                let mut operand = char.to_string();
+                while let Some((_, digit)) = data.next_if(|(_, c)| is_radix_digit(c)) {
+                    operand.push(digit);
+                }
+                if operand.len() > 1 && operand.starts_with('0') {
+                    self.apply_leading_zero_policy(operand.clone())?;
+                }
+                let value = usize::from_str_radix(&operand, radix)
+                    .map_err(|err| ParseError::ParseDigitError {
+                        operand: operand.clone(),
+                        message: err.to_string(),
+                    })?;
+                diagnostic!(self, Trace, "{} = {}", operand, value);
+                expanded.push_str(&value.to_string(), start);
+                continue;
+            }
+            if !char.is_ascii_uppercase() {
+                expanded.push(char, start);
+                continue;
+            }
+            let mut letters = char.to_string();
+            while let Some((_, letter)) = data.next_if(|(_, c)| c.is_ascii_uppercase()) {
+                letters.push(letter);
+            }
+            // A word glued to decimal digits would silently merge into a different operand
+            if expanded.text.ends_with(|c: char| c.is_ascii_digit()) {
+                let found = char.to_string();
+                return Err(ParseError::MalformedExpression { position: start, found });
+            }
+            let row_start = start + letters.chars().count();
+            let mut row = String::new();
+            while let Some((_, digit)) = data.next_if(|(_, c)| c.is_ascii_digit()) {
+                row.push(digit);
+            }
+            let value = match &self.cell_resolver {
+                Some(resolver) if !row.is_empty() => {
+                    let reference = format!("{}{}", letters, row);
+                    row.parse::<usize>()
+                        .ok()
+                        .and_then(|row| resolver.resolve(&letters, row))
+                        .ok_or(ParseError::UnresolvedCell { reference })?
+                }
+                _ if !row.is_empty() => {
+                    let found = row[..1].to_string();
+                    return Err(ParseError::MalformedExpression { position: row_start, found });
+                }
+                _ if self.options.roman_numerals && letters.chars().all(roman::is_roman_symbol) => {
+                    roman::from_roman(&letters)
+                        .ok_or(ParseError::InvalidRomanNumeral { numeral: letters.clone() })?
+                }
+                Some(resolver) => match resolver.resolve_relative(&letters) {
+                    Some(value) => value,
+                    None => {
+                        expanded.push_verbatim(&letters, start);
+                        continue;
+                    }
+                },
+                _ => {
+                    expanded.push_verbatim(&letters, start);
+                    continue;
+                }
+            };
+            diagnostic!(self, Trace, "{} = {}", letters, value);
+            expanded.push_str(&value.to_string(), start);
+        }
+        Self::percentages(expanded)
+    }
+
+    /// Rewrite the percentages into plain operations on the result so far, which the operations
+    /// of a group are applied to from left to right:
+    ///  - `x a y%` and `x b y%` add or subtract `y` percent of `x`, as `x c e100ayf d100`
+    ///  - `x c y%` takes `y` percent of `x`, as `x c y d100`
+    ///  - `x d y%` divides `x` by `y` percent, as `x c100 d y`
+    ///  - A percentage first operand divides the result of its whole group by 100, so that
+    ///    `y% c x` takes `y` percent of `x`, as `y c x d100`
+    ///
+    /// Only the decimal operands may be percentages, since a group is already a result
+    fn percentages(expression: Expansion) -> Result<Expansion, ParseError> {
+        if !expression.text.contains(PERCENT) {
+            return Ok(expression);
+        }
+        let mut rewritten = Expansion::new(expression.end);
+        // Whether the first operand of each open group, the outermost first, is a percentage
+        let mut scaled = vec![false];
+        let mut data = expression.text.chars().zip(expression.sources.iter().copied()).peekable();
+        while let Some((char, source)) = data.next() {
+            match char {
+                OPCODE_OPEN => scaled.push(false),
+                // The closed group is popped either way, while a stray close parenthesis is left
+                // to the parse process to report
+                OPCODE_CLOSE if scaled.len() > 1 && scaled.pop() == Some(true) => {
+                    rewritten.push_str(&format!("{}100", OPCODE_DIV), source);
+                }
+                PERCENT => {
+                    let found = char.to_string();
+                    return Err(ParseError::MalformedExpression { position: source, found });
+                }
+                _ if char.is_ascii_digit() => {
+                    let mut operand = char.to_string();
+                    let mut sources = vec![source];
+                    while let Some((digit, source)) = data.next_if(|(c, _)| c.is_ascii_digit()) {
+                        operand.push(digit);
+                        sources.push(source);
+                    }
+                    let Some((_, percent)) = data.next_if(|(c, _)| *c == PERCENT) else {
+                        rewritten.text.push_str(&operand);
+                        rewritten.sources.extend(sources);
+                        continue;
+                    };
+                    // Digits would merge into the `100` of the rewritten percentage
+                    if let Some((next, position)) =
+                        data.next_if(|(c, _)| c.is_ascii_digit() || *c == PERCENT)
+                    {
+                        let found = next.to_string();
+                        return Err(ParseError::MalformedExpression { position, found });
+                    }
+                    // The operation code, if any, is rewritten along with the operand, which the
+                    // rewritten characters come from
+                    let code = rewritten.pop();
+                    let percentage = match code {
+                        None | Some((OPCODE_OPEN, _)) => {
+                            if let Some((code, source)) = code {
+                                rewritten.push(code, source);
+                            }
+                            *scaled.last_mut().unwrap() = true;
+                            operand
+                        }
+                        Some((code @ (OPCODE_ADD | OPCODE_SUB), _)) => format!(
+                            "{}{}100{}{}{}{}100",
+                            OPCODE_MUL, OPCODE_OPEN, code, operand, OPCODE_CLOSE, OPCODE_DIV
+                        ),
+                        Some((OPCODE_MUL, _)) => {
+                            format!("{}{}{}100", OPCODE_MUL, operand, OPCODE_DIV)
+                        }
+                        Some((OPCODE_DIV, _)) => {
+                            format!("{}100{}{}", OPCODE_MUL, OPCODE_DIV, operand)
+                        }
+                        Some(_) => {
+                            let found = PERCENT.to_string();
+                            let position = percent;
+                            return Err(ParseError::MalformedExpression { position, found });
+                        }
+                    };
+                    rewritten.push_str(&percentage, sources[0]);
+                    continue;
+                }
+                _ => {}
+            }
+            rewritten.push(char, source);
+        }
+        if scaled == [true] {
+            rewritten.push_str(&format!("{}100", OPCODE_DIV), rewritten.end);
+        }
+        Ok(rewritten)
+    }
+
+    /// Apply an operation to a second operand, as signed integers if the options emulate them,
+    /// wrapping around if it overflows and the options ask to
+    fn apply(&self, operation: Operation, second_operand: usize) -> Result<usize, ParseError> {
+        let rounding = self.options.rounding;
+        match self.options.signed_width {
+            Some(width) => operation.signed_apply(second_operand, width, rounding),
+            None => operation.apply_result_rounded(second_operand, rounding),
+        }
+        .or_else(|err| self.wrap_around(operation, second_operand, err))
+    }
+
+    /// Recover from the failure of an operation by wrapping around, if it overflowed and the
+    /// options ask to
+    fn wrap_around(
+        &self,
+        operation: Operation,
+        second_operand: usize,
+        err: OperationError,
+    ) -> Result<usize, ParseError> {
+        let rounding = self.options.rounding;
+        let wrapped = match (&err, self.options.signed_width) {
+            (OperationError::OverflowError, Some(width)) if self.options.wrapping => {
+                operation.signed_wrapping_apply(second_operand, width, rounding)
+            }
+            (OperationError::OverflowError, None) if self.options.wrapping => {
+                operation.wrapping_apply(second_operand)
+            }
+            _ => None,
+        };
+        let wrapped = wrapped.ok_or(ParseError::InvalidOperation { source: err })?;
+        match self.options.signed_width {
+            Some(width) => diagnostic!(self, Warn,
+                "{} {} {} overflows, wrapping around to {}",
+                bits::signed(operation.first_operand(), width),
+                operation.symbol(),
+                bits::signed(second_operand, width),
+                bits::signed(wrapped, width)
+            ),
+            None => diagnostic!(self, Warn,
+                "{} {} overflows, wrapping around to {}",
+                operation,
+                second_operand,
+                wrapped
+            ),
+        }
+        Ok(wrapped)
+    }
+
+    /// Check the expression against the limits set by the options in a single pass, so that
+    /// pathological inputs are rejected before any operand is read or any group is entered
+    fn check_limits(&self, expression: &str) -> Result<(), ParseError> {
+        let options = &self.options;
+        if options.max_digits.is_none() && options.max_depth.is_none() && options.max_length.is_none() {
+            return Ok(());
+        }
+        let max_digits = options.max_digits.unwrap_or(usize::MAX);
+        let max_depth = options.max_depth.unwrap_or(usize::MAX);
+        let max_length = options.max_length.unwrap_or(usize::MAX);
+        // Characters are at most as many as bytes, so short expressions need no counting
+        if expression.len() > max_length && expression.chars().nth(max_length).is_some() {
+            return Err(ParseError::ExpressionTooLong { max_length });
+        }
+        let radix = options.radix;
+        let mut start = 0;
+        let mut digits = 0;
+        let mut depth = 0usize;
+        for (position, char) in expression.chars().enumerate() {
+            if char.is_ascii_digit() || (char.is_ascii_uppercase() && char.is_digit(radix)) {
+                if digits == 0 {
+                    start = position;
+                }
+                digits += 1;
+                if digits > max_digits {
+                    return Err(ParseError::OperandTooLong { position: start, max_digits });
+                }
+                continue;
+            }
+            digits = 0;
+            match char {
+                OPCODE_OPEN => {
+                    depth += 1;
+                    if depth > max_depth {
+                        return Err(ParseError::NestingTooDeep { position, max_depth });
+                    }
+                }
+                OPCODE_CLOSE => depth = depth.saturating_sub(1),
+                _ => {}
             }
         }
+        Ok(())
+    }
+
+    /// Check the expanded expression against the operations the options allow. Every operation
+    /// code left once the operands and the percentages are expanded applies one operation, so that
+    /// the expressions over the limit are rejected before any operation is applied
+    fn check_operations(&self, expression: &str) -> Result<(), ParseError> {
+        let Some(max_operations) = self.options.max_operations else {
+            return Ok(());
+        };
+        let mut operations = expression.chars().enumerate().filter(|(_, char)| {
+            matches!(
+                *char,
+                OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD | OPCODE_LCM
+            )
+        });
+        match operations.nth(max_operations) {
+            Some((position, _)) => Err(ParseError::TooManyOperations { position, max_operations }),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that the operands of the expanded expression fit the signed integers emulated, if the
+    /// options ask for them. Operands are written without sign, so they range from 0 to the
+    /// largest integer (e.g. `127` in 8 bits), while negative values come from the operations
+    fn check_signed_operands(&self, expression: &str) -> Result<(), ParseError> {
+        let Some(width) = self.options.signed_width else {
+            return Ok(());
+        };
+        let max = (1usize << (width - 1)) - 1;
+        let mut operands = expression.split(|c: char| !c.is_ascii_digit());
+        // The operands too large for `usize` are left to the parse process to report
+        match operands.find(|operand| operand.parse::<usize>().is_ok_and(|value| value > max)) {
+            Some(operand) => Err(ParseError::ParseDigitError {
+                operand: operand.to_string(),
+                message: "number too large to fit in target type".to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
 
+    /// Report as notices the parentheses which change nothing, if the options ask for it: those
+    /// around a single operand which is not a percentage, and those around another group
+    fn check_parentheses(&self, expression: &str) {
+        if !self.options.redundant_parentheses || !self.diagnostics.enabled(Level::Warn) {
+            return;
+        }
+        let insensitive = self.options.case_insensitive_opcodes;
+        let chars: Vec<char> = expression.chars()
+            .map(|c| match c.to_ascii_lowercase() {
+                code @ (OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD
+                | OPCODE_LCM | OPCODE_OPEN | OPCODE_CLOSE) if insensitive => code,
+                _ => c,
+            })
+            .collect();
+        let mut opened = Vec::new();
+        // The span of the group closed last, which a group closing right after it encloses
+        let mut closed = None;
+        for (position, char) in chars.iter().enumerate() {
+            match *char {
+                OPCODE_OPEN => opened.push(position),
+                OPCODE_CLOSE => {
+                    let Some(start) = opened.pop() else {
+                        return;
+                    };
+                    let inner = &chars[start + 1..position];
+                    let single = !inner.is_empty() && inner.iter().all(|c| !matches!(*c,
+                        OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD
+                        | OPCODE_LCM | OPCODE_OPEN | OPCODE_CLOSE | PERCENT));
+                    let nested = closed == Some((start + 1, position - 1));
+                    if single || nested {
+                        self.diagnostics.notice(&Notice::RedundantParentheses(start..position + 1));
+                    }
+                    closed = Some((start, position));
+                }
+                _ => {}
+            }
+        }
     }
 
     /// Internal, recursive parse function
     fn parse_internal(
         &self,
-        data: &mut Peekable<Chars>,
+        data: &mut Cursor,
         mut result: Option<usize>,
+        depth: usize,
     ) -> Result<usize, ParseError> {
-        trace!("parse_internal() recursion");
+        diagnostic!(self, Trace, "parse_internal() recursion");
+        if depth > MAX_RECURSION {
+            return Err(ParseError::DepthLimitExceeded {
+                position: data.position - 1,
+                max_depth: MAX_RECURSION,
+            });
+        }
 
         let mut state = ParserState::FirstOperand;
         let mut operation: Option<Operation> = None;
         let mut acc = String::new();
         while let Some(char) = data.next() {
+            let position = data.position - 1;
+            if self.cancellation.as_ref().is_some_and(|token| token.load(Ordering::Relaxed)) {
+                return Err(ParseError::Cancelled { position });
+            }
+            self.emit(ParseEvent::Consumed(position, char));
             let is_digit = char.is_ascii_digit();
-            let new_state = self.compute_state(state, char.to_owned(), &mut acc)?;
+            let new_state = self.compute_state(state, char.to_owned(), position, &mut acc)?;
             if state != new_state {
-                trace!("{:?} -> {:?}", state, new_state);
+                diagnostic!(self, Trace, "{:?} -> {:?}", state, new_state);
+                self.emit(ParseEvent::StateChanged(state, new_state));
                 state = new_state;
             }
+            let operand_complete = !data.peek().is_some_and(char::is_ascii_digit);
 
             match char {
                 char if state == ParserState::FirstOperand && is_digit.to_owned() => {
-                    acc.push_str(&char.to_string());
-                    trace!("a = {:?}", acc);
-                    result = Some(acc.parse::<usize>().map_err(|err| {
-                        ParseError::ParseDigitError(acc.clone(), err.to_string())
-                    })?);
+                    self.check_leading_zero(&acc, char, data)?;
+                    acc.push(char);
+                    diagnostic!(self, Trace, "a = {:?}", acc);
+                    let operand = acc.parse::<usize>().map_err(|err| {
+                        let (operand, message) = (acc.clone(), err.to_string());
+                        ParseError::ParseDigitError { operand, message }
+                    })?;
+                    if operand_complete {
+                        self.emit(ParseEvent::OperandParsed(position + 1 - acc.len(), operand));
+                    }
+                    result = Some(operand);
                 }
                 char if state == ParserState::SecondOperand && is_digit.to_owned() => {
-                    acc.push_str(&char.to_string());
-                    trace!("b = {:?}", acc);
-                    result = Some(
-                        operation
-                            .ok_or(IllegalState(
-                                "No operation when evaluating SecondOperand".to_string(),
-                            ))?
-                            .apply(acc.to_string())
-                            .map_err(ParseError::InvalidOperation)?,
-                    );
+                    self.check_leading_zero(&acc, char, data)?;
+                    acc.push(char);
+                    diagnostic!(self, Trace, "b = {:?}", acc);
+                    let current = operation.ok_or(IllegalState {
+                        reason: "No operation when evaluating SecondOperand".to_string(),
+                    })?;
+                    // Only the complete operand is applied, so that a part of it neither fails nor
+                    // wraps around (e.g. `1 - 2` in `1b23`, or `5 / 0` in `5d05`)
+                    if !operand_complete {
+                        continue;
+                    }
+                    diagnostic!(self, Trace, "{:?} {}", current, acc);
+                    let operand = acc.parse::<usize>().map_err(|err| {
+                        let (operand, message) = (acc.clone(), err.to_string());
+                        let source = OperationError::InvalidSecondOperand { operand, message };
+                        ParseError::InvalidOperation { source }
+                    })?;
+                    let res = self.apply(current, operand)?;
+                    diagnostic!(self, Trace, "{} {} = {}", current, acc, res);
+                    self.emit(ParseEvent::OperandParsed(position + 1 - acc.len(), operand));
+                    self.emit(ParseEvent::OperationApplied(current, operand, res));
+                    result = Some(res);
                 }
-                code @ (OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV)
+                code @ (OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD | OPCODE_LCM)
                     if state == ParserState::Operation =>
                 {
                     operation = if acc.is_empty() {
-                        let first_operand = result.ok_or(ParseError::IllegalState(
-                            "No previous result and accumulator empty instantiating operation"
+                        let first_operand = result.ok_or(ParseError::IllegalState {
+                            reason: "No previous result and accumulator empty instantiating operation"
                                 .to_string(),
-                        ))?;
+                        })?;
                         Some(
                             Operation::from_result(code, first_operand)
-                                .map_err(ParseError::InvalidOperation)?,
+                                .map_err(|source| ParseError::InvalidOperation { source })?,
                         )
                     } else {
-                        Some(
-                            Operation::from(code, acc.to_string())
-                                .map_err(ParseError::InvalidOperation)?,
-                        )
+                        let operation = Operation::from(code, acc.to_string())
+                            .map_err(|source| ParseError::InvalidOperation { source })?;
+                        diagnostic!(self, Trace, "parsed={}", operation.first_operand());
+                        Some(operation)
                     };
-                    trace!("op = {:?}", operation);
+                    diagnostic!(self, Trace, "op = {:?}", operation);
                     acc.clear();
                 }
                 OPCODE_OPEN => {
-                    trace!(
+                    diagnostic!(self, Trace, 
                         "Open Parenthesis: state = {:?}, operation = {:?}",
                         state,
                         operation
                     );
+                    self.emit(ParseEvent::GroupOpened(depth + 1));
                     let res = match operation {
-                        None => self.parse_internal(data, result),
-                        Some(operation) => operation
-                            .apply_result(self.parse_internal(data, result)?)
-                            .map_err(ParseError::InvalidOperation),
+                        None => self.parse_internal(data, result, depth + 1),
+                        Some(operation) => {
+                            let group = self.parse_internal(data, result, depth + 1)?;
+                            diagnostic!(self, Trace, "{:?} {}", operation, group);
+                            let res = self.apply(operation, group);
+                            diagnostic!(self, Trace, "{} {} = {:?}", operation, group, res);
+                            if let Ok(res) = res {
+                                self.emit(ParseEvent::OperationApplied(operation, group, res));
+                            }
+                            res
+                        }
                     };
                     match data.peek().cloned() {
-                        Some(OPCODE_ADD) | Some(OPCODE_SUB) | Some(OPCODE_MUL) | Some(OPCODE_DIV) => {
+                        Some(
+                            OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD
+                            | OPCODE_LCM,
+                        ) => {
                             result = res.ok();
                             state = ParserState::FirstOperand;
                         },
@@ -159,40 +1244,77 @@ impl Parser {
                     }
                 }
                 OPCODE_CLOSE if state == ParserState::CloseParenthesis => {
-                    trace!(
+                    diagnostic!(self, Trace, 
                         "Close Parenthesis, operation={:?}, result = {:?}",
                         operation,
                         result,
                     );
-                    return result.ok_or(IllegalState(
-                        "Result not available when closing parenthesis".to_string(),
-                    ));
+                    let result = result.ok_or(IllegalState {
+                        reason: "Result not available when closing parenthesis".to_string(),
+                    })?;
+                    self.emit(ParseEvent::GroupClosed(depth, result));
+                    return Ok(result);
                 }
                 symbol => {
-                    return Err(ParseError::UnexpectedSymbol(
-                        symbol.to_string(),
+                    return Err(ParseError::UnexpectedSymbol {
+                        position,
+                        found: symbol.to_string(),
                         state,
                         operation,
-                    ))
+                    })
                 }
             }
         }
 
-        debug!("result = {:?}", &result);
+        diagnostic!(self, Debug, "result = {:?}", &result);
         result.ok_or(EmptyExpression)
     }
 
-    /// Compute the new state of the parser
-    fn compute_state(
+    /// Apply the `LeadingZeroPolicy` when a digit follows a lone zero in the accumulator
+    fn check_leading_zero(
         &self,
-        state: ParserState,
+        acc: &str,
         char: char,
-        acc: &mut String,
-    ) -> Result<ParserState, ParseError> {
-        let is_digit = char.is_ascii_digit();
+        data: &Cursor,
+    ) -> Result<(), ParseError> {
+        if acc != "0" {
+            return Ok(());
+        }
+        let operand = format!(
+            "{}{}{}",
+            acc,
+            char,
+            data.clone()
+                .take_while(|c| c.is_ascii_digit())
+                .collect::<String>()
+        );
+        self.apply_leading_zero_policy(operand)
+    }
+
+    /// Apply the `LeadingZeroPolicy` to an operand having leading zeros
+    fn apply_leading_zero_policy(&self, operand: String) -> Result<(), ParseError> {
+        match self.options.leading_zeros {
+            LeadingZeroPolicy::Accept => Ok(()),
+            LeadingZeroPolicy::Warn => {
+                diagnostic!(self, Warn, "operand {} has leading zeros", operand);
+                Ok(())
+            }
+            LeadingZeroPolicy::Reject => Err(ParseError::LeadingZero { operand }),
+        }
+    }
+
+    /// Compute the new state of the parser
+    fn compute_state(
+        &self,
+        state: ParserState,
+        char: char,
+        position: usize,
+        acc: &mut String,
+    ) -> Result<ParserState, ParseError> {
+        let is_digit = char.is_ascii_digit();
         match state {
             ParserState::FirstOperand if !is_digit.to_owned() => match char {
-                OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV => {
+                OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD | OPCODE_LCM => {
                     acc.clear();
                     Ok(ParserState::Operation)
                 }
@@ -201,11 +1323,13 @@ impl Parser {
                     acc.clear();
                     Ok(ParserState::CloseParenthesis)
                 }
-                _ => Err(ParseError::MalformedExpression(char.to_string())),
+                _ => Err(ParseError::MalformedExpression { position, found: char.to_string() }),
             },
             ParserState::Operation if is_digit.to_owned() => Ok(ParserState::SecondOperand),
             ParserState::Operation if !is_digit.to_owned() => match char {
-                OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV if !acc.is_empty() => {
+                OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD | OPCODE_LCM
+                    if !acc.is_empty() =>
+                {
                     acc.clear();
                     Ok(state)
                 }
@@ -213,10 +1337,10 @@ impl Parser {
                     acc.clear();
                     Ok(state)
                 }
-                _ => Err(ParseError::MalformedExpression(char.to_string())),
+                _ => Err(ParseError::MalformedExpression { position, found: char.to_string() }),
             },
             ParserState::SecondOperand if !is_digit.to_owned() => match char {
-                OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV => {
+                OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD | OPCODE_LCM => {
                     acc.clear();
                     Ok(ParserState::Operation)
                 }
@@ -225,32 +1349,54 @@ impl Parser {
                     acc.clear();
                     Ok(ParserState::CloseParenthesis)
                 }
-                _ => Err(ParseError::MalformedExpression(char.to_string())),
+                _ => Err(ParseError::MalformedExpression { position, found: char.to_string() }),
             },
             ParserState::CloseParenthesis if !is_digit.to_owned() => match char {
-                OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV => {
+                OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD | OPCODE_LCM => {
                     acc.clear();
                     Ok(ParserState::Operation)
                 }
                 OPCODE_CLOSE => Ok(ParserState::CloseParenthesis),
-                _ => Err(ParseError::UnbalancedParenthesis(char.to_string())),
+                _ => Err(UnbalancedParenthesis { position, parenthesis: char.to_string() }),
             },
             ParserState::FirstOperand | ParserState::SecondOperand if is_digit.to_owned() => {
                 Ok(state)
             }
-            _ => Err(ParseError::MalformedExpression(char.to_string())),
+            _ => Err(ParseError::MalformedExpression { position, found: char.to_string() }),
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::operation::OperationError::OverflowError;
+    use crate::bits;
+    use crate::check::Warnings;
+    use crate::definitions::{DefinitionError, Definitions};
+    use crate::diagnostics::{DiagnosticsSink, Level, Notice};
+    use crate::events::ParseEvent::{
+        Consumed, GroupClosed, GroupOpened, OperandParsed, OperationApplied, StateChanged,
+    };
+    use crate::operation::Operation;
+    use crate::operation::OperationError::{InvalidSecondOperand, OverflowError};
+    use crate::options::{
+        LeadingZeroPolicy, Limits, ParserOptions, RoundingMode, TrailingInputPolicy,
+    };
     use crate::parser::ParseError::{
-        EmptyExpression, InvalidOperation, MalformedExpression, ParseDigitError,
-        UnbalancedParenthesis,
+        EmptyExpression, InvalidOperation, InvalidRomanNumeral, LeadingZero, MalformedExpression,
+        ExpressionTooLong, InvalidRadix, NestingTooDeep, OperandTooLong, ParseDigitError,
+        TooManyOperations, TrailingInput, UnbalancedParenthesis, UnresolvedCell,
     };
-    use crate::parser::Parser;
+    use crate::parser::{Parser, ParserState, MAX_RECURSION};
+    use crate::parser::ParseError;
+    use crate::stats::Stats;
+    use crate::telemetry::{Phase, Telemetry};
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::time::Duration;
 
     #[test]
     fn test_examples() {
@@ -298,7 +1444,7 @@ mod test {
         let expression = "3aa2c4".to_string();
         let parser = Parser::new(expression);
         let result = parser.parse();
-        assert_eq!(Err(MalformedExpression("a".to_string())), result);
+        assert_eq!(Err(MalformedExpression { position: 2, found: "a".to_string() }), result);
     }
 
     #[test]
@@ -306,12 +1452,18 @@ mod test {
         let expression = "3aee2fc4".to_string();
         let parser = Parser::new(expression);
         let result = parser.parse();
-        assert_eq!(Err(UnbalancedParenthesis("e".to_string())), result);
+        assert_eq!(
+            Err(UnbalancedParenthesis { position: 2, parenthesis: "e".to_string() }),
+            result
+        );
 
         let expression = "3aee2fffc4".to_string();
         let parser = Parser::new(expression);
         let result = parser.parse();
-        assert_eq!(Err(UnbalancedParenthesis("f".to_string())), result);
+        assert_eq!(
+            Err(UnbalancedParenthesis { position: 7, parenthesis: "f".to_string() }),
+            result
+        );
     }
 
     #[test]
@@ -333,17 +1485,32 @@ mod test {
         let parser = Parser::new(expression);
         let result = parser.parse();
         assert_eq!(
-            Err(ParseDigitError(
-                "99999999999999999999".to_string(),
-                "number too large to fit in target type".to_string()
-            )),
+            Err(ParseDigitError {
+                operand: "99999999999999999999".to_string(),
+                message: "number too large to fit in target type".to_string()
+            }),
             result
         );
 
         let expression = "9c99999999999999999999999999".to_string();
         let parser = Parser::new(expression);
         let result = parser.parse();
-        assert_eq!(Err(InvalidOperation(OverflowError)), result);
+        assert_eq!(
+            Err(InvalidOperation {
+                source: InvalidSecondOperand {
+                    operand: "99999999999999999999999999".to_string(),
+                    message: "number too large to fit in target type".to_string()
+                }
+            }),
+            result
+        );
+
+        let expression = "9c9999999999999999999".to_string();
+        let parser = Parser::new(expression);
+        let result = parser.parse();
+        assert_eq!(Err(InvalidOperation { source: OverflowError }), result);
+        // Only complete operands are applied, so a part of one never fails
+        assert_eq!(Ok(1), Parser::new("5d05".to_string()).parse());
     }
 
     #[test]
@@ -353,4 +1520,943 @@ mod test {
         let result = parser.parse();
         assert_eq!(Err(EmptyExpression), result);
     }
+
+    #[test]
+    fn test_leading_zeros() {
+        let expression = "007a3".to_string();
+        let parser = Parser::new(expression);
+        let result = parser.parse().unwrap();
+        assert_eq!(10, result);
+
+        let options = ParserOptions {
+            leading_zeros: LeadingZeroPolicy::Warn,
+            ..Default::default()
+        };
+        let expression = "3a007".to_string();
+        let parser = Parser::new(expression).with_options(options);
+        let result = parser.parse().unwrap();
+        assert_eq!(10, result);
+
+        let options = ParserOptions {
+            leading_zeros: LeadingZeroPolicy::Reject,
+            ..Default::default()
+        };
+        let expression = "3a007".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse();
+        assert_eq!(Err(LeadingZero { operand: "007".to_string() }), result);
+
+        let expression = "0a10c0".to_string();
+        let parser = Parser::new(expression).with_options(options);
+        let result = parser.parse().unwrap();
+        assert_eq!(0, result);
+    }
+
+    #[test]
+    fn test_roman_numerals() {
+        let options = ParserOptions {
+            roman_numerals: true,
+            ..Default::default()
+        };
+        let expression = "XIVaII".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse().unwrap();
+        assert_eq!(16, result);
+
+        let expression = "MCMXCIVbeXcXf".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse().unwrap();
+        assert_eq!(1894, result);
+
+        let expression = "IIIIaI".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse();
+        assert_eq!(Err(InvalidRomanNumeral { numeral: "IIII".to_string() }), result);
+
+        let expression = "X5aI".to_string();
+        let parser = Parser::new(expression).with_options(options);
+        let result = parser.parse();
+        assert_eq!(Err(MalformedExpression { position: 1, found: "5".to_string() }), result);
+
+        let expression = "XaI".to_string();
+        let parser = Parser::new(expression);
+        let result = parser.parse();
+        assert_eq!(Err(MalformedExpression { position: 0, found: "X".to_string() }), result);
+    }
+
+    #[test]
+    fn test_cell_references() {
+        let cells = HashMap::from([("A1".to_string(), 3), ("B2".to_string(), 4)]);
+        let expression = "A1aB2c2".to_string();
+        let parser = Parser::new(expression).with_cell_resolver(cells.clone());
+        let result = parser.parse().unwrap();
+        assert_eq!(14, result);
+
+        let expression = "eA1aB2fcC3".to_string();
+        let parser = Parser::new(expression).with_cell_resolver(cells);
+        let result = parser.parse();
+        assert_eq!(Err(UnresolvedCell { reference: "C3".to_string() }), result);
+
+        let expression = "AA10aA1".to_string();
+        let parser =
+            Parser::new(expression).with_cell_resolver(|column: &str, row| Some(column.len() * row));
+        let result = parser.parse().unwrap();
+        assert_eq!(21, result);
+
+        let options = ParserOptions {
+            roman_numerals: true,
+            ..Default::default()
+        };
+        let expression = "C1aX".to_string();
+        let parser = Parser::new(expression)
+            .with_options(options)
+            .with_cell_resolver(|_: &str, _| Some(5));
+        let result = parser.parse().unwrap();
+        assert_eq!(15, result);
+    }
+
+    #[test]
+    fn test_events() {
+        let expression = "3ae12f".to_string();
+        let parser = Parser::new(expression);
+        let (result, events) = parser.parse_with_events();
+        assert_eq!(Ok(15), result);
+        assert_eq!(
+            vec![
+                Consumed(0, '3'),
+                OperandParsed(0, 3),
+                Consumed(1, 'a'),
+                StateChanged(ParserState::FirstOperand, ParserState::Operation),
+                Consumed(2, 'e'),
+                GroupOpened(1),
+                Consumed(3, '1'),
+                Consumed(4, '2'),
+                OperandParsed(3, 12),
+                Consumed(5, 'f'),
+                StateChanged(ParserState::FirstOperand, ParserState::CloseParenthesis),
+                GroupClosed(1, 12),
+                OperationApplied(Operation::Add(3), 12, 15),
+            ],
+            events
+        );
+
+        let expression = "2c10".to_string();
+        let parser = Parser::new(expression);
+        let (result, events) = parser.parse_with_events();
+        assert_eq!(Ok(20), result);
+        assert_eq!(Some(&OperationApplied(Operation::Mul(2), 10, 20)), events.last());
+        assert_eq!(Ok(20), parser.parse());
+    }
+
+    #[test]
+    fn test_stats() {
+        let expression = "3c4d2aee2a4c41fc4f".to_string();
+        let parser = Parser::new(expression);
+        let (result, stats) = parser.parse_with_stats();
+        assert_eq!(Ok(990), result);
+        assert_eq!(
+            Stats {
+                additions: 2,
+                subtractions: 0,
+                multiplications: 3,
+                divisions: 1,
+                divisibility: 0,
+                max_depth: 2,
+                operands: 7,
+            },
+            stats
+        );
+        assert_eq!(6, stats.operations());
+    }
+
+    #[test]
+    fn test_telemetry() {
+        struct Recorder(Rc<RefCell<Vec<String>>>);
+        impl Telemetry for Recorder {
+            fn parse_started(&self, expression: &str) {
+                self.0.borrow_mut().push(format!("started {}", expression));
+            }
+            fn parse_finished(&self, result: usize, _duration: Duration) {
+                self.0.borrow_mut().push(format!("finished {}", result));
+            }
+            fn error_raised(&self, error: &ParseError, _duration: Duration) {
+                self.0.borrow_mut().push(format!("error {:?}", error));
+            }
+            fn phase_finished(&self, phase: Phase, _duration: Duration) {
+                self.0.borrow_mut().push(format!("phase {:?}", phase));
+            }
+        }
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let parser = Parser::new("3a2".to_string()).with_telemetry(Recorder(Rc::clone(&log)));
+        assert_eq!(Ok(5), parser.parse());
+        let parser = Parser::new("3aa2".to_string()).with_telemetry(Recorder(Rc::clone(&log)));
+        assert!(parser.parse().is_err());
+        assert_eq!(
+            vec![
+                "started 3a2",
+                "phase Lex",
+                "phase Parse",
+                "phase Eval",
+                "finished 5",
+                "started 3aa2",
+                "phase Lex",
+                "phase Parse",
+                "error MalformedExpression { position: 2, found: \"a\" }"
+            ],
+            *log.borrow()
+        );
+
+        struct Silent;
+        impl Telemetry for Silent {}
+        let parser = Parser::new("3a2".to_string()).with_telemetry(Silent);
+        assert_eq!(Ok(5), parser.parse());
+    }
+
+    #[test]
+    fn test_radix() {
+        let options = ParserOptions {
+            radix: 16,
+            ..Default::default()
+        };
+        let expression = "FFa1c10".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse().unwrap();
+        assert_eq!(4096, result);
+
+        let expression = "FGa1".to_string();
+        let parser = Parser::new(expression).with_options(options);
+        let result = parser.parse();
+        assert_eq!(Err(MalformedExpression { position: 1, found: "G".to_string() }), result);
+
+        let options = ParserOptions {
+            radix: 2,
+            leading_zeros: LeadingZeroPolicy::Reject,
+            ..Default::default()
+        };
+        let expression = "101a11".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse().unwrap();
+        assert_eq!(8, result);
+
+        let expression = "102".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse();
+        assert_eq!(
+            Err(ParseDigitError {
+                operand: "102".to_string(),
+                message: "invalid digit found in string".to_string()
+            }),
+            result
+        );
+
+        let expression = "011".to_string();
+        let parser = Parser::new(expression).with_options(options);
+        let result = parser.parse();
+        assert_eq!(Err(LeadingZero { operand: "011".to_string() }), result);
+
+        let options = ParserOptions {
+            radix: 37,
+            ..Default::default()
+        };
+        let parser = Parser::new("1".to_string()).with_options(options);
+        assert_eq!(Err(InvalidRadix { radix: 37 }), parser.parse());
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let expressions = [
+            "3a2c4",
+            "32a2d2",
+            "500a10b66c32",
+            "3ae4c66fb32",
+            "3c4d2aee2a4c41fc4f",
+            "233b3ae4c66fb99ae33ce3a5ff",
+            "eeee5fae3fffcee2fff",
+            "3aa2c4",
+            "3aee2fc4",
+            "3x",
+            "3x2",
+            "",
+        ];
+        for expression in expressions {
+            let parser = Parser::new(expression.to_string());
+            assert_eq!(
+                parser.parse(),
+                Parser::parse_bytes(expression.as_bytes()),
+                "{}",
+                expression
+            );
+        }
+        assert_eq!(
+            Err(UnbalancedParenthesis { position: 3, parenthesis: "f".to_string() }),
+            Parser::parse_bytes(b"3a2fc4")
+        );
+        assert_eq!(
+            Err(MalformedExpression { position: 1, found: "x".to_string() }),
+            Parser::parse_bytes(b"3x")
+        );
+        assert_eq!(
+            Err(ParseDigitError {
+                operand: "99999999999999999999999999".to_string(),
+                message: "number too large to fit in target type".to_string()
+            }),
+            Parser::parse_bytes(b"99999999999999999999999999c9")
+        );
+    }
+
+    #[test]
+    fn test_trailing_input() {
+        let expression = "3a2fc4".to_string();
+        let parser = Parser::new(expression);
+        let result = parser.parse();
+        assert_eq!(
+            Err(UnbalancedParenthesis { position: 3, parenthesis: "f".to_string() }),
+            result
+        );
+
+        let options = ParserOptions {
+            trailing_input: TrailingInputPolicy::Strict,
+            ..Default::default()
+        };
+        let expression = "3a2fc4".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse();
+        assert_eq!(Err(TrailingInput { position: 3, found: "fc4".to_string() }), result);
+
+        let expression = "e3a2f)".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse();
+        assert_eq!(Err(TrailingInput { position: 5, found: ")".to_string() }), result);
+
+        let expression = "3a)".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse();
+        assert_eq!(Err(MalformedExpression { position: 2, found: ")".to_string() }), result);
+
+        let expression = "3ae2c2f".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse_prefix();
+        assert_eq!(Ok((7, 7)), result);
+
+        // Positions are in the expression as written, before the notations are expanded
+        let expression = "200a10%fc4".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse();
+        assert_eq!(Err(TrailingInput { position: 7, found: "fc4".to_string() }), result);
+        assert_eq!(Some(7), result.unwrap_err().position());
+
+        let roman = ParserOptions {
+            roman_numerals: true,
+            ..options.clone()
+        };
+        let expression = "MCMXCIVa1fc4".to_string();
+        let parser = Parser::new(expression).with_options(roman.clone());
+        let result = parser.parse();
+        assert_eq!(Err(TrailingInput { position: 9, found: "fc4".to_string() }), result);
+
+        let expression = "Xa50%fc4".to_string();
+        let parser = Parser::new(expression).with_options(roman);
+        let result = parser.parse();
+        assert_eq!(Err(TrailingInput { position: 5, found: "fc4".to_string() }), result);
+
+        let options = ParserOptions {
+            trailing_input: TrailingInputPolicy::Lenient,
+            ..Default::default()
+        };
+        let expression = "3a2fc4".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse_prefix();
+        assert_eq!(Ok((5, 3)), result);
+
+        let expression = "e3a2f) and more".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse_prefix();
+        assert_eq!(Ok((5, 5)), result);
+
+        let expression = "200a10%fc4".to_string();
+        let parser = Parser::new(expression).with_options(options.clone());
+        let result = parser.parse_prefix();
+        assert_eq!(Ok((220, 7)), result);
+
+        let options = ParserOptions {
+            roman_numerals: true,
+            ..options
+        };
+        let expression = "MCMXCIVa1fc4".to_string();
+        let parser = Parser::new(expression).with_options(options);
+        let result = parser.parse_prefix();
+        assert_eq!(Ok((1995, 9)), result);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        /// Collects the messages up to a level
+        struct Collector(Level, Rc<RefCell<Vec<(Level, String)>>>);
+
+        impl DiagnosticsSink for Collector {
+            fn enabled(&self, level: Level) -> bool {
+                level <= self.0
+            }
+
+            fn diagnostic(&self, level: Level, message: fmt::Arguments) {
+                self.1.borrow_mut().push((level, message.to_string()));
+            }
+        }
+
+        let options = ParserOptions {
+            leading_zeros: LeadingZeroPolicy::Warn,
+            ..Default::default()
+        };
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let parser = Parser::new("3a07".to_string())
+            .with_options(options.clone())
+            .with_diagnostics(Collector(Level::Warn, Rc::clone(&messages)));
+        assert_eq!(Ok(10), parser.parse());
+        assert_eq!(
+            vec![(Level::Warn, "operand 07 has leading zeros".to_string())],
+            *messages.borrow()
+        );
+
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let parser = Parser::new("3a2".to_string())
+            .with_diagnostics(Collector(Level::Trace, Rc::clone(&messages)));
+        assert_eq!(Ok(5), parser.parse());
+        let messages = messages.borrow();
+        assert!(messages.contains(&(Level::Trace, "Add(3) 2".to_string())));
+        assert!(messages.contains(&(Level::Trace, "3 + 2 = 5".to_string())));
+        assert_eq!(Some(&(Level::Debug, "result = Some(5)".to_string())), messages.last());
+
+        let options = ParserOptions {
+            redundant_parentheses: true,
+            ..Default::default()
+        };
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let parser = Parser::new("3ae2f".to_string())
+            .with_options(options)
+            .with_diagnostics(Collector(Level::Warn, Rc::clone(&messages)));
+        assert_eq!(Ok(5), parser.parse());
+        assert_eq!(
+            vec![(Level::Warn, "redundant parentheses at 2..5 change nothing".to_string())],
+            *messages.borrow()
+        );
+    }
+
+    #[test]
+    fn test_redundant_parentheses() {
+        /// Collects the spans of the notices
+        struct Spans(Rc<RefCell<Vec<(usize, usize)>>>);
+
+        impl DiagnosticsSink for Spans {
+            fn diagnostic(&self, _level: Level, _message: fmt::Arguments) {}
+
+            fn notice(&self, notice: &Notice) {
+                let span = notice.span();
+                self.0.borrow_mut().push((span.start, span.end));
+            }
+        }
+
+        let spans = |expression: &str, options: &ParserOptions| {
+            let spans = Rc::new(RefCell::new(Vec::new()));
+            let result = Parser::new(expression.to_string())
+                .with_options(options.clone())
+                .with_diagnostics(Spans(Rc::clone(&spans)))
+                .parse();
+            assert!(result.is_ok());
+            spans.take()
+        };
+        let options = ParserOptions {
+            redundant_parentheses: true,
+            ..Default::default()
+        };
+        assert_eq!(vec![(2, 5)], spans("3ae2fc4", &options));
+        assert_eq!(vec![(0, 7)], spans("ee3a2ffc4", &options));
+        assert_eq!(vec![(2, 5), (1, 6), (0, 7)], spans("eee2fffa1", &options));
+        assert!(spans("3ae5%f", &options).is_empty());
+        assert!(spans("e3a2fc4", &options).is_empty());
+        assert!(spans("ee3a2fc4f", &options).is_empty());
+        assert!(spans("3ae2f", &ParserOptions::default()).is_empty());
+        let options = ParserOptions {
+            case_insensitive_opcodes: true,
+            ..options
+        };
+        assert_eq!(vec![(2, 5)], spans("3AE2F", &options));
+
+    }
+
+    #[test]
+    fn test_bindings() {
+        let parse = |expression: &str| Parser::new(expression.to_string()).parse();
+        assert_eq!(Ok(5), parse("let x = 3 in x a 2"));
+        assert_eq!(Ok(25), parse("let t = 3a2 in t c t"));
+        assert_eq!(Ok(20), parse("sum(1..4) c 2"));
+        assert_eq!(
+            Err(ParseError::InvalidBinding {
+                source: Box::new(DefinitionError::MalformedBinding("letx=3x".to_string()))
+            }),
+            parse("let x = 3 x")
+        );
+        // Functions, bound locally or defined with the other named formulas
+        assert_eq!(Ok(25), parse("let sq = fn(x) -> x c x in sq[3] a sq[4]"));
+        let definitions = Definitions::parse("ratio = fn(a, b) -> a d b\nhalf = {x} d 2").unwrap();
+        let expression = "let sq = fn(x) -> x c x in sq[4] a @ratio[10, 2] a @half[8]";
+        let parser = Parser::new(expression.to_string()).with_definitions(definitions);
+        assert_eq!(Ok(25), parser.parse());
+        assert_eq!(
+            Err(ParseError::InvalidBinding {
+                source: Box::new(DefinitionError::Undefined("ratio".to_string()))
+            }),
+            Parser::new("@ratio[10, 2]".to_string())
+                .with_definitions(Definitions::default())
+                .parse()
+        );
+        // The limits apply to the desugared expression
+        let parser = Parser::new("let x = 3 in x a x".to_string()).with_limits(Limits {
+            max_length: Some(4),
+            ..Limits::default()
+        });
+        assert_eq!(Ok(6), parser.parse());
+        let parser = Parser::new("let x = 1234 in x a x".to_string()).with_limits(Limits {
+            max_length: Some(8),
+            ..Limits::default()
+        });
+        assert_eq!(Err(ExpressionTooLong { max_length: 8 }), parser.parse());
+    }
+
+    #[test]
+    fn test_error_accessors() {
+        let error = Parser::new("3a2fc4".to_string()).parse().unwrap_err();
+        assert_eq!((Some(3), Some('f')), (error.position(), error.offending_char()));
+        // Positions are in the expression as written, whatever it expands to
+        let options = ParserOptions {
+            roman_numerals: true,
+            ..ParserOptions::default()
+        };
+        let error = Parser::new("XIIaVaa1".to_string()).with_options(options).parse().unwrap_err();
+        assert_eq!((Some(6), Some('a')), (error.position(), error.offending_char()));
+        let error = Parser::new("2c10%aa1".to_string()).parse().unwrap_err();
+        assert_eq!((Some(6), Some('a')), (error.position(), error.offending_char()));
+        let error = Parser::new("e10%c3fee2".to_string()).parse().unwrap_err();
+        assert_eq!((Some(7), Some('e')), (error.position(), error.offending_char()));
+        let error = Parser::new("3ax".to_string()).parse().unwrap_err();
+        assert_eq!(Some('x'), error.offending_char());
+        let options = ParserOptions {
+            trailing_input: TrailingInputPolicy::Strict,
+            ..Default::default()
+        };
+        let error = Parser::new("3a2fc4".to_string())
+            .with_options(options)
+            .parse()
+            .unwrap_err();
+        assert_eq!((Some(3), Some('f')), (error.position(), error.offending_char()));
+        let error = ParseError::InvalidOperation { source: OverflowError };
+        assert_eq!((None, None), (error.position(), error.offending_char()));
+    }
+
+    #[test]
+    fn test_parser_state_display() {
+        let states = [
+            ParserState::FirstOperand,
+            ParserState::Operation,
+            ParserState::SecondOperand,
+            ParserState::CloseParenthesis,
+        ];
+        for state in states {
+            assert_eq!(Ok(state), state.to_string().parse());
+        }
+        assert_eq!("second operand", ParserState::SecondOperand.to_string());
+        assert_eq!(
+            Err(ParseError::IllegalState { reason: "Unknown parser state done".to_string() }),
+            "done".parse::<ParserState>()
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive_opcodes() {
+        let result = Parser::new("3A2".to_string()).parse();
+        assert_eq!(Err(MalformedExpression { position: 1, found: "A".to_string() }), result);
+
+        let options = ParserOptions {
+            case_insensitive_opcodes: true,
+            ..Default::default()
+        };
+        let parser = Parser::new("E3A2FC4".to_string()).with_options(options.clone());
+        assert_eq!(Ok(20), parser.parse());
+        let parser = Parser::new("e3A2fC4d2".to_string()).with_options(options.clone());
+        assert_eq!(Ok(10), parser.parse());
+        // Opcodes take precedence over the Roman numerals sharing their letters
+        let options = ParserOptions {
+            roman_numerals: true,
+            ..options
+        };
+        let parser = Parser::new("XCII".to_string()).with_options(options);
+        assert_eq!(Ok(20), parser.parse());
+    }
+
+    #[test]
+    fn test_rounding() {
+        let parse = |expression: &str, rounding| {
+            let options = ParserOptions {
+                rounding,
+                ..Default::default()
+            };
+            Parser::new(expression.to_string()).with_options(options).parse()
+        };
+        for rounding in [RoundingMode::Trunc, RoundingMode::Floor, RoundingMode::Euclid] {
+            assert_eq!(Ok(3), parse("7d2", rounding));
+        }
+        assert_eq!(Ok(4), parse("7d2", RoundingMode::Ceil));
+        assert_eq!(Ok(3), parse("6d2", RoundingMode::Ceil));
+        assert_eq!(Ok(4), parse("7d2", RoundingMode::Round));
+        assert_eq!(Ok(2), parse("7d3", RoundingMode::Round));
+        assert_eq!(Ok(3), parse("8d3", RoundingMode::Round));
+        // Groups divide with the same rounding
+        assert_eq!(Ok(3), parse("7de1a2f", RoundingMode::Ceil));
+        assert_eq!(Ok(5), parse("7d2a1", RoundingMode::Ceil));
+        assert_eq!(
+            Err(ParseError::InvalidOperation { source: OverflowError }),
+            parse("7d0", RoundingMode::Ceil)
+        );
+    }
+
+    #[test]
+    fn test_untrusted_limits() {
+        let deep = format!("{}1{}", "e".repeat(65), "f".repeat(65));
+        assert_eq!(Ok(1), Parser::new(deep.clone()).parse());
+        let parser = Parser::new(deep).with_limits(Limits::untrusted());
+        assert_eq!(Err(NestingTooDeep { position: 64, max_depth: 64 }), parser.parse());
+        let long = format!("1{}", "a1".repeat(2048));
+        let parser = Parser::new(long).with_limits(Limits::untrusted());
+        assert_eq!(Err(ExpressionTooLong { max_length: 4096 }), parser.parse());
+        let parser = Parser::new(format!("{}a1", usize::MAX - 1)).with_limits(Limits::untrusted());
+        assert_eq!(Ok(usize::MAX), parser.parse());
+        // Limits set later replace the preset
+        let parser = Parser::new("e1f".to_string())
+            .with_limits(Limits::untrusted())
+            .with_limits(Limits::unlimited());
+        assert_eq!(Ok(1), parser.parse());
+    }
+
+    #[test]
+    fn test_max_digits() {
+        let options = ParserOptions {
+            max_digits: Some(3),
+            ..Default::default()
+        };
+        let parser = Parser::new("123a456".to_string()).with_options(options.clone());
+        assert_eq!(Ok(579), parser.parse());
+
+        let parser = Parser::new("12a3456".to_string()).with_options(options.clone());
+        assert_eq!(Err(OperandTooLong { position: 3, max_digits: 3 }), parser.parse());
+
+        let expression = format!("1a{}", "9".repeat(100_000));
+        let parser = Parser::new(expression).with_options(options.clone());
+        assert_eq!(Err(OperandTooLong { position: 2, max_digits: 3 }), parser.parse());
+
+        let options = ParserOptions {
+            radix: 16,
+            ..options
+        };
+        let parser = Parser::new("FFFaFF".to_string()).with_options(options.clone());
+        assert_eq!(Ok(4350), parser.parse());
+        let parser = Parser::new("1aFFFF".to_string()).with_options(options);
+        assert_eq!(Err(OperandTooLong { position: 2, max_digits: 3 }), parser.parse());
+    }
+
+    #[test]
+    fn test_limits() {
+        let options = ParserOptions {
+            max_depth: Some(2),
+            max_length: Some(12),
+            ..Default::default()
+        };
+        let parser = Parser::new("ee3a2fc2fc4".to_string()).with_options(options.clone());
+        assert_eq!(Ok(40), parser.parse());
+
+        let parser = Parser::new("eee3fff".to_string()).with_options(options.clone());
+        assert_eq!(Err(NestingTooDeep { position: 2, max_depth: 2 }), parser.parse());
+
+        let parser = Parser::new("1a1a1a1a1a1a1".to_string()).with_options(options.clone());
+        assert_eq!(Err(ExpressionTooLong { max_length: 12 }), parser.parse());
+
+        // Multibyte characters count once
+        let parser = Parser::new("3a2é".repeat(3)).with_options(options.clone());
+        assert!(!matches!(parser.parse(), Err(ExpressionTooLong { .. })));
+
+        let expression = "e".repeat(1_000_000);
+        let parser = Parser::new(expression).with_options(ParserOptions {
+            max_depth: Some(100),
+            ..Default::default()
+        });
+        assert_eq!(Err(NestingTooDeep { position: 100, max_depth: 100 }), parser.parse());
+    }
+
+    #[test]
+    fn test_max_operations() {
+        let options = ParserOptions {
+            max_operations: Some(2),
+            ..Default::default()
+        };
+        let parse = |expression: &str| {
+            Parser::new(expression.to_string()).with_options(options.clone()).parse()
+        };
+        assert_eq!(Ok(20), parse("3a2c4"));
+        assert_eq!(Err(TooManyOperations { position: 5, max_operations: 2 }), parse("3a2c4b1"));
+        assert_eq!(Err(TooManyOperations { position: 8, max_operations: 2 }), parse("e3a2fce4d2f"));
+        // Percentages apply a division
+        assert_eq!(Ok(10), parse("200c5%"));
+        assert!(matches!(parse("200c5%a1"), Err(TooManyOperations { .. })));
+        // The bindings apply the operations of their value every time they are used
+        assert!(matches!(parse("let t = 3a2 in t c t"), Err(TooManyOperations { .. })));
+
+        let parser =
+            Parser::new(format!("1{}", "a1".repeat(1025))).with_limits(Limits::untrusted());
+        assert_eq!(Err(TooManyOperations { position: 2049, max_operations: 1024 }), parser.parse());
+        assert_eq!(Some(2049), parser.parse().unwrap_err().position());
+    }
+
+    #[test]
+    fn test_error_code() {
+        let parser = Parser::new("3aa2".to_string());
+        assert_eq!("E003", parser.parse().unwrap_err().error_code());
+        let parser = Parser::new("3d0".to_string());
+        assert_eq!("E104", parser.parse().unwrap_err().error_code());
+        assert_eq!("E001", EmptyExpression.error_code());
+        assert_eq!("E014", ExpressionTooLong { max_length: 1 }.error_code());
+        assert_eq!("E018", TooManyOperations { position: 0, max_operations: 1 }.error_code());
+        assert_eq!("E019", ParseError::InvalidWidth { width: 12 }.error_code());
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let token = Arc::new(AtomicBool::new(false));
+        let parser = Parser::new("3a2".to_string()).with_cancellation(Arc::clone(&token));
+        assert_eq!(Ok(5), parser.parse());
+
+        token.store(true, Ordering::Relaxed);
+        let parser = Parser::new("3a2".to_string()).with_cancellation(Arc::clone(&token));
+        assert_eq!(Err(ParseError::Cancelled { position: 0 }), parser.parse());
+
+        /// Holds the parse process at its first operand until the token is set
+        struct Pause(Arc<Barrier>, Cell<bool>);
+
+        impl DiagnosticsSink for Pause {
+            fn enabled(&self, _level: Level) -> bool {
+                !self.1.get()
+            }
+
+            fn diagnostic(&self, _level: Level, message: fmt::Arguments) {
+                if message.to_string().starts_with("a = ") {
+                    self.1.set(true);
+                    self.0.wait();
+                    self.0.wait();
+                }
+            }
+        }
+
+        // Cancelled from another thread while running
+        let token = Arc::new(AtomicBool::new(false));
+        let cancellation = Arc::clone(&token);
+        let barrier = Arc::new(Barrier::new(2));
+        let pause = Pause(Arc::clone(&barrier), Cell::new(false));
+        let parse = std::thread::spawn(move || {
+            let expression = "1a".repeat(2_000_000) + "1";
+            Parser::new(expression)
+                .with_cancellation(cancellation)
+                .with_diagnostics(pause)
+                .parse()
+        });
+        barrier.wait();
+        token.store(true, Ordering::Relaxed);
+        barrier.wait();
+        assert!(matches!(parse.join().unwrap(), Err(ParseError::Cancelled { position: 1 })));
+    }
+
+    #[test]
+    fn test_eval_many() {
+        let expressions: Vec<String> = (0..1000).map(|n| format!("{}a1c2", n)).collect();
+        let expressions: Vec<&str> = expressions.iter().map(String::as_str).collect();
+        let results = Parser::eval_many(&expressions, &ParserOptions::default());
+        assert_eq!((0..1000).map(|n| Ok((n + 1) * 2)).collect::<Vec<_>>(), results);
+
+        let results = Parser::eval_many(&["3a2", "3aa2", "ec4"], &ParserOptions::default());
+        assert_eq!(Ok(5), results[0]);
+        assert_eq!(Err(MalformedExpression { position: 2, found: "a".to_string() }), results[1]);
+        assert_eq!(
+            Err(UnbalancedParenthesis { position: 0, parenthesis: "e".to_string() }),
+            results[2]
+        );
+        assert!(Parser::eval_many(&[], &ParserOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_eval_batch() {
+        let rows: Vec<HashMap<String, usize>> = (0..1000)
+            .map(|n| HashMap::from([("A1".to_string(), n), ("B1".to_string(), 2)]))
+            .collect();
+        let results = Parser::eval_batch("A1a1cB1", &rows, &ParserOptions::default());
+        assert_eq!((0..1000).map(|n| Ok((n + 1) * 2)).collect::<Vec<_>>(), results);
+
+        let rows = [HashMap::from([("A1".to_string(), 3)]), HashMap::new()];
+        assert_eq!(
+            vec![Ok(6), Err(ParseError::UnresolvedCell { reference: "A1".to_string() })],
+            Parser::eval_batch("A1c2", &rows, &ParserOptions::default())
+        );
+    }
+
+    #[test]
+    fn test_initial() {
+        let parser = Parser::new("c4".to_string()).with_initial(5);
+        assert_eq!(Ok(20), parser.parse());
+        let parser = Parser::new("de1a1f".to_string()).with_initial(20);
+        assert_eq!(Ok(10), parser.parse());
+        let parser = Parser::new("".to_string()).with_initial(5);
+        assert_eq!(Ok(5), parser.parse());
+    }
+
+    #[test]
+    fn test_percentages() {
+        let parse = |expression: &str| Parser::new(expression.to_string()).parse();
+        assert_eq!(Ok(220), parse("200a10%"));
+        assert_eq!(Ok(180), parse("200b10%"));
+        assert_eq!(Ok(20), parse("200c10%"));
+        assert_eq!(Ok(2000), parse("200d10%"));
+        assert_eq!(Ok(150), parse("50%c300"));
+        // The percentage applies to the result so far, and a percentage first operand to its group
+        assert_eq!(Ok(121), parse("100a10%a10%"));
+        assert_eq!(Ok(154), parse("e50%c300fa4"));
+        assert_eq!(Ok(180), parse("3c4ce50%c300fd10"));
+        let parser = Parser::new("a10%".to_string()).with_initial(200);
+        assert_eq!(Ok(220), parser.parse());
+        assert_eq!(
+            Err(ParseError::MalformedExpression { position: 5, found: "%".to_string() }),
+            parse("e3a2f%")
+        );
+        assert_eq!(
+            Err(ParseError::MalformedExpression { position: 2, found: "%".to_string() }),
+            parse("3a%")
+        );
+        // Digits after a percentage are not part of it
+        assert_eq!(
+            Err(ParseError::MalformedExpression { position: 5, found: "5".to_string() }),
+            parse("2c10%5")
+        );
+        assert_eq!(
+            Err(ParseError::MalformedExpression { position: 5, found: "%".to_string() }),
+            parse("2c10%%")
+        );
+    }
+
+    #[test]
+    fn test_gcd_lcm() {
+        let parse = |expression: &str| Parser::new(expression.to_string()).parse();
+        assert_eq!(Ok(6), parse("12g18"));
+        assert_eq!(Ok(13), parse("4h6a1"));
+        assert_eq!(Ok(12), parse("e12g18fh4"));
+        assert_eq!(Ok(24), parse("2ce9h12fg48"));
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let nested = |depth: usize| format!("{}1{}", "e".repeat(depth), "f".repeat(depth));
+        assert_eq!(Ok(1), Parser::new(nested(MAX_RECURSION)).parse());
+        assert_eq!(Ok(1), Parser::parse_bytes(nested(MAX_RECURSION).as_bytes()));
+        let error =
+            ParseError::DepthLimitExceeded { position: MAX_RECURSION, max_depth: MAX_RECURSION };
+        assert_eq!(Err(error), Parser::new(nested(100_000)).parse());
+        let error =
+            ParseError::DepthLimitExceeded { position: MAX_RECURSION, max_depth: MAX_RECURSION };
+        assert_eq!(Err(error), Parser::parse_bytes(nested(100_000).as_bytes()));
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let options = ParserOptions {
+            wrapping: true,
+            ..Default::default()
+        };
+        let parse = |expression: &str| {
+            Parser::new(expression.to_string())
+                .with_options(options.clone())
+                .parse()
+        };
+        assert_eq!(Ok(usize::MAX - 1), parse("3b5"));
+        assert_eq!(Ok(0), parse("3b5a2"));
+        assert_eq!(Ok(usize::MAX), parse("0be3b2f"));
+        assert_eq!(Ok(0), parse(&format!("{}c2a2", usize::MAX)));
+        assert_eq!(
+            Err(ParseError::InvalidOperation { source: OverflowError }),
+            parse("3d0")
+        );
+        // The operation wraps around once, on the complete operand
+        let warnings = Rc::new(Warnings::default());
+        let result = Parser::new("1b23".to_string())
+            .with_options(options.clone())
+            .with_diagnostics(Rc::clone(&warnings))
+            .parse();
+        assert_eq!(Ok(usize::MAX - 21), result);
+        let messages: Vec<String> = warnings.take().into_iter().map(|w| w.message).collect();
+        assert_eq!(
+            vec![format!("1 - 23 overflows, wrapping around to {}", usize::MAX - 21)],
+            messages
+        );
+    }
+    #[test]
+    fn test_signed() {
+        let options = ParserOptions {
+            signed_width: Some(8),
+            ..Default::default()
+        };
+        let parse = |expression: &str, options: &ParserOptions| {
+            Parser::new(expression.to_string())
+                .with_options(options.clone())
+                .parse()
+                .map(|result| bits::signed(result, options.signed_width.unwrap_or(64)))
+        };
+        let overflow = Err(ParseError::InvalidOperation { source: OverflowError });
+        assert_eq!(Ok(-2), parse("3b5", &options));
+        assert_eq!(Ok(0xFE), Parser::new("3b5".to_string()).with_options(options.clone()).parse());
+        assert_eq!(Ok(-100), parse("0b100d2c2", &options));
+        assert_eq!(Ok(6), parse("e0b12fge0b18f", &options));
+        assert_eq!(Ok(12), parse("e0b4fh6", &options));
+        assert_eq!(overflow, parse("127a1", &options));
+        assert_eq!(overflow, parse("0b100c2", &options));
+        // The quotient of the smallest integer by -1 is one past the largest
+        assert_eq!(overflow, parse("0b127b1de0b1f", &options));
+        assert_eq!(overflow, parse("3d0", &options));
+        assert_eq!(
+            Err(ParseDigitError {
+                operand: "128".to_string(),
+                message: "number too large to fit in target type".to_string()
+            }),
+            parse("1a128", &options)
+        );
+        let rounded = |rounding| parse("e0b7fd2", &ParserOptions { rounding, ..options.clone() });
+        assert_eq!(Ok(-3), rounded(RoundingMode::Trunc));
+        assert_eq!(Ok(-4), rounded(RoundingMode::Floor));
+        assert_eq!(Ok(-3), rounded(RoundingMode::Ceil));
+        assert_eq!(Ok(-4), rounded(RoundingMode::Round));
+        assert_eq!(Ok(-4), rounded(RoundingMode::Euclid));
+
+        let wrapping = ParserOptions { wrapping: true, ..options.clone() };
+        assert_eq!(Ok(-128), parse("127a1", &wrapping));
+        assert_eq!(Ok(56), parse("0b100c2", &wrapping));
+        assert_eq!(overflow, parse("3d0", &wrapping));
+        let warnings = Rc::new(Warnings::default());
+        let result = Parser::new("0b127b1de0b1f".to_string())
+            .with_options(wrapping)
+            .with_diagnostics(Rc::clone(&warnings))
+            .parse();
+        assert_eq!(Ok(0x80), result);
+        let messages: Vec<String> = warnings.take().into_iter().map(|w| w.message).collect();
+        assert_eq!(vec!["-128 / -1 overflows, wrapping around to -128".to_string()], messages);
+
+        let options = ParserOptions { signed_width: Some(16), ..options };
+        assert_eq!(Ok(i16::MIN as i64), parse("0b32767b1", &options));
+        let options = ParserOptions { signed_width: Some(64), ..options };
+        assert_eq!(Ok(-1), parse("0b1", &options));
+        assert_eq!(overflow, parse(&format!("0b{}b1de0b1f", i64::MAX), &options));
+        let options = ParserOptions { signed_width: Some(12), ..options };
+        assert_eq!(Err(ParseError::InvalidWidth { width: 12 }), parse("3", &options));
+    }
 }
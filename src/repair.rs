@@ -0,0 +1,171 @@
+use std::fmt;
+
+use crate::operation::codes::{
+    OPCODE_ADD, OPCODE_CLOSE, OPCODE_DIV, OPCODE_GCD, OPCODE_LCM, OPCODE_MUL, OPCODE_OPEN,
+    OPCODE_SUB,
+};
+
+/// An edit of an expression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fix {
+    /// Insert a character before the given position (position, character)
+    Insert(usize, char),
+    /// Remove the character at the given position (position)
+    Remove(usize),
+}
+
+impl fmt::Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Insert(position, char) => write!(f, "insert {} at {}", char, position),
+            Self::Remove(position) => write!(f, "remove the character at {}", position),
+        }
+    }
+}
+
+/// The alternative fixes of an unmatched parenthesis, either of which balances it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Suggestion {
+    /// The position of the unmatched parenthesis
+    pub position: usize,
+    /// Removing the unmatched parenthesis
+    pub remove: Fix,
+    /// Inserting the matching parenthesis
+    pub insert: Fix,
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unmatched parenthesis at {}: {}, or {}",
+            self.position, self.remove, self.insert
+        )
+    }
+}
+
+/// Suggest how to balance the parentheses of an expression. Positions are character offsets in the
+/// expression as written, and every suggestion applies to the original expression independently
+/// of the others.
+/// # Arguments
+///  - expression: The expression to check
+/// # Return
+/// The `Suggestion`s, one per unmatched parenthesis, empty if the parentheses are balanced
+pub fn suggest_balance(expression: &str) -> Vec<Suggestion> {
+    let mut suggestions = Vec::new();
+    let mut open = Vec::new();
+    let mut length = 0;
+    for (position, char) in expression.chars().enumerate() {
+        length = position + 1;
+        match char {
+            OPCODE_OPEN => open.push(position),
+            OPCODE_CLOSE if open.pop().is_none() => {
+                suggestions.push(Suggestion {
+                    position,
+                    remove: Fix::Remove(position),
+                    // The missing parenthesis makes a group of everything preceding the closing one
+                    insert: Fix::Insert(0, OPCODE_OPEN),
+                });
+            }
+            _ => {}
+        }
+    }
+    suggestions.extend(open.into_iter().map(|position| Suggestion {
+        position,
+        remove: Fix::Remove(position),
+        insert: Fix::Insert(length, OPCODE_CLOSE),
+    }));
+    suggestions
+}
+
+/// Apply the repairs that cannot change the meaning of an expression: whitespace is stripped,
+/// operation codes repeated consecutively are dropped and parentheses left open at the end are closed
+/// # Arguments
+///  - expression: The expression to repair
+/// # Return
+/// A tuple having the repaired expression and the `Fix`es applied, whose positions are character
+/// offsets in the original expression
+pub fn repair(expression: &str) -> (String, Vec<Fix>) {
+    let mut repaired = String::with_capacity(expression.len());
+    let mut fixes = Vec::new();
+    let mut depth = 0usize;
+    let mut length = 0;
+    for (position, char) in expression.chars().enumerate() {
+        length = position + 1;
+        let duplicate = matches!(
+            char,
+            OPCODE_ADD | OPCODE_SUB | OPCODE_MUL | OPCODE_DIV | OPCODE_GCD | OPCODE_LCM
+        ) && repaired.ends_with(char);
+        if char.is_whitespace() || duplicate {
+            fixes.push(Fix::Remove(position));
+            continue;
+        }
+        match char {
+            OPCODE_OPEN => depth += 1,
+            OPCODE_CLOSE => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        repaired.push(char);
+    }
+    for _ in 0..depth {
+        fixes.push(Fix::Insert(length, OPCODE_CLOSE));
+        repaired.push(OPCODE_CLOSE);
+    }
+    (repaired, fixes)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::repair::{repair, suggest_balance, Fix, Suggestion};
+
+    #[test]
+    fn test_suggest_balance() {
+        assert_eq!(Vec::<Suggestion>::new(), suggest_balance("e3a2fc4"));
+        assert_eq!(
+            vec![Suggestion {
+                position: 0,
+                remove: Fix::Remove(0),
+                insert: Fix::Insert(7, 'f'),
+            }],
+            suggest_balance("e3a2c4b")
+        );
+        assert_eq!(
+            vec![
+                Suggestion {
+                    position: 3,
+                    remove: Fix::Remove(3),
+                    insert: Fix::Insert(0, 'e'),
+                },
+                Suggestion {
+                    position: 6,
+                    remove: Fix::Remove(6),
+                    insert: Fix::Insert(0, 'e'),
+                }
+            ],
+            suggest_balance("3a2fc4f")
+        );
+        assert_eq!(
+            "unmatched parenthesis at 3: remove the character at 3, or insert e at 0",
+            suggest_balance("3a2fc4")[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_repair() {
+        assert_eq!(("e3a2fc4".to_string(), vec![]), repair("e3a2fc4"));
+        assert_eq!(
+            (
+                "ee3a2fc4f".to_string(),
+                vec![
+                    Fix::Remove(3),
+                    Fix::Remove(5),
+                    Fix::Remove(6),
+                    Fix::Insert(11, 'f')
+                ]
+            ),
+            repair("ee3 aa 2fc4")
+        );
+        // Distinct operation codes, or unmatched closing parentheses, have no safe repair
+        assert_eq!(("3ab2f".to_string(), vec![]), repair("3ab2f"));
+    }
+}
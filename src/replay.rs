@@ -0,0 +1,391 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::events::ParseEvent;
+use crate::json;
+use crate::options::{
+    EvalOrder, LeadingZeroPolicy, ParserOptions, RoundingMode, TrailingInputPolicy,
+};
+use crate::parser::{ParseError, Parser};
+
+/// The start of the first line of every recording, identifying the file format
+const HEADER: &str = "# arithmetic-parser recording";
+
+/// The version of the recording format, bumped whenever the serialized outcome changes shape.
+/// Version 2 records errors with named fields, version 3 the position of every syntax error
+const VERSION: u32 = 3;
+
+/// Defines the errors raised reading or replaying a recording
+#[derive(Debug, PartialEq)]
+pub enum ReplayError {
+    /// The recording does not start with the expected header
+    MissingHeader,
+    /// The recording was written in another version of the format (version)
+    UnsupportedVersion(String),
+    /// A line of the recording cannot be read (line number, line)
+    MalformedLine(usize, String),
+    /// The recording has no expression
+    MissingExpression,
+    /// The replayed parse process diverged from the recorded one (step, recorded, replayed)
+    Diverged(usize, String, String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader => write!(f, "MissingHeader"),
+            Self::UnsupportedVersion(version) => write!(f, "UnsupportedVersion({})", version),
+            Self::MalformedLine(number, line) => write!(f, "MalformedLine({}, {:?})", number, line),
+            Self::MissingExpression => write!(f, "MissingExpression"),
+            Self::Diverged(step, recorded, replayed) => write!(
+                f,
+                "Diverged at step {}: recorded {}, replayed {}",
+                step, recorded, replayed
+            ),
+        }
+    }
+}
+
+/// A parse process saved with everything needed to reproduce it: the input, the options, the
+/// cells and, as recorded, every event and the outcome
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    /// The parsed expression
+    pub expression: String,
+    /// The result the parse process started from, if any
+    pub initial: Option<usize>,
+    /// The options the parser ran with
+    pub options: ParserOptions,
+    /// The cells defined by reference, sorted so that recordings are deterministic
+    pub cells: BTreeMap<String, usize>,
+    /// The events of the parse process, serialized as JSON
+    pub events: Vec<String>,
+    /// The outcome of the parse process, either `Ok(<result>)` or `Err(<error>)`
+    pub outcome: String,
+}
+
+/// Implementation of the recordings
+impl Recording {
+    /// Record a parse process
+    /// # Arguments
+    ///  - expression: The parsed expression
+    ///  - initial: The result the parse process started from, if any
+    ///  - options: The options the parser ran with
+    ///  - cells: The cells defined by reference
+    ///  - events: The `ParseEvent`s of the parse process
+    ///  - result: The `Result` of the parse process
+    /// # Return
+    /// The `Recording` of the parse process
+    pub fn new(
+        expression: String,
+        initial: Option<usize>,
+        options: ParserOptions,
+        cells: &HashMap<String, usize>,
+        events: &[ParseEvent],
+        result: &Result<usize, ParseError>,
+    ) -> Self {
+        Self {
+            expression,
+            initial,
+            options,
+            cells: cells.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+            events: events.iter().map(|event| event.to_json()).collect(),
+            outcome: format!("{:?}", result),
+        }
+    }
+
+    /// Serialize the recording, one field per line
+    /// # Return
+    /// The text of the recording
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![
+            format!("{} {}", HEADER, VERSION),
+            format!("expression {}", json::string(&self.expression)),
+            format!("leading_zeros {:?}", self.options.leading_zeros),
+            format!("roman_numerals {}", self.options.roman_numerals),
+            format!("radix {}", self.options.radix),
+            format!("trailing_input {:?}", self.options.trailing_input),
+            format!("rounding {:?}", self.options.rounding),
+            format!(
+                "case_insensitive_opcodes {}",
+                self.options.case_insensitive_opcodes
+            ),
+            format!("wrapping {}", self.options.wrapping),
+            format!("eval_order {:?}", self.options.eval_order),
+        ];
+        lines.extend(
+            self.options
+                .signed_width
+                .map(|width| format!("signed_width {}", width)),
+        );
+        lines.extend(self.initial.map(|initial| format!("initial {}", initial)));
+        lines.extend(
+            self.cells
+                .iter()
+                .map(|(reference, value)| format!("cell {} {}", reference, value)),
+        );
+        lines.extend(self.events.iter().map(|event| format!("event {}", event)));
+        lines.push(format!("outcome {}", self.outcome));
+        lines.join("\n")
+    }
+
+    /// Read a recording serialized by `to_text`
+    /// # Arguments
+    ///  - text: The text of the recording
+    /// # Return
+    /// A `Result` having the `Recording` if the text is valid, `ReplayError` otherwise
+    pub fn from_text(text: &str) -> Result<Self, ReplayError> {
+        let mut lines = text.lines().enumerate();
+        let version = lines
+            .next()
+            .and_then(|(_, line)| line.strip_prefix(HEADER))
+            .and_then(|rest| rest.strip_prefix(' '))
+            .ok_or(ReplayError::MissingHeader)?;
+        if version != VERSION.to_string() {
+            return Err(ReplayError::UnsupportedVersion(version.to_string()));
+        }
+        let mut expression = None;
+        let mut recording = Self {
+            expression: String::new(),
+            initial: None,
+            options: ParserOptions::default(),
+            cells: BTreeMap::new(),
+            events: Vec::new(),
+            outcome: String::new(),
+        };
+        for (index, line) in lines {
+            let malformed = || ReplayError::MalformedLine(index + 1, line.to_string());
+            if line.is_empty() {
+                continue;
+            }
+            let (field, value) = line.split_once(' ').ok_or_else(malformed)?;
+            match field {
+                "expression" => expression = Some(json::parse_string(value).ok_or_else(malformed)?),
+                "leading_zeros" => {
+                    recording.options.leading_zeros = match value {
+                        "Accept" => LeadingZeroPolicy::Accept,
+                        "Warn" => LeadingZeroPolicy::Warn,
+                        "Reject" => LeadingZeroPolicy::Reject,
+                        _ => return Err(malformed()),
+                    }
+                }
+                "roman_numerals" => {
+                    recording.options.roman_numerals = value.parse().map_err(|_| malformed())?
+                }
+                "case_insensitive_opcodes" => {
+                    recording.options.case_insensitive_opcodes =
+                        value.parse().map_err(|_| malformed())?
+                }
+                "wrapping" => {
+                    recording.options.wrapping = value.parse().map_err(|_| malformed())?
+                }
+                "eval_order" => {
+                    recording.options.eval_order = match value {
+                        "LeftToRight" => EvalOrder::LeftToRight,
+                        _ => return Err(malformed()),
+                    }
+                }
+                "signed_width" => {
+                    recording.options.signed_width = Some(value.parse().map_err(|_| malformed())?)
+                }
+                "initial" => recording.initial = Some(value.parse().map_err(|_| malformed())?),
+                "radix" => recording.options.radix = value.parse().map_err(|_| malformed())?,
+                "trailing_input" => {
+                    recording.options.trailing_input = match value {
+                        "Unchecked" => TrailingInputPolicy::Unchecked,
+                        "Strict" => TrailingInputPolicy::Strict,
+                        "Lenient" => TrailingInputPolicy::Lenient,
+                        _ => return Err(malformed()),
+                    }
+                }
+                "rounding" => {
+                    recording.options.rounding = match value {
+                        "Trunc" => RoundingMode::Trunc,
+                        "Floor" => RoundingMode::Floor,
+                        "Ceil" => RoundingMode::Ceil,
+                        "Round" => RoundingMode::Round,
+                        "Euclid" => RoundingMode::Euclid,
+                        _ => return Err(malformed()),
+                    }
+                }
+                "cell" => {
+                    let (reference, value) = value.split_once(' ').ok_or_else(malformed)?;
+                    let value = value.parse().map_err(|_| malformed())?;
+                    recording.cells.insert(reference.to_string(), value);
+                }
+                "event" => recording.events.push(value.to_string()),
+                "outcome" => recording.outcome = value.to_string(),
+                _ => return Err(malformed()),
+            }
+        }
+        recording.expression = expression.ok_or(ReplayError::MissingExpression)?;
+        Ok(recording)
+    }
+
+    /// Parse the recorded expression again, with the recorded options and cells, checking that
+    /// the process goes through the same events to the same outcome
+    /// # Return
+    /// A `Result` having the outcome if the replay matches the recording, `ReplayError` otherwise
+    pub fn replay(&self) -> Result<&str, ReplayError> {
+        let mut parser = Parser::new(self.expression.clone()).with_options(self.options.clone());
+        if let Some(initial) = self.initial {
+            parser = parser.with_initial(initial);
+        }
+        if !self.cells.is_empty() {
+            let cells: HashMap<String, usize> = self.cells.clone().into_iter().collect();
+            parser = parser.with_cell_resolver(cells);
+        }
+        let (result, events) = parser.parse_with_events();
+        let replayed = Self::new(
+            self.expression.clone(),
+            self.initial,
+            self.options.clone(),
+            &HashMap::new(),
+            &events,
+            &result,
+        );
+        let end = "end of the events".to_string();
+        let steps = self.events.len().max(replayed.events.len());
+        for step in 0..steps {
+            let recorded = self.events.get(step).unwrap_or(&end);
+            let event = replayed.events.get(step).unwrap_or(&end);
+            if recorded != event {
+                return Err(ReplayError::Diverged(step, recorded.clone(), event.clone()));
+            }
+        }
+        if self.outcome != replayed.outcome {
+            return Err(ReplayError::Diverged(
+                steps,
+                self.outcome.clone(),
+                replayed.outcome,
+            ));
+        }
+        Ok(&self.outcome)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::options::{LeadingZeroPolicy, ParserOptions, RoundingMode};
+    use crate::parser::Parser;
+    use crate::replay::{Recording, ReplayError};
+
+    fn record(
+        expression: &str,
+        options: ParserOptions,
+        cells: HashMap<String, usize>,
+    ) -> Recording {
+        let mut parser = Parser::new(expression.to_string()).with_options(options.clone());
+        if !cells.is_empty() {
+            parser = parser.with_cell_resolver(cells.clone());
+        }
+        let (result, events) = parser.parse_with_events();
+        Recording::new(
+            expression.to_string(),
+            None,
+            options,
+            &cells,
+            &events,
+            &result,
+        )
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let options = ParserOptions {
+            leading_zeros: LeadingZeroPolicy::Warn,
+            roman_numerals: true,
+            rounding: RoundingMode::Ceil,
+            case_insensitive_opcodes: true,
+            wrapping: true,
+            signed_width: Some(16),
+            ..Default::default()
+        };
+        let cells = HashMap::from([("B2".to_string(), 4), ("A1".to_string(), 3)]);
+        let recording = record("e\"XaA1fcB2", options, cells);
+        let text = recording.to_text();
+        assert!(text.contains("cell A1 3\ncell B2 4"));
+        assert!(text.contains("\neval_order LeftToRight\n"));
+        assert!(text.contains("\nsigned_width 16\n"));
+        assert_eq!(Ok(recording.clone()), Recording::from_text(&text));
+    }
+
+    #[test]
+    fn test_replay() {
+        let options = ParserOptions {
+            roman_numerals: true,
+            ..Default::default()
+        };
+        let cells = HashMap::from([("B2".to_string(), 4), ("A1".to_string(), 3)]);
+        let recording = record("eXaA1fcB2", options, cells);
+        assert_eq!(Ok("Ok(52)"), recording.replay());
+        let recording = record("3a2c4", ParserOptions::default(), HashMap::new());
+        assert_eq!(Ok("Ok(20)"), recording.replay());
+        let recording = record("3aa2", ParserOptions::default(), HashMap::new());
+        assert_eq!(
+            Ok("Err(MalformedExpression { position: 2, found: \"a\" })"),
+            recording.replay()
+        );
+
+        let parser = Parser::new("c4".to_string()).with_initial(5);
+        let (result, events) = parser.parse_with_events();
+        let options = ParserOptions::default();
+        let recording = Recording::new(
+            "c4".to_string(),
+            Some(5),
+            options,
+            &HashMap::new(),
+            &events,
+            &result,
+        );
+        assert_eq!(
+            Ok(recording.clone()),
+            Recording::from_text(&recording.to_text())
+        );
+        assert_eq!(Ok("Ok(20)"), recording.replay());
+    }
+
+    #[test]
+    fn test_diverged() {
+        let mut recording = record("3a2c4", ParserOptions::default(), HashMap::new());
+        recording.events.truncate(3);
+        assert!(matches!(
+            recording.replay(),
+            Err(ReplayError::Diverged(3, _, _))
+        ));
+        let mut recording = record("3a2c4", ParserOptions::default(), HashMap::new());
+        recording.outcome = "Ok(14)".to_string();
+        assert_eq!(
+            Err(ReplayError::Diverged(
+                recording.events.len(),
+                "Ok(14)".to_string(),
+                "Ok(20)".to_string()
+            )),
+            recording.replay()
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(
+            Err(ReplayError::MissingHeader),
+            Recording::from_text("expression \"3\"")
+        );
+        assert_eq!(
+            Err(ReplayError::UnsupportedVersion("1".to_string())),
+            Recording::from_text("# arithmetic-parser recording 1\nexpression \"3\"")
+        );
+        let text = "# arithmetic-parser recording 3\nradix ten";
+        assert_eq!(
+            Err(ReplayError::MalformedLine(2, "radix ten".to_string())),
+            Recording::from_text(text)
+        );
+        let text = "# arithmetic-parser recording 3\noutcome Ok(3)";
+        assert_eq!(
+            Err(ReplayError::MissingExpression),
+            Recording::from_text(text)
+        );
+    }
+}
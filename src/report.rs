@@ -0,0 +1,90 @@
+use crate::options::EvalOrder;
+use crate::parser::ParseError;
+
+/// A limit bounding the work of a parse process
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    /// The largest number of digits of an operand, `ParserOptions::max_digits`
+    Digits,
+    /// The deepest parenthesis nesting, `ParserOptions::max_depth`
+    Depth,
+    /// The largest number of characters of an expression, `ParserOptions::max_length`
+    Length,
+    /// The largest number of operations applied, `ParserOptions::max_operations`
+    Operations,
+    /// The deepest parenthesis nesting of any parse process, `MAX_RECURSION`
+    Recursion,
+}
+
+/// Implementation of the limits
+impl Limit {
+    /// The limit a parse process failed on
+    /// # Arguments
+    ///  - error: The `ParseError` of the parse process
+    /// # Return
+    /// The `Limit` hit, `None` if the error is not about a limit
+    pub fn of(error: &ParseError) -> Option<Self> {
+        match error {
+            ParseError::OperandTooLong { .. } => Some(Self::Digits),
+            ParseError::NestingTooDeep { .. } => Some(Self::Depth),
+            ParseError::ExpressionTooLong { .. } => Some(Self::Length),
+            ParseError::TooManyOperations { .. } => Some(Self::Operations),
+            ParseError::DepthLimitExceeded { .. } => Some(Self::Recursion),
+            _ => None,
+        }
+    }
+}
+
+/// How the result of an expression was produced, for auditing systems storing it
+#[derive(Debug, PartialEq)]
+pub struct EvaluationReport {
+    /// The result of the parse process
+    pub value: Result<usize, ParseError>,
+    /// The number of operations applied
+    pub ops_count: usize,
+    /// The deepest parenthesis nesting reached
+    pub depth: usize,
+    /// The order the operations were applied in
+    pub mode: EvalOrder,
+    /// The limits the parse process failed on, empty if it did not
+    pub limits_hit: Vec<Limit>,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::options::{EvalOrder, ParserOptions};
+    use crate::parser::{ParseError, Parser};
+    use crate::report::{EvaluationReport, Limit};
+
+    #[test]
+    fn test_parse_report() {
+        assert_eq!(
+            EvaluationReport {
+                value: Ok(20),
+                ops_count: 2,
+                depth: 1,
+                mode: EvalOrder::LeftToRight,
+                limits_hit: Vec::new(),
+            },
+            Parser::new("e3a2fc4".to_string()).parse_report()
+        );
+        let options = ParserOptions {
+            max_digits: Some(2),
+            ..ParserOptions::default()
+        };
+        let report = Parser::new("3a200".to_string())
+            .with_options(options)
+            .parse_report();
+        assert_eq!(
+            Err(ParseError::OperandTooLong {
+                position: 2,
+                max_digits: 2
+            }),
+            report.value
+        );
+        assert_eq!(vec![Limit::Digits], report.limits_hit);
+        let report = Parser::new("3aa2".to_string()).parse_report();
+        assert!(report.value.is_err());
+        assert!(report.limits_hit.is_empty());
+    }
+}
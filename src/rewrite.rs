@@ -0,0 +1,53 @@
+use crate::expr::{Expr, Pattern};
+
+/// Rewrite every expression of a formula file, one per line, replacing the subtrees matching a
+/// pattern. Blank lines, lines starting with `#` and the expressions which do not parse, e.g.
+/// those referencing earlier lines (`$2`), are left as they are, and so are the expressions
+/// having no match, so that only the rewritten lines change.
+/// # Arguments
+///  - text: The formula file
+///  - pattern: The `Pattern` to look for
+///  - template: The `Pattern` replacing the matches
+/// # Return
+/// The rewritten text and the number of expressions rewritten
+pub fn rewrite(text: &str, pattern: &Pattern, template: &Pattern) -> (String, usize) {
+    let mut rewritten = 0;
+    let mut lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            let expression = line.trim();
+            if expression.is_empty() || expression.starts_with('#') {
+                return line.to_string();
+            }
+            let expr = Expr::parse_tolerant(expression);
+            if !expr.errors().is_empty() || expr.find_all(pattern).is_empty() {
+                return line.to_string();
+            }
+            rewritten += 1;
+            let indent = &line[..line.len() - line.trim_start().len()];
+            format!("{}{}", indent, expr.replace(pattern, template).to_code())
+        })
+        .collect();
+    if text.ends_with('\n') {
+        lines.push(String::new());
+    }
+    (lines.join("\n"), rewritten)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::expr::Pattern;
+    use crate::rewrite::rewrite;
+
+    #[test]
+    fn test_rewrite() {
+        let pattern = Pattern::parse("_ c 1").unwrap();
+        let template = Pattern::parse("_").unwrap();
+        let text = "# Budget\n3c1a2\n\n  e4a1fc1c1\n$2c1\n5a6\n";
+        assert_eq!(
+            ("# Budget\n3a2\n\n  4a1\n$2c1\n5a6\n".to_string(), 2),
+            rewrite(text, &pattern, &template)
+        );
+        assert_eq!(("7a1".to_string(), 0), rewrite("7a1", &pattern, &template));
+    }
+}
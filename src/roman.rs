@@ -0,0 +1,87 @@
+/// The symbols of the Roman numeral system, including the subtractive pairs, by decreasing value
+const NUMERALS: [(&str, usize); 13] = [
+    ("M", 1000),
+    ("CM", 900),
+    ("D", 500),
+    ("CD", 400),
+    ("C", 100),
+    ("XC", 90),
+    ("L", 50),
+    ("XL", 40),
+    ("X", 10),
+    ("IX", 9),
+    ("V", 5),
+    ("IV", 4),
+    ("I", 1),
+];
+
+/// The largest value that can be written in standard Roman notation
+pub const MAX_ROMAN: usize = 3999;
+
+/// Check whether a character is a Roman numeral symbol
+pub fn is_roman_symbol(char: char) -> bool {
+    matches!(char, 'I' | 'V' | 'X' | 'L' | 'C' | 'D' | 'M')
+}
+
+/// Converts a Roman numeral to its value
+/// # Arguments
+///  - numeral: The Roman numeral, in uppercase standard notation
+/// # Return
+/// An `Option` having the value if the numeral is well-formed, `None` otherwise
+pub fn from_roman(numeral: &str) -> Option<usize> {
+    let mut value = 0;
+    let mut rest = numeral;
+    for (symbol, symbol_value) in NUMERALS {
+        while let Some(stripped) = rest.strip_prefix(symbol) {
+            value += symbol_value;
+            rest = stripped;
+        }
+    }
+    // Only the canonical spelling is accepted, so that `IIII` or `IC` are rejected
+    match rest.is_empty() && to_roman(value).as_deref() == Some(numeral) {
+        true => Some(value),
+        false => None,
+    }
+}
+
+/// Converts a value to a Roman numeral
+/// # Arguments
+///  - value: The value to convert
+/// # Return
+/// An `Option` having the numeral if the value is between 1 and `MAX_ROMAN`, `None` otherwise
+pub fn to_roman(mut value: usize) -> Option<String> {
+    if value == 0 || value > MAX_ROMAN {
+        return None;
+    }
+    let mut numeral = String::new();
+    for (symbol, symbol_value) in NUMERALS {
+        while value >= symbol_value {
+            numeral.push_str(symbol);
+            value -= symbol_value;
+        }
+    }
+    Some(numeral)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::roman::{from_roman, to_roman};
+
+    #[test]
+    fn test_round_trip() {
+        for value in 1..=3999 {
+            let numeral = to_roman(value).unwrap();
+            assert_eq!(Some(value), from_roman(&numeral));
+        }
+        assert_eq!(Some("MCMXCIV".to_string()), to_roman(1994));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(None, to_roman(0));
+        assert_eq!(None, to_roman(4000));
+        assert_eq!(None, from_roman("IIII"));
+        assert_eq!(None, from_roman("IC"));
+        assert_eq!(None, from_roman(""));
+    }
+}
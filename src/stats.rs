@@ -0,0 +1,75 @@
+use std::fmt;
+
+use crate::events::ParseEvent;
+use crate::operation::Operation;
+
+/// Counters describing the work performed by a parse process
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Stats {
+    /// The number of additions applied
+    pub additions: usize,
+    /// The number of subtractions applied
+    pub subtractions: usize,
+    /// The number of multiplications applied
+    pub multiplications: usize,
+    /// The number of divisions applied
+    pub divisions: usize,
+    /// The number of greatest common divisors and least common multiples applied
+    pub divisibility: usize,
+    /// The deepest parenthesis nesting reached
+    pub max_depth: usize,
+    /// The number of operands parsed
+    pub operands: usize,
+}
+
+/// Implementation of the statistics
+impl Stats {
+    /// Compute the statistics from the events of a parse process
+    /// # Arguments
+    ///  - events: The `ParseEvent`s recorded during the parse process
+    /// # Return
+    /// The `Stats` of the parse process
+    pub fn from_events(events: &[ParseEvent]) -> Self {
+        events.iter().fold(Self::default(), |mut stats, event| {
+            match event {
+                ParseEvent::OperandParsed(..) => stats.operands += 1,
+                ParseEvent::GroupOpened(depth) => stats.max_depth = stats.max_depth.max(*depth),
+                ParseEvent::OperationApplied(operation, ..) => match operation {
+                    Operation::Add(_) => stats.additions += 1,
+                    Operation::Sub(_) => stats.subtractions += 1,
+                    Operation::Mul(_) => stats.multiplications += 1,
+                    Operation::Div(_) => stats.divisions += 1,
+                    Operation::Gcd(_) | Operation::Lcm(_) => stats.divisibility += 1,
+                },
+                _ => {}
+            }
+            stats
+        })
+    }
+
+    /// The total number of operations applied
+    pub fn operations(&self) -> usize {
+        self.additions
+            + self.subtractions
+            + self.multiplications
+            + self.divisions
+            + self.divisibility
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "operands: {}", self.operands)?;
+        writeln!(
+            f,
+            "operations: {} (additions: {}, subtractions: {}, multiplications: {}, divisions: {}, gcd/lcm: {})",
+            self.operations(),
+            self.additions,
+            self.subtractions,
+            self.multiplications,
+            self.divisions,
+            self.divisibility
+        )?;
+        write!(f, "max depth: {}", self.max_depth)
+    }
+}
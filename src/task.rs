@@ -0,0 +1,262 @@
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::expr::Expr;
+use crate::operation::OperationError;
+use crate::options::ParserOptions;
+use crate::parser::{ParseError, Parser};
+
+/// The most workers the pool runs, whatever the parallelism available
+const MAX_WORKERS: usize = 8;
+
+/// A job of the worker pool
+type Job = Box<dyn FnOnce() + Send>;
+
+/// The queue of the worker pool shared by all the tasks, started on first use
+static POOL: OnceLock<Mutex<Sender<Job>>> = OnceLock::new();
+
+/// The state shared between a `Task` and the worker running its job
+struct Shared<T> {
+    /// The output, once the job is over
+    output: Option<T>,
+    /// The waker of the last task polling the output
+    waker: Option<Waker>,
+}
+
+/// A job running on the worker pool, whose output is awaited without blocking the executor. The
+/// pool has a bounded number of workers, so that many concurrent tasks queue instead of each
+/// taking a thread. Dropping the task, e.g. when a request times out, cancels its job: a queued
+/// job never runs, and a running parse process stops at its next character.
+pub struct Task<T> {
+    /// The state shared with the worker running the job
+    shared: Arc<Mutex<Shared<T>>>,
+    /// The token cancelling the job
+    cancellation: Arc<AtomicBool>,
+}
+
+/// A parse process running on the worker pool
+pub type ParseTask = Task<Result<usize, ParseError>>;
+
+/// An expression tree evaluated on the worker pool
+pub type EvalTask = Task<Result<usize, OperationError>>;
+
+/// Parse an expression without blocking the caller, for async servers evaluating untrusted input
+/// # Arguments
+///  - expression: The expression to parse
+///  - options: The options tuning the parse process
+/// # Return
+/// The `ParseTask` to await for the result of the parse process
+pub fn parse_async(expression: String, options: ParserOptions) -> ParseTask {
+    submit(move |cancellation| {
+        Parser::new(expression)
+            .with_options(options)
+            .with_cancellation(cancellation)
+            .parse()
+    })
+}
+
+/// Evaluate an expression tree without blocking the caller
+/// # Arguments
+///  - expr: The `Expr` to evaluate
+/// # Return
+/// The `EvalTask` to await for the value of the expression
+pub fn eval_async(expr: Expr) -> EvalTask {
+    submit(move |_| expr.eval())
+}
+
+/// Queue a job on the worker pool, unless its task is dropped before a worker takes it
+/// # Arguments
+///  - job: The job, receiving the token cancelling it
+/// # Return
+/// The `Task` to await for the output of the job
+fn submit<T, F>(job: F) -> Task<T>
+where
+    T: Send + 'static,
+    F: FnOnce(Arc<AtomicBool>) -> T + Send + 'static,
+{
+    let shared = Arc::new(Mutex::new(Shared {
+        output: None,
+        waker: None,
+    }));
+    let cancellation = Arc::new(AtomicBool::new(false));
+    let task = Task {
+        shared: Arc::clone(&shared),
+        cancellation: Arc::clone(&cancellation),
+    };
+    let job: Job = Box::new(move || {
+        if cancellation.load(Ordering::Relaxed) {
+            return;
+        }
+        let output = job(cancellation);
+        let mut shared = shared.lock().unwrap_or_else(|err| err.into_inner());
+        shared.output = Some(output);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    });
+    let pool = POOL.get_or_init(start_pool);
+    // The workers never stop, so the queue always has a receiver
+    let _ = pool.lock().unwrap_or_else(|err| err.into_inner()).send(job);
+    task
+}
+
+/// Start the workers of the pool, one per available core up to `MAX_WORKERS`
+/// # Return
+/// The queue of the jobs, which the workers take in turn
+fn start_pool() -> Mutex<Sender<Job>> {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    for _ in 0..workers.min(MAX_WORKERS) {
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || work(&receiver));
+    }
+    Mutex::new(sender)
+}
+
+/// Run the jobs of the queue, one at a time, surviving the jobs which panic
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = receiver
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .recv();
+        match job {
+            Ok(job) => {
+                let _ = panic::catch_unwind(AssertUnwindSafe(job));
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap_or_else(|err| err.into_inner());
+        match shared.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => {
+                shared.waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for Task<T> {
+    fn drop(&mut self) {
+        self.cancellation.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+    use std::fmt;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::sync::mpsc::{self, Sender};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    use crate::diagnostics::{DiagnosticsSink, Level};
+    use crate::expr::Expr;
+    use crate::operation::OperationError;
+    use crate::options::ParserOptions;
+    use crate::parser::{ParseError, Parser};
+    use crate::task::{eval_async, parse_async, submit};
+
+    /// Wakes the thread blocked on a future
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Run a future to completion on the current thread
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Arc::new(Unpark(thread::current())).into();
+        let mut context = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            match future.as_mut().poll(&mut context) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_async() {
+        let task = parse_async("e3a2fc4".to_string(), ParserOptions::default());
+        assert_eq!(Ok(20), block_on(task));
+        let task = parse_async("3aa2".to_string(), ParserOptions::default());
+        assert_eq!(
+            Err(ParseError::MalformedExpression {
+                position: 2,
+                found: "a".to_string()
+            }),
+            block_on(task)
+        );
+        // More tasks than workers queue on the pool
+        let tasks: Vec<_> = (0..50)
+            .map(|n| parse_async(format!("{}a1", n), ParserOptions::default()))
+            .collect();
+        let results: Vec<_> = tasks.into_iter().map(block_on).collect();
+        assert_eq!((1..=50).map(Ok).collect::<Vec<_>>(), results);
+    }
+
+    #[test]
+    fn test_eval_async() {
+        assert_eq!(Ok(20), block_on(eval_async(Expr::num(3).add(2).mul(4))));
+        assert_eq!(
+            Err(OperationError::OverflowError),
+            block_on(eval_async(Expr::num(1).div(0)))
+        );
+    }
+
+    #[test]
+    fn test_drop_cancels() {
+        /// Signals once that the parse process started
+        struct Started(Sender<()>, Cell<bool>);
+
+        impl DiagnosticsSink for Started {
+            fn enabled(&self, _level: Level) -> bool {
+                !self.1.replace(true)
+            }
+
+            fn diagnostic(&self, _level: Level, _message: fmt::Arguments) {
+                let _ = self.0.send(());
+            }
+        }
+
+        let (started, running) = mpsc::channel();
+        let (finished, outcome) = mpsc::channel();
+        let task = submit(move |cancellation| {
+            let expression = format!("{}3", "3a".repeat(2_000_000));
+            let result = Parser::new(expression)
+                .with_diagnostics(Started(started, Cell::new(false)))
+                .with_cancellation(cancellation)
+                .parse();
+            let _ = finished.send(result);
+        });
+        running.recv().unwrap();
+        drop(task);
+        assert!(matches!(
+            outcome.recv().unwrap(),
+            Err(ParseError::Cancelled { .. })
+        ));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::events::ParseEvent;
+use crate::operation::codes::*;
+use crate::parser::ParserState;
+
+/// Narrate a parse process in plain language, one line per character read, for classrooms
+/// introducing parsers (e.g. `read digit 3 → first operand`, `read 'a' → addition pending`).
+/// The operations applied and the groups closed follow the character completing them.
+/// # Arguments
+///  - events: The `ParseEvent`s recorded during the parse process
+/// # Return
+/// The narration
+pub fn narrate(events: &[ParseEvent]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut state = ParserState::FirstOperand;
+    // The line reading the last character, completed with the outcome of reading it
+    let mut reading: Option<(usize, char)> = None;
+    for event in events {
+        match *event {
+            ParseEvent::Consumed(position, char) => {
+                complete(&mut lines, reading, state);
+                let read = match char.is_ascii_digit() {
+                    true => format!("digit {}", char),
+                    false => format!("'{}'", char),
+                };
+                lines.push(format!("{}: read {}", position, read));
+                reading = Some((lines.len() - 1, char));
+            }
+            ParseEvent::StateChanged(_, to) => state = to,
+            ParseEvent::OperandParsed(_, value) => {
+                lines.push(format!("   operand {} complete", value))
+            }
+            ParseEvent::OperationApplied(operation, second_operand, result) => {
+                lines.push(format!("   {} {} = {}", operation, second_operand, result))
+            }
+            ParseEvent::GroupOpened(_) => {}
+            ParseEvent::GroupClosed(depth, result) => {
+                lines.push(format!("   the group at depth {} equals {}", depth, result))
+            }
+        }
+    }
+    complete(&mut lines, reading, state);
+    lines.join("\n")
+}
+
+/// Complete the line reading the last character with the outcome of reading it
+fn complete(lines: &mut [String], reading: Option<(usize, char)>, state: ParserState) {
+    if let Some((line, char)) = reading {
+        lines[line].push_str(&format!(" → {}", outcome(char, state)));
+    }
+}
+
+/// What reading a character led to, given the state of the parser after reading it
+fn outcome(char: char, state: ParserState) -> String {
+    match char {
+        OPCODE_ADD => "addition pending".to_string(),
+        OPCODE_SUB => "subtraction pending".to_string(),
+        OPCODE_MUL => "multiplication pending".to_string(),
+        OPCODE_DIV => "division pending".to_string(),
+        OPCODE_GCD => "greatest common divisor pending".to_string(),
+        OPCODE_LCM => "least common multiple pending".to_string(),
+        OPCODE_OPEN => "group opened".to_string(),
+        OPCODE_CLOSE => "group closed".to_string(),
+        _ => state.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::Parser;
+    use crate::teach::narrate;
+
+    #[test]
+    fn test_narrate() {
+        let (_, events) = Parser::new("12ae3c2f".to_string()).parse_with_events();
+        assert_eq!(
+            "0: read digit 1 → first operand
+1: read digit 2 → first operand
+   operand 12 complete
+2: read 'a' → addition pending
+3: read 'e' → group opened
+4: read digit 3 → operation
+   operand 3 complete
+5: read 'c' → multiplication pending
+6: read digit 2 → second operand
+   operand 2 complete
+   3 * 2 = 6
+7: read 'f' → group closed
+   the group at depth 1 equals 6
+   12 + 6 = 18",
+            narrate(&events)
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::parser::ParseError;
+
+/// The phases of a parse process
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    /// Radix literals, Roman numerals and cell references are expanded into decimal operands
+    Lex,
+    /// The trailing input and the parenthesis balance are checked
+    Parse,
+    /// The state machine evaluates the expression
+    Eval,
+}
+
+/// Hooks notified by the `Parser` about its activity, so that embedders can feed their metrics systems.
+/// Every hook does nothing by default.
+pub trait Telemetry {
+    /// A parse process started
+    /// # Arguments
+    ///  - expression: The expression being parsed
+    fn parse_started(&self, _expression: &str) {}
+
+    /// A parse process finished successfully
+    /// # Arguments
+    ///  - result: The result of the expression
+    ///  - duration: The time taken by the parse process
+    fn parse_finished(&self, _result: usize, _duration: Duration) {}
+
+    /// A parse process failed
+    /// # Arguments
+    ///  - error: The error raised
+    ///  - duration: The time taken by the parse process until the error
+    fn error_raised(&self, _error: &ParseError, _duration: Duration) {}
+
+    /// A phase of the parse process finished successfully
+    /// # Arguments
+    ///  - phase: The `Phase` finished
+    ///  - duration: The time taken by the phase
+    fn phase_finished(&self, _phase: Phase, _duration: Duration) {}
+}
+
+/// The durations of parse processes accumulated by phase, so that batches can be profiled as a whole
+#[derive(Debug, Default)]
+pub struct Timings {
+    /// The number of parse processes
+    parses: Cell<usize>,
+    /// The time spent in `Phase::Lex`
+    lex: Cell<Duration>,
+    /// The time spent in `Phase::Parse`
+    parse: Cell<Duration>,
+    /// The time spent in `Phase::Eval`
+    eval: Cell<Duration>,
+    /// The time spent in the parse processes, including the failed phases
+    total: Cell<Duration>,
+}
+
+/// Shared timings, accumulating the durations of every parser they are passed to
+impl Telemetry for Rc<Timings> {
+    fn parse_finished(&self, _result: usize, duration: Duration) {
+        self.parses.set(self.parses.get() + 1);
+        self.total.set(self.total.get() + duration);
+    }
+
+    fn error_raised(&self, _error: &ParseError, duration: Duration) {
+        self.parses.set(self.parses.get() + 1);
+        self.total.set(self.total.get() + duration);
+    }
+
+    fn phase_finished(&self, phase: Phase, duration: Duration) {
+        let timing = match phase {
+            Phase::Lex => &self.lex,
+            Phase::Parse => &self.parse,
+            Phase::Eval => &self.eval,
+        };
+        timing.set(timing.get() + duration);
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "parses: {}, lex: {:?}, parse: {:?}, eval: {:?}, total: {:?}",
+            self.parses.get(),
+            self.lex.get(),
+            self.parse.get(),
+            self.eval.get(),
+            self.total.get()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use crate::parser::Parser;
+    use crate::telemetry::Timings;
+
+    #[test]
+    fn test_timings() {
+        let timings = Rc::new(Timings::default());
+        for expression in ["3a2", "ec4f", "3aa2"] {
+            let parser = Parser::new(expression.to_string()).with_telemetry(Rc::clone(&timings));
+            let _ = parser.parse();
+        }
+        assert_eq!(3, timings.parses.get());
+        assert!(
+            timings.total.get() >= timings.lex.get() + timings.parse.get() + timings.eval.get()
+        );
+        assert!(timings.to_string().starts_with("parses: 3, lex: "));
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+
+use crate::options::ParserOptions;
+use crate::parser::{ParseError, Parser};
+
+/// The delimiter opening an expression in a template
+const OPEN: &str = "{{";
+/// The delimiter closing an expression in a template
+const CLOSE: &str = "}}";
+
+/// Errors that the template rendering can cause
+#[derive(Debug, PartialEq)]
+pub enum TemplateError {
+    /// An expression is opened but never closed (byte offset of the opening delimiter)
+    Unclosed(usize),
+    /// An expression failed to evaluate (expression, `ParseError` for further information)
+    Expression(String, ParseError),
+    /// The result of an expression cannot be rendered (expression, result)
+    UnrepresentableResult(String, usize),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unclosed(offset) => write!(f, "Unclosed({})", offset),
+            Self::Expression(expression, err) => {
+                write!(
+                    f,
+                    "Expression({:?}, {}, {:?})",
+                    expression,
+                    err.error_code(),
+                    err
+                )
+            }
+            Self::UnrepresentableResult(expression, result) => {
+                write!(f, "UnrepresentableResult({:?}, {})", expression, result)
+            }
+        }
+    }
+}
+
+/// Expand every `{{ expression }}` found in a text with the result of the expression.
+/// Whitespace surrounding the expression inside the delimiters is ignored.
+/// # Arguments
+///  - text: The template text
+///  - options: The options tuning the parser
+///  - render: Renders a result as text, `None` if it cannot be represented
+/// # Return
+/// A `Result` having the expanded text if all the expressions are valid, `TemplateError` otherwise
+pub fn render(
+    text: &str,
+    options: &ParserOptions,
+    render: impl Fn(usize) -> Option<String>,
+) -> Result<String, TemplateError> {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(OPEN) {
+        rendered.push_str(&rest[..start]);
+        let offset = text.len() - rest.len() + start;
+        let inner = &rest[start + OPEN.len()..];
+        let end = inner.find(CLOSE).ok_or(TemplateError::Unclosed(offset))?;
+        let expression = inner[..end].trim().to_string();
+        let result = Parser::new(expression.clone())
+            .with_options(options.clone())
+            .parse()
+            .map_err(|err| TemplateError::Expression(expression.clone(), err))?;
+        let result =
+            render(result).ok_or(TemplateError::UnrepresentableResult(expression, result))?;
+        rendered.push_str(&result);
+        rest = &inner[end + CLOSE.len()..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::options::ParserOptions;
+    use crate::parser::ParseError::MalformedExpression;
+    use crate::template::{render, TemplateError};
+
+    #[test]
+    fn test_render() {
+        let options = ParserOptions::default();
+        let text = "Total: {{ 3a2c4 }} items, {{e2f}} boxes {no expression}";
+        let result = render(text, &options, |result| Some(result.to_string()));
+        assert_eq!(
+            Ok("Total: 20 items, 2 boxes {no expression}".to_string()),
+            result
+        );
+
+        let result = render("{{3aa2}}", &options, |result| Some(result.to_string()));
+        assert_eq!(
+            Err(TemplateError::Expression(
+                "3aa2".to_string(),
+                MalformedExpression {
+                    position: 2,
+                    found: "a".to_string()
+                }
+            )),
+            result
+        );
+
+        let result = render("ok {{3a2", &options, |result| Some(result.to_string()));
+        assert_eq!(Err(TemplateError::Unclosed(3)), result);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::expr::Expr;
+use crate::operation::codes::*;
+
+/// The operation codes the generated expressions use
+const OPERATIONS: [char; 6] = [
+    OPCODE_ADD, OPCODE_SUB, OPCODE_MUL, OPCODE_DIV, OPCODE_GCD, OPCODE_LCM,
+];
+
+/// Generates arbitrary valid `Expr` values, so that downstream crates can check properties of their
+/// extensions (e.g. that `to_code` followed by `parse_tolerant` gives the expression back) over many
+/// more expressions than they would write by hand. The same seed generates the same expressions.
+#[derive(Debug, Clone)]
+pub struct ExprStrategy {
+    /// The state of the SplitMix64 generator, advanced on every draw
+    state: u64,
+    /// The deepest nesting of operations generated
+    max_depth: usize,
+    /// The largest operand generated
+    max_operand: usize,
+}
+
+/// Implementation of the expression generator
+impl ExprStrategy {
+    /// Creates a generator of expressions nesting up to 4 operations, with operands up to 1000
+    /// # Arguments
+    ///  - seed: The seed of the generator
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+            max_depth: 4,
+            max_operand: 1000,
+        }
+    }
+
+    /// Bound the nesting of the generated operations
+    /// # Arguments
+    ///  - max_depth: The deepest nesting of operations, `0` generating only numbers
+    /// # Return
+    /// The `ExprStrategy` nesting up to the given depth
+    pub fn with_max_depth(self, max_depth: usize) -> Self {
+        Self { max_depth, ..self }
+    }
+
+    /// Bound the generated operands
+    /// # Arguments
+    ///  - max_operand: The largest operand
+    /// # Return
+    /// The `ExprStrategy` generating operands up to the given value
+    pub fn with_max_operand(self, max_operand: usize) -> Self {
+        Self {
+            max_operand,
+            ..self
+        }
+    }
+
+    /// Generate the next expression
+    pub fn generate(&mut self) -> Expr {
+        self.expr(self.max_depth)
+    }
+
+    /// Generate an expression nesting up to the given depth, a number once it is reached
+    fn expr(&mut self, depth: usize) -> Expr {
+        // Numbers are as likely as operations, so that trees stay small on average
+        if depth == 0 || self.draw(2) == 0 {
+            let value = self.draw(self.max_operand.wrapping_add(1));
+            return Expr::num(value);
+        }
+        let code = OPERATIONS[self.draw(OPERATIONS.len())];
+        let first = self.expr(depth - 1);
+        let second = self.expr(depth - 1);
+        Expr::Binary(code, Box::new(first), Box::new(second))
+    }
+
+    /// Draw a number below a bound, or any number if the bound is `0` (e.g. `usize::MAX + 1`)
+    fn draw(&mut self, bound: usize) -> usize {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut mixed = (self.state ^ (self.state >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        let mixed = (mixed ^ (mixed >> 31)) as usize;
+        match bound {
+            0 => mixed,
+            bound => mixed % bound,
+        }
+    }
+}
+
+impl Iterator for ExprStrategy {
+    type Item = Expr;
+
+    fn next(&mut self) -> Option<Expr> {
+        Some(self.generate())
+    }
+}
+
+/// Check a property over generated expressions
+/// # Arguments
+///  - strategy: The `ExprStrategy` generating the expressions
+///  - cases: The number of expressions to check
+///  - property: The property every expression must have
+/// # Return
+/// A `Result` which is `Ok` if all the expressions have the property, or has the first one which
+/// does not otherwise
+pub fn check<P>(strategy: ExprStrategy, cases: usize, property: P) -> Result<(), Expr>
+where
+    P: Fn(&Expr) -> bool,
+{
+    match strategy.take(cases).find(|expr| !property(expr)) {
+        Some(expr) => Err(expr),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::expr::Expr;
+    use crate::parser::Parser;
+    use crate::testing::{check, ExprStrategy};
+
+    #[test]
+    fn test_generate() {
+        let exprs: Vec<Expr> = ExprStrategy::new(7).take(50).collect();
+        assert_eq!(exprs, ExprStrategy::new(7).take(50).collect::<Vec<Expr>>());
+        assert_ne!(exprs, ExprStrategy::new(8).take(50).collect::<Vec<Expr>>());
+        assert!(exprs.iter().any(|expr| matches!(expr, Expr::Binary(..))));
+        assert!(exprs.iter().all(|expr| expr.errors().is_empty()));
+        let mut numbers = ExprStrategy::new(7).with_max_depth(0).with_max_operand(9);
+        assert!((0..50).all(|_| matches!(numbers.generate(), Expr::Num(value) if value <= 9)));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let strategy = ExprStrategy::new(42);
+        assert_eq!(
+            Ok(()),
+            check(strategy.clone(), 500, |expr| {
+                Expr::parse_tolerant(&expr.to_code()) == *expr
+            })
+        );
+        assert_eq!(
+            Ok(()),
+            check(strategy.clone(), 500, |expr| {
+                expr.eval().ok() == Parser::new(expr.to_code()).parse().ok()
+            })
+        );
+        assert!(check(strategy, 500, |expr| expr.eval().is_ok()).is_err());
+    }
+}
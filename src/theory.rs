@@ -0,0 +1,179 @@
+/// The largest number that is factored by trial division, which takes up to a million steps
+pub const MAX_FACTORED: usize = 1_000_000_000_000;
+
+/// The bases for which the Miller-Rabin test is exact for every 64 bit number
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Check whether a number is prime, in logarithmic time whatever its size
+/// # Arguments
+///  - number: The number to check
+/// # Return
+/// Whether the number is prime
+pub fn is_prime(number: usize) -> bool {
+    let number = number as u64;
+    if number < 2 {
+        return false;
+    }
+    if let Some(prime) = WITNESSES
+        .iter()
+        .find(|prime| number.is_multiple_of(**prime))
+    {
+        return number == *prime;
+    }
+    // number - 1 = odd * 2^twos
+    let twos = (number - 1).trailing_zeros();
+    let odd = (number - 1) >> twos;
+    WITNESSES.iter().all(|witness| {
+        let mut power = pow_mod(*witness, odd, number);
+        if power == 1 || power == number - 1 {
+            return true;
+        }
+        (1..twos).any(|_| {
+            power = mul_mod(power, power, number);
+            power == number - 1
+        })
+    })
+}
+
+/// The prime factors of a number, by increasing value and repeated by multiplicity
+/// # Arguments
+///  - number: The number to factor, up to `MAX_FACTORED`
+/// # Return
+/// An `Option` having the factors, none for 0 and 1, or `None` if the number is too large
+pub fn factors(number: usize) -> Option<Vec<usize>> {
+    if number > MAX_FACTORED {
+        return None;
+    }
+    let mut factors = Vec::new();
+    let mut rest = number;
+    let mut divisor = 2;
+    while rest > 1 && divisor * divisor <= rest {
+        while rest.is_multiple_of(divisor) {
+            factors.push(divisor);
+            rest /= divisor;
+        }
+        divisor += if divisor == 2 { 1 } else { 2 };
+    }
+    if rest > 1 {
+        factors.push(rest);
+    }
+    Some(factors)
+}
+
+/// Euler's totient of a number, the count of the numbers up to it which are coprime with it
+/// # Arguments
+///  - number: The number, up to `MAX_FACTORED`
+/// # Return
+/// An `Option` having the totient, 0 for 0, or `None` if the number is too large
+pub fn totient(number: usize) -> Option<usize> {
+    let mut factors = factors(number)?;
+    factors.dedup();
+    Some(
+        factors
+            .iter()
+            .fold(number, |totient, factor| totient / factor * (factor - 1)),
+    )
+}
+
+/// The number of ways to choose items from a set, regardless of their order
+/// # Arguments
+///  - n: The size of the set
+///  - k: The number of items chosen
+/// # Return
+/// An `Option` having the binomial coefficient, 0 if `k > n`, or `None` if it overflows
+pub fn choose(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+    // Every partial product is a smaller binomial coefficient, which fits when the result does
+    let k = k.min(n - k);
+    let mut result: u128 = 1;
+    for i in 0..k {
+        result = result * (n - i) as u128 / (i + 1) as u128;
+        if result > usize::MAX as u128 {
+            return None;
+        }
+    }
+    Some(result as usize)
+}
+
+/// The number of ways to arrange items chosen from a set
+/// # Arguments
+///  - n: The size of the set
+///  - k: The number of items arranged
+/// # Return
+/// An `Option` having the count of permutations, 0 if `k > n`, or `None` if it overflows
+pub fn perm(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+    (n - k + 1..=n).try_fold(1usize, |result, factor| result.checked_mul(factor))
+}
+
+/// Multiply two numbers modulo another, without overflowing
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    (a as u128 * b as u128 % modulus as u128) as u64
+}
+
+/// Raise a number to a power modulo another, by repeated squaring
+fn pow_mod(base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1;
+    let mut base = base % modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::theory::{choose, factors, is_prime, perm, totient, MAX_FACTORED};
+
+    #[test]
+    fn test_is_prime() {
+        let primes: Vec<usize> = (0..50).filter(|number| is_prime(*number)).collect();
+        assert_eq!(
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47],
+            primes
+        );
+        assert!(is_prime(1_000_000_007));
+        assert!(!is_prime(1_000_000_007 * 998_244_353));
+        // Strong pseudoprime to the bases up to 23
+        assert!(!is_prime(3_825_123_056_546_413_051));
+        assert!(is_prime(18_446_744_073_709_551_557));
+    }
+
+    #[test]
+    fn test_factors() {
+        assert_eq!(Some(vec![2, 2, 3, 7]), factors(84));
+        assert_eq!(Some(vec![]), factors(1));
+        assert_eq!(Some(vec![999_983, 1_000_003]), factors(999_983 * 1_000_003));
+        assert_eq!(None, factors(MAX_FACTORED + 1));
+    }
+
+    #[test]
+    fn test_totient() {
+        assert_eq!(Some(24), totient(84));
+        assert_eq!(Some(1), totient(1));
+        assert_eq!(Some(0), totient(0));
+        assert_eq!(Some(12), totient(13));
+    }
+
+    #[test]
+    fn test_combinatorics() {
+        assert_eq!(Some(10), choose(5, 2));
+        assert_eq!(Some(1), choose(5, 0));
+        assert_eq!(Some(0), choose(2, 5));
+        assert_eq!(Some(14_226_520_737_620_288_370), choose(67, 33));
+        assert_eq!(None, choose(68, 34));
+        assert_eq!(Some(20), perm(5, 2));
+        assert_eq!(Some(120), perm(5, 5));
+        assert_eq!(Some(0), perm(2, 5));
+        assert_eq!(Some(2_432_902_008_176_640_000), perm(20, 20));
+        assert_eq!(None, perm(21, 21));
+    }
+}
@@ -0,0 +1,141 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::history::History;
+
+/// How often a watched file is checked for changes
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The escape sequences highlighting errors on a terminal
+const HIGHLIGHT: (&str, &str) = ("\x1b[31m", "\x1b[0m");
+
+/// A file watched for changes, by polling its modification time and size
+#[derive(Debug)]
+pub struct Watcher {
+    /// The path of the file
+    path: PathBuf,
+    /// The modification time and size of the file when it was last read
+    version: Option<(SystemTime, u64)>,
+}
+
+/// Implementation of the file watcher
+impl Watcher {
+    /// Creates a watcher of a file, which reports the file as changed on the first poll
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            version: None,
+        }
+    }
+
+    /// Check whether the file changed since the last poll. A missing file counts as unchanged,
+    /// since editors may delete it for a moment when saving.
+    /// # Return
+    /// A `Result` having the text of the file if it changed, `None` otherwise
+    pub fn poll(&mut self) -> io::Result<Option<String>> {
+        let metadata = match fs::metadata(&self.path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            metadata => metadata?,
+        };
+        let version = Some((metadata.modified()?, metadata.len()));
+        if version == self.version {
+            return Ok(None);
+        }
+        self.version = version;
+        fs::read_to_string(&self.path).map(Some)
+    }
+}
+
+/// Evaluate every expression of a formula file, one per line, reporting the results. Blank lines
+/// and lines starting with `#` are skipped, and an expression can reference the result of an
+/// earlier line by its number (e.g. `$2`).
+/// # Arguments
+///  - text: The formula file
+///  - evaluate: Evaluates an expression into the result and its rendering, or the error message
+///  - highlight: Whether the errors are highlighted, for terminals
+/// # Return
+/// The report, one line per expression, numbered as in the file
+pub fn report<F>(text: &str, evaluate: F, highlight: bool) -> String
+where
+    F: Fn(&str) -> Result<(usize, String), String>,
+{
+    let (start, end) = if highlight { HIGHLIGHT } else { ("", "") };
+    let mut history = History::default();
+    let mut report = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            history.push(None);
+            continue;
+        }
+        let evaluated = history
+            .resolve(line)
+            .map_err(|err| err.to_string())
+            .and_then(|expression| evaluate(&expression));
+        history.push(evaluated.as_ref().ok().map(|(result, _)| *result));
+        report.push(match evaluated {
+            Ok((_, rendered)) => format!("{}: {} = {}", index + 1, line, rendered),
+            Err(err) => format!("{}{}: {} ! {}{}", start, index + 1, line, err, end),
+        });
+    }
+    report.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+
+    use crate::parser::Parser;
+    use crate::watch::{report, Watcher};
+
+    fn evaluate(expression: &str) -> Result<(usize, String), String> {
+        Parser::new(expression.to_string())
+            .parse()
+            .map(|result| (result, result.to_string()))
+            .map_err(|err| err.error_code().to_string())
+    }
+
+    #[test]
+    fn test_report() {
+        let text = "# Budget\n3a2\n\n  e3a2fc4\n3aa2\n";
+        assert_eq!(
+            "2: 3a2 = 5\n4: e3a2fc4 = 20\n5: 3aa2 ! E003",
+            report(text, evaluate, false)
+        );
+        assert_eq!(
+            "\x1b[31m1: 3aa2 ! E003\x1b[0m",
+            report("3aa2", evaluate, true)
+        );
+    }
+
+    #[test]
+    fn test_history() {
+        let text = "3a2\n# Doubled\n$1c2\n3aa2\n$3a$1\n$4a1\n$6";
+        assert_eq!(
+            "1: 3a2 = 5\n3: $1c2 = 10\n4: 3aa2 ! E003\n5: $3a$1 = 15\n6: $4a1 ! Failed(4)\n7: $6 ! Failed(6)",
+            report(text, evaluate, false)
+        );
+    }
+
+    #[test]
+    fn test_poll() {
+        let path = std::env::temp_dir().join(format!("watch-{}.txt", std::process::id()));
+        let mut watcher = Watcher::new(&path);
+        assert_eq!(None, watcher.poll().unwrap());
+        fs::write(&path, "3a2").unwrap();
+        assert_eq!(Some("3a2".to_string()), watcher.poll().unwrap());
+        assert_eq!(None, watcher.poll().unwrap());
+        // A save within the resolution of the modification time is told apart by the size
+        fs::write(&path, "3a20").unwrap();
+        assert_eq!(Some("3a20".to_string()), watcher.poll().unwrap());
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(Some("3a20".to_string()), watcher.poll().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(None, watcher.poll().unwrap());
+    }
+}
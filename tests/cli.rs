@@ -0,0 +1,51 @@
+use std::process::{Command, Output};
+
+/// Run the binary with the arguments passed
+fn run(arguments: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_arithmetic-parser"))
+        .args(arguments)
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_leading_zeros_warning() {
+    let output = run(&["--leading-zeros", "warn", "3a007"]);
+    assert!(output.status.success());
+    assert_eq!("10\n", String::from_utf8_lossy(&output.stdout));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Warning:"), "{}", stderr);
+    assert!(stderr.contains("007"), "{}", stderr);
+    // Accepted silently by default
+    let output = run(&["3a007"]);
+    assert_eq!("10\n", String::from_utf8_lossy(&output.stdout));
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_redundant_parentheses_notice() {
+    let output = run(&["--warn-parentheses", "ee3a2ff"]);
+    assert_eq!("5\n", String::from_utf8_lossy(&output.stdout));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Warning: redundant parentheses at 0..7"), "{}", stderr);
+}
+
+#[test]
+fn test_lenient_trailing_input_warning() {
+    let output = run(&["--trailing", "lenient", "3a2fa1"]);
+    assert_eq!("5\n", String::from_utf8_lossy(&output.stdout));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Warning: ignoring trailing input after 3"), "{}", stderr);
+}
+
+#[test]
+fn test_signed() {
+    let output = run(&["--signed", "8", "--bits", "bin", "3b5"]);
+    assert_eq!("-2 = 0b1111_1110\n", String::from_utf8_lossy(&output.stdout));
+    let output = run(&["--signed", "8", "--wrapping", "0b127b1de0b1f"]);
+    assert_eq!("-128\n", String::from_utf8_lossy(&output.stdout));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Checked: E104"), "{}", stderr);
+    assert!(stderr.contains("from -128 past 127"), "{}", stderr);
+}